@@ -8,10 +8,10 @@ ALTER TABLE companies ADD COLUMN business_type TEXT;
 ALTER TABLE companies ADD COLUMN business_item TEXT;
 ALTER TABLE companies ADD COLUMN default_invoice_memo TEXT;
 
--- customers 테이블에 business_type, business_item, outstanding_balance 추가
+-- customers 테이블에 business_type, business_item, is_active 추가
+-- (outstanding_balance 는 002에서 이미 추가됨)
 ALTER TABLE customers ADD COLUMN business_type TEXT;
 ALTER TABLE customers ADD COLUMN business_item TEXT;
-ALTER TABLE customers ADD COLUMN outstanding_balance DECIMAL(12,2) DEFAULT 0;
 ALTER TABLE customers ADD COLUMN is_active BOOLEAN DEFAULT 1;
 
 -- products 테이블에 추가 컬럼
@@ -0,0 +1,239 @@
+-- Rust 모델(models.rs)과 스키마를 하나로 맞추는 마이그레이션
+-- 모든 핵심 테이블을 합의된 스키마로 재생성합니다.
+-- - 금액/수량 컬럼은 REAL (DECIMAL 선언 시 정수값이 INTEGER로 저장되어 f64 디코딩 실패)
+-- - 거래 상태는 draft/confirmed/cancelled 로 통일 (pending -> draft, completed -> confirmed)
+-- - 날짜 컬럼은 DATETIME (YYYY-MM-DD 형식은 자정 UTC로 변환)
+-- - customers.type (006) 은 customer_type 과 중복이므로 제거
+-- 테이블 재생성 중에는 마이그레이션 실행기가 외래 키 검사를 비활성화합니다.
+
+-- 1. companies
+CREATE TABLE companies_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    business_number TEXT UNIQUE NOT NULL,
+    representative TEXT NOT NULL,
+    address TEXT NOT NULL,
+    phone TEXT,
+    email TEXT,
+    business_type TEXT,
+    business_item TEXT,
+    default_invoice_memo TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO companies_new (
+    id, name, business_number, representative, address, phone, email,
+    business_type, business_item, default_invoice_memo, created_at, updated_at
+)
+SELECT
+    id, name, business_number, representative, address, phone, email,
+    business_type, business_item, default_invoice_memo,
+    COALESCE(created_at, CURRENT_TIMESTAMP), COALESCE(updated_at, CURRENT_TIMESTAMP)
+FROM companies;
+
+DROP TABLE companies;
+ALTER TABLE companies_new RENAME TO companies;
+
+-- 2. customers
+CREATE TABLE customers_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    business_number TEXT,
+    representative TEXT,
+    address TEXT,
+    phone TEXT,
+    email TEXT,
+    business_type TEXT,
+    business_item TEXT,
+    contact_person TEXT,
+    customer_type TEXT NOT NULL CHECK(customer_type IN ('customer', 'supplier')) DEFAULT 'customer',
+    outstanding_balance REAL NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT 1,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO customers_new (
+    id, name, business_number, representative, address, phone, email,
+    business_type, business_item, contact_person, customer_type,
+    outstanding_balance, is_active, created_at, updated_at
+)
+SELECT
+    id, name, business_number, representative, address, phone, email,
+    business_type, business_item, contact_person,
+    CASE WHEN customer_type = 'supplier' THEN 'supplier' ELSE 'customer' END,
+    CAST(COALESCE(outstanding_balance, 0) AS REAL), COALESCE(is_active, 1),
+    COALESCE(created_at, CURRENT_TIMESTAMP), COALESCE(updated_at, CURRENT_TIMESTAMP)
+FROM customers;
+
+DROP TABLE customers;
+ALTER TABLE customers_new RENAME TO customers;
+
+-- 3. products
+CREATE TABLE products_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    code TEXT UNIQUE,
+    description TEXT,
+    category TEXT,
+    unit TEXT NOT NULL DEFAULT '개',
+    unit_price REAL NOT NULL DEFAULT 0,
+    tax_rate REAL NOT NULL DEFAULT 0.1,
+    traceability_number TEXT,
+    origin TEXT,
+    slaughterhouse TEXT,
+    use_inventory_management BOOLEAN NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT 1,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO products_new (
+    id, name, code, description, category, unit, unit_price, tax_rate,
+    traceability_number, origin, slaughterhouse, use_inventory_management,
+    is_active, created_at, updated_at
+)
+SELECT
+    id, name, code, description, category, COALESCE(unit, '개'),
+    CAST(COALESCE(unit_price, 0) AS REAL), 0.1,
+    traceability_number, origin, slaughterhouse, COALESCE(use_inventory_management, 0),
+    COALESCE(is_active, 1),
+    COALESCE(created_at, CURRENT_TIMESTAMP), COALESCE(updated_at, CURRENT_TIMESTAMP)
+FROM products;
+
+DROP TABLE products;
+ALTER TABLE products_new RENAME TO products;
+
+-- 4. transactions
+CREATE TABLE transactions_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_number TEXT UNIQUE NOT NULL,
+    customer_id INTEGER NOT NULL,
+    transaction_type TEXT NOT NULL CHECK(transaction_type IN ('sale', 'purchase', 'payment_in', 'payment_out')),
+    transaction_date DATETIME NOT NULL,
+    subtotal REAL NOT NULL DEFAULT 0,
+    tax_amount REAL NOT NULL DEFAULT 0,
+    total_amount REAL NOT NULL DEFAULT 0,
+    status TEXT NOT NULL CHECK(status IN ('draft', 'confirmed', 'cancelled')) DEFAULT 'draft',
+    notes TEXT,
+    reference_payment_id INTEGER,
+    current_balance REAL NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (customer_id) REFERENCES customers(id),
+    FOREIGN KEY (reference_payment_id) REFERENCES transactions(id)
+);
+
+INSERT INTO transactions_new (
+    id, transaction_number, customer_id, transaction_type, transaction_date,
+    subtotal, tax_amount, total_amount, status, notes, reference_payment_id,
+    current_balance, created_at, updated_at
+)
+SELECT
+    id, transaction_number, customer_id, transaction_type,
+    CASE
+        WHEN length(transaction_date) = 10 THEN transaction_date || 'T00:00:00+00:00'
+        ELSE transaction_date
+    END,
+    CAST(subtotal AS REAL), CAST(tax_amount AS REAL), CAST(total_amount AS REAL),
+    CASE status
+        WHEN 'pending' THEN 'draft'
+        WHEN 'completed' THEN 'confirmed'
+        ELSE COALESCE(status, 'draft')
+    END,
+    notes, reference_payment_id, CAST(COALESCE(current_balance, 0) AS REAL),
+    COALESCE(created_at, CURRENT_TIMESTAMP), COALESCE(updated_at, CURRENT_TIMESTAMP)
+FROM transactions;
+
+DROP TABLE transactions;
+ALTER TABLE transactions_new RENAME TO transactions;
+
+-- 5. transaction_items
+CREATE TABLE transaction_items_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    quantity REAL NOT NULL,
+    unit_price REAL NOT NULL,
+    tax_rate REAL NOT NULL DEFAULT 0,
+    subtotal REAL NOT NULL,
+    tax_amount REAL NOT NULL DEFAULT 0,
+    total_amount REAL NOT NULL,
+    traceability_number TEXT,
+    origin TEXT,
+    slaughterhouse TEXT,
+    notes TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+INSERT INTO transaction_items_new (
+    id, transaction_id, product_id, quantity, unit_price, tax_rate,
+    subtotal, tax_amount, total_amount, traceability_number, origin,
+    slaughterhouse, notes, created_at
+)
+SELECT
+    id, transaction_id, product_id, CAST(quantity AS REAL), CAST(unit_price AS REAL), 0,
+    CAST(subtotal AS REAL), 0, CAST(subtotal AS REAL), traceability_number, origin,
+    slaughterhouse, notes, COALESCE(created_at, CURRENT_TIMESTAMP)
+FROM transaction_items;
+
+DROP TABLE transaction_items;
+ALTER TABLE transaction_items_new RENAME TO transaction_items;
+
+-- 6. tax_invoices (공급자/공급받는자 정보를 발행 시점 기준으로 보관)
+CREATE TABLE tax_invoices_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_id INTEGER NOT NULL,
+    invoice_number TEXT UNIQUE NOT NULL,
+    issue_date DATETIME NOT NULL,
+    supplier_business_number TEXT NOT NULL,
+    supplier_name TEXT NOT NULL,
+    buyer_business_number TEXT NOT NULL,
+    buyer_name TEXT NOT NULL,
+    supply_amount REAL NOT NULL,
+    tax_amount REAL NOT NULL,
+    total_amount REAL NOT NULL,
+    status TEXT NOT NULL CHECK(status IN ('issued', 'sent', 'received')) DEFAULT 'issued',
+    pdf_path TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id)
+);
+
+INSERT INTO tax_invoices_new (
+    id, transaction_id, invoice_number, issue_date,
+    supplier_business_number, supplier_name, buyer_business_number, buyer_name,
+    supply_amount, tax_amount, total_amount, status, pdf_path, created_at, updated_at
+)
+SELECT
+    ti.id, ti.transaction_id, ti.invoice_number,
+    CASE
+        WHEN length(ti.issue_date) = 10 THEN ti.issue_date || 'T00:00:00+00:00'
+        ELSE ti.issue_date
+    END,
+    COALESCE((SELECT business_number FROM companies ORDER BY id LIMIT 1), ''),
+    COALESCE((SELECT name FROM companies ORDER BY id LIMIT 1), ''),
+    COALESCE(c.business_number, ''), COALESCE(c.name, ''),
+    CAST(ti.supply_amount AS REAL), CAST(ti.tax_amount AS REAL), CAST(ti.total_amount AS REAL),
+    COALESCE(ti.status, 'issued'), ti.pdf_path,
+    COALESCE(ti.created_at, CURRENT_TIMESTAMP), COALESCE(ti.updated_at, CURRENT_TIMESTAMP)
+FROM tax_invoices ti
+LEFT JOIN transactions t ON ti.transaction_id = t.id
+LEFT JOIN customers c ON t.customer_id = c.id;
+
+DROP TABLE tax_invoices;
+ALTER TABLE tax_invoices_new RENAME TO tax_invoices;
+
+-- 7. 인덱스 재생성
+CREATE INDEX IF NOT EXISTS idx_customers_business_number ON customers(business_number);
+CREATE INDEX IF NOT EXISTS idx_products_code ON products(code);
+CREATE INDEX IF NOT EXISTS idx_transactions_date ON transactions(transaction_date);
+CREATE INDEX IF NOT EXISTS idx_transactions_customer ON transactions(customer_id);
+CREATE INDEX IF NOT EXISTS idx_transactions_reference_payment ON transactions(reference_payment_id);
+CREATE INDEX IF NOT EXISTS idx_transactions_customer_date ON transactions(customer_id, transaction_date, id);
+CREATE INDEX IF NOT EXISTS idx_transactions_type ON transactions(transaction_type);
+CREATE INDEX IF NOT EXISTS idx_transaction_items_transaction ON transaction_items(transaction_id);
+CREATE INDEX IF NOT EXISTS idx_tax_invoices_transaction ON tax_invoices(transaction_id);
@@ -0,0 +1,20 @@
+-- 거래번호 자동 채번 (예: S-20261017-0001)
+
+-- 회사별 거래 유형별 채번 설정 (행이 없으면 기본값 S/P/R/D, 4자리 사용)
+CREATE TABLE IF NOT EXISTS transaction_number_settings (
+    company_id INTEGER NOT NULL,
+    transaction_type TEXT NOT NULL CHECK(transaction_type IN ('sale', 'purchase', 'payment_in', 'payment_out')),
+    prefix TEXT NOT NULL,
+    sequence_digits INTEGER NOT NULL DEFAULT 4,
+    PRIMARY KEY (company_id, transaction_type)
+);
+
+-- 회사/유형/일자별 마지막 발번 번호
+-- 거래 INSERT와 같은 DB 트랜잭션에서 증가시키므로 롤백 시 번호도 함께 취소됨 (결번 없음)
+CREATE TABLE IF NOT EXISTS transaction_number_sequences (
+    company_id INTEGER NOT NULL,
+    transaction_type TEXT NOT NULL,
+    sequence_date TEXT NOT NULL,
+    last_number INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (company_id, transaction_type, sequence_date)
+);
@@ -0,0 +1,14 @@
+-- 수금/지급 거래를 특정 매출/매입 거래에 배분한 내역
+-- payment_id: payment_in/payment_out 거래, transaction_id: 대상 sale/purchase 거래
+CREATE TABLE IF NOT EXISTS payment_allocations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    payment_id INTEGER NOT NULL,
+    transaction_id INTEGER NOT NULL,
+    amount REAL NOT NULL CHECK(amount > 0),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (payment_id) REFERENCES transactions(id) ON DELETE CASCADE,
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_payment_allocations_payment ON payment_allocations(payment_id);
+CREATE INDEX IF NOT EXISTS idx_payment_allocations_transaction ON payment_allocations(transaction_id);
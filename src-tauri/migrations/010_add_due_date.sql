@@ -0,0 +1,4 @@
+-- 거래별 결제 기한 (미수금/미지급금 연령 분석용, NULL이면 거래일 기준)
+ALTER TABLE transactions ADD COLUMN due_date DATETIME;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_due_date ON transactions(due_date);
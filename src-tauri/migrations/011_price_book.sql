@@ -0,0 +1,30 @@
+-- 거래처별 상품 단가표 (거래 등록 시 단가 기본값)
+CREATE TABLE IF NOT EXISTS customer_product_prices (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    customer_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    current_price_per_kg REAL NOT NULL CHECK(current_price_per_kg >= 0),
+    last_updated DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    is_active BOOLEAN NOT NULL DEFAULT 1,
+    FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+    UNIQUE(customer_id, product_id)
+);
+
+-- 단가 변경 이력 (거래 확정으로 바뀐 경우 transaction_id 기록)
+CREATE TABLE IF NOT EXISTS price_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    customer_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    old_price REAL NOT NULL,
+    new_price REAL NOT NULL,
+    changed_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    transaction_id INTEGER,
+    notes TEXT,
+    FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_history_customer_product ON price_history(customer_id, product_id, changed_date);
+CREATE INDEX IF NOT EXISTS idx_price_history_transaction ON price_history(transaction_id);
@@ -0,0 +1,104 @@
+-- 재고 테이블(005)을 Rust 모델과 맞추는 마이그레이션
+-- - 수량/단가 컬럼은 REAL (DECIMAL 선언 시 정수값이 INTEGER로 저장되어 f64 디코딩 실패)
+-- - 유통기한(expiry_date)은 날짜만 의미가 있으므로 DATE(YYYY-MM-DD) 유지
+-- 테이블 재생성 중에는 마이그레이션 실행기가 외래 키 검사를 비활성화합니다.
+
+-- 1. product_inventory
+CREATE TABLE product_inventory_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    product_id INTEGER NOT NULL UNIQUE,
+    current_stock REAL NOT NULL DEFAULT 0,
+    safety_stock REAL NOT NULL DEFAULT 30,
+    location TEXT NOT NULL CHECK(location IN ('frozen', 'cold', 'room')) DEFAULT 'cold',
+    expiry_date DATE,
+    last_updated DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+INSERT INTO product_inventory_new (
+    id, product_id, current_stock, safety_stock, location, expiry_date, last_updated
+)
+SELECT
+    id, product_id,
+    CAST(COALESCE(current_stock, 0) AS REAL), CAST(COALESCE(safety_stock, 30) AS REAL),
+    COALESCE(location, 'cold'), expiry_date, COALESCE(last_updated, CURRENT_TIMESTAMP)
+FROM product_inventory;
+
+DROP TABLE product_inventory;
+ALTER TABLE product_inventory_new RENAME TO product_inventory;
+
+-- 2. stock_movements
+CREATE TABLE stock_movements_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    product_id INTEGER NOT NULL,
+    movement_type TEXT CHECK(movement_type IN ('in', 'out', 'adjust', 'expired')) NOT NULL,
+    quantity REAL NOT NULL,
+    unit_price REAL,
+    lot_number TEXT,
+    expiry_date DATE,
+    traceability_number TEXT,
+    origin TEXT,
+    slaughterhouse TEXT,
+    transaction_id INTEGER,
+    reference_type TEXT CHECK(reference_type IN ('purchase', 'sales', 'manual', 'adjustment', 'cancellation')),
+    reference_id INTEGER,
+    notes TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by TEXT,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE SET NULL
+);
+
+INSERT INTO stock_movements_new (
+    id, product_id, movement_type, quantity, unit_price, lot_number, expiry_date,
+    traceability_number, origin, slaughterhouse, transaction_id, reference_type,
+    reference_id, notes, created_at, created_by
+)
+SELECT
+    id, product_id, movement_type, CAST(quantity AS REAL), CAST(unit_price AS REAL),
+    lot_number, expiry_date, traceability_number, origin, slaughterhouse, transaction_id,
+    reference_type, reference_id, notes, COALESCE(created_at, CURRENT_TIMESTAMP), created_by
+FROM stock_movements;
+
+DROP TABLE stock_movements;
+ALTER TABLE stock_movements_new RENAME TO stock_movements;
+
+-- 3. stock_lots
+CREATE TABLE stock_lots_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    product_id INTEGER NOT NULL,
+    lot_number TEXT NOT NULL UNIQUE,
+    initial_quantity REAL NOT NULL,
+    remaining_quantity REAL NOT NULL,
+    expiry_date DATE NOT NULL,
+    traceability_number TEXT,
+    origin TEXT,
+    slaughterhouse TEXT,
+    supplier_id INTEGER,
+    status TEXT NOT NULL CHECK(status IN ('active', 'expired', 'finished', 'cancelled')) DEFAULT 'active',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+    FOREIGN KEY (supplier_id) REFERENCES customers(id) ON DELETE SET NULL
+);
+
+INSERT INTO stock_lots_new (
+    id, product_id, lot_number, initial_quantity, remaining_quantity, expiry_date,
+    traceability_number, origin, slaughterhouse, supplier_id, status, created_at
+)
+SELECT
+    id, product_id, lot_number, CAST(initial_quantity AS REAL), CAST(remaining_quantity AS REAL),
+    expiry_date, traceability_number, origin, slaughterhouse, supplier_id,
+    COALESCE(status, 'active'), COALESCE(created_at, CURRENT_TIMESTAMP)
+FROM stock_lots;
+
+DROP TABLE stock_lots;
+ALTER TABLE stock_lots_new RENAME TO stock_lots;
+
+-- 4. 인덱스 재생성
+CREATE INDEX IF NOT EXISTS idx_product_inventory_product ON product_inventory(product_id);
+CREATE INDEX IF NOT EXISTS idx_stock_movements_product ON stock_movements(product_id);
+CREATE INDEX IF NOT EXISTS idx_stock_movements_transaction ON stock_movements(transaction_id);
+CREATE INDEX IF NOT EXISTS idx_stock_movements_date ON stock_movements(created_at);
+CREATE INDEX IF NOT EXISTS idx_stock_lots_product ON stock_lots(product_id);
+CREATE INDEX IF NOT EXISTS idx_stock_lots_status ON stock_lots(status);
+CREATE INDEX IF NOT EXISTS idx_stock_lots_expiry ON stock_lots(expiry_date);
@@ -0,0 +1,25 @@
+-- 로트 기반 재고 관리 (선입선출 FIFO / 유통기한 우선 FEFO)
+
+-- 상품별 로트 출고 순서: 'fefo' 유통기한 빠른 순, 'fifo' 입고 순
+ALTER TABLE products ADD COLUMN lot_consumption TEXT NOT NULL DEFAULT 'fefo' CHECK(lot_consumption IN ('fefo', 'fifo'));
+
+-- 매입 품목의 유통기한 (재고관리 상품 매입 확정 시 로트의 유통기한이 됨)
+ALTER TABLE transaction_items ADD COLUMN expiry_date DATE;
+
+-- 로트를 만든 매입 품목 (수동 생성 로트는 NULL)
+ALTER TABLE stock_lots ADD COLUMN transaction_item_id INTEGER REFERENCES transaction_items(id) ON DELETE SET NULL;
+
+-- 매출 품목별 로트 소진 내역 (어떤 품목이 어느 로트에서 얼마나 출고되었는지)
+CREATE TABLE IF NOT EXISTS stock_lot_consumptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_item_id INTEGER NOT NULL,
+    lot_id INTEGER NOT NULL,
+    quantity REAL NOT NULL CHECK(quantity > 0),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (transaction_item_id) REFERENCES transaction_items(id) ON DELETE CASCADE,
+    FOREIGN KEY (lot_id) REFERENCES stock_lots(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_stock_lot_consumptions_item ON stock_lot_consumptions(transaction_item_id);
+CREATE INDEX IF NOT EXISTS idx_stock_lot_consumptions_lot ON stock_lot_consumptions(lot_id);
+CREATE INDEX IF NOT EXISTS idx_stock_lots_transaction_item ON stock_lots(transaction_item_id);
@@ -0,0 +1,3 @@
+-- 유통기한 경과 재고 자동 폐기 설정
+-- 1이면 매일 실행되는 유통기한 점검에서 만료 로트/재고를 'expired' 재고 이동으로 차감
+ALTER TABLE companies ADD COLUMN write_off_expired_stock BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- 이력번호 형식 검증 방식
+-- 'reject': 형식이 잘못된 이력번호는 저장 거부, 'warn': 경고만 남기고 저장
+ALTER TABLE companies ADD COLUMN traceability_validation TEXT NOT NULL DEFAULT 'reject' CHECK(traceability_validation IN ('reject', 'warn'));
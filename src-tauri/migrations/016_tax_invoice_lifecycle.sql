@@ -0,0 +1,40 @@
+-- 세금계산서 발행/전송/취소 이력
+-- status에 'void'(발행 취소) 추가: SQLite는 CHECK 제약을 변경할 수 없으므로 테이블 재생성
+CREATE TABLE tax_invoices_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_id INTEGER NOT NULL,
+    invoice_number TEXT UNIQUE NOT NULL,
+    issue_date DATETIME NOT NULL,
+    supplier_business_number TEXT NOT NULL,
+    supplier_name TEXT NOT NULL,
+    buyer_business_number TEXT NOT NULL,
+    buyer_name TEXT NOT NULL,
+    supply_amount REAL NOT NULL,
+    tax_amount REAL NOT NULL,
+    total_amount REAL NOT NULL,
+    status TEXT NOT NULL CHECK(status IN ('issued', 'sent', 'received', 'void')) DEFAULT 'issued',
+    pdf_path TEXT,
+    sent_at DATETIME,
+    voided_at DATETIME,
+    void_reason TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id)
+);
+
+INSERT INTO tax_invoices_new (
+    id, transaction_id, invoice_number, issue_date,
+    supplier_business_number, supplier_name, buyer_business_number, buyer_name,
+    supply_amount, tax_amount, total_amount, status, pdf_path, created_at, updated_at
+)
+SELECT
+    id, transaction_id, invoice_number, issue_date,
+    supplier_business_number, supplier_name, buyer_business_number, buyer_name,
+    supply_amount, tax_amount, total_amount, status, pdf_path, created_at, updated_at
+FROM tax_invoices;
+
+DROP TABLE tax_invoices;
+ALTER TABLE tax_invoices_new RENAME TO tax_invoices;
+
+CREATE INDEX IF NOT EXISTS idx_tax_invoices_transaction ON tax_invoices(transaction_id);
+CREATE INDEX IF NOT EXISTS idx_tax_invoices_issue_date ON tax_invoices(issue_date);
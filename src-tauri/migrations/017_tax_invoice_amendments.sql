@@ -0,0 +1,6 @@
+-- 수정세금계산서: 당초 세금계산서와 연결하고 수정사유를 기록
+-- 'return' 환입, 'price_change' 공급가액 변동, 'cancellation' 계약의 해제, 'duplicate' 착오에 의한 이중발급
+ALTER TABLE tax_invoices ADD COLUMN original_invoice_id INTEGER REFERENCES tax_invoices(id);
+ALTER TABLE tax_invoices ADD COLUMN amendment_reason TEXT CHECK(amendment_reason IN ('return', 'price_change', 'cancellation', 'duplicate'));
+
+CREATE INDEX IF NOT EXISTS idx_tax_invoices_original ON tax_invoices(original_invoice_id);
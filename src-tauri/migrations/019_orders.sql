@@ -0,0 +1,66 @@
+-- 수주(매출 주문서) / 발주(매입 주문서): 여러 번에 나누어 납품되는 주문
+-- 상태: 'open' 미납, 'partially_fulfilled' 부분 납품, 'fulfilled' 납품 완료, 'closed' 종결 (잔량 취소)
+CREATE TABLE IF NOT EXISTS sales_orders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_number TEXT NOT NULL UNIQUE,
+    customer_id INTEGER NOT NULL,
+    order_date DATETIME NOT NULL,
+    expected_date DATE,
+    status TEXT NOT NULL DEFAULT 'open' CHECK(status IN ('open', 'partially_fulfilled', 'fulfilled', 'closed')),
+    notes TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (customer_id) REFERENCES customers(id)
+);
+
+CREATE TABLE IF NOT EXISTS sales_order_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    quantity REAL NOT NULL CHECK(quantity > 0),
+    delivered_quantity REAL NOT NULL DEFAULT 0,
+    unit_price REAL NOT NULL,
+    tax_rate REAL NOT NULL DEFAULT 0.1,
+    notes TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (order_id) REFERENCES sales_orders(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+CREATE TABLE IF NOT EXISTS purchase_orders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_number TEXT NOT NULL UNIQUE,
+    customer_id INTEGER NOT NULL,
+    order_date DATETIME NOT NULL,
+    expected_date DATE,
+    status TEXT NOT NULL DEFAULT 'open' CHECK(status IN ('open', 'partially_fulfilled', 'fulfilled', 'closed')),
+    notes TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (customer_id) REFERENCES customers(id)
+);
+
+CREATE TABLE IF NOT EXISTS purchase_order_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    quantity REAL NOT NULL CHECK(quantity > 0),
+    delivered_quantity REAL NOT NULL DEFAULT 0,
+    unit_price REAL NOT NULL,
+    tax_rate REAL NOT NULL DEFAULT 0.1,
+    notes TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (order_id) REFERENCES purchase_orders(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+-- 거래 품목이 어느 주문 품목의 납품분인지
+ALTER TABLE transaction_items ADD COLUMN sales_order_item_id INTEGER REFERENCES sales_order_items(id);
+ALTER TABLE transaction_items ADD COLUMN purchase_order_item_id INTEGER REFERENCES purchase_order_items(id);
+
+CREATE INDEX IF NOT EXISTS idx_sales_orders_customer ON sales_orders(customer_id);
+CREATE INDEX IF NOT EXISTS idx_sales_order_items_order ON sales_order_items(order_id);
+CREATE INDEX IF NOT EXISTS idx_purchase_orders_customer ON purchase_orders(customer_id);
+CREATE INDEX IF NOT EXISTS idx_purchase_order_items_order ON purchase_order_items(order_id);
+CREATE INDEX IF NOT EXISTS idx_transaction_items_sales_order_item ON transaction_items(sales_order_item_id);
+CREATE INDEX IF NOT EXISTS idx_transaction_items_purchase_order_item ON transaction_items(purchase_order_item_id);
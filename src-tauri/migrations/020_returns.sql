@@ -0,0 +1,9 @@
+-- 반품: 확정된 매출/매입의 일부 품목을 되돌리는 음수 거래
+-- 반품 거래는 원거래와 같은 유형이며 수량·금액이 음수
+ALTER TABLE transactions ADD COLUMN return_of_transaction_id INTEGER REFERENCES transactions(id);
+
+-- 반품 품목이 되돌리는 원거래 품목
+ALTER TABLE transaction_items ADD COLUMN returned_item_id INTEGER REFERENCES transaction_items(id);
+
+CREATE INDEX IF NOT EXISTS idx_transactions_return_of ON transactions(return_of_transaction_id);
+CREATE INDEX IF NOT EXISTS idx_transaction_items_returned_item ON transaction_items(returned_item_id);
@@ -0,0 +1,463 @@
+-- 금액 컬럼을 정수 원(INTEGER) 단위로 변환하는 마이그레이션
+-- - REAL 금액은 부동소수점 오차로 세금계산서와 1원 차이가 나므로 원 단위 정수로 저장
+-- - 기존 값은 ROUND() 후 정수로 변환 (0.5원은 0에서 먼 쪽으로 반올림)
+-- - 수량(quantity)과 세율(tax_rate)은 REAL 유지
+-- - 부가세 반올림 정책(회사 설정): 품목별/문서 전체, 반올림/절사
+-- 테이블 재생성 중에는 마이그레이션 실행기가 외래 키 검사를 비활성화합니다.
+
+-- 1. customers
+CREATE TABLE customers_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    business_number TEXT,
+    representative TEXT,
+    address TEXT,
+    phone TEXT,
+    email TEXT,
+    business_type TEXT,
+    business_item TEXT,
+    contact_person TEXT,
+    customer_type TEXT NOT NULL CHECK(customer_type IN ('customer', 'supplier')) DEFAULT 'customer',
+    outstanding_balance INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT 1,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO customers_new (
+    id, name, business_number, representative, address, phone, email, business_type,
+    business_item, contact_person, customer_type, outstanding_balance, is_active,
+    created_at, updated_at
+)
+SELECT
+    id, name, business_number, representative, address, phone, email, business_type,
+    business_item, contact_person, customer_type,
+    CAST(ROUND(outstanding_balance) AS INTEGER), is_active, created_at, updated_at
+FROM customers;
+
+DROP TABLE customers;
+ALTER TABLE customers_new RENAME TO customers;
+
+CREATE INDEX IF NOT EXISTS idx_customers_business_number ON customers(business_number);
+
+-- 2. products
+CREATE TABLE products_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    code TEXT UNIQUE,
+    description TEXT,
+    category TEXT,
+    unit TEXT NOT NULL DEFAULT '개',
+    unit_price INTEGER NOT NULL DEFAULT 0,
+    tax_rate REAL NOT NULL DEFAULT 0.1,
+    traceability_number TEXT,
+    origin TEXT,
+    slaughterhouse TEXT,
+    use_inventory_management BOOLEAN NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT 1,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    lot_consumption TEXT NOT NULL DEFAULT 'fefo' CHECK(lot_consumption IN ('fefo', 'fifo'))
+);
+
+INSERT INTO products_new (
+    id, name, code, description, category, unit, unit_price, tax_rate, traceability_number,
+    origin, slaughterhouse, use_inventory_management, is_active, created_at, updated_at,
+    lot_consumption
+)
+SELECT
+    id, name, code, description, category, unit, CAST(ROUND(unit_price) AS INTEGER),
+    tax_rate, traceability_number, origin, slaughterhouse, use_inventory_management,
+    is_active, created_at, updated_at, lot_consumption
+FROM products;
+
+DROP TABLE products;
+ALTER TABLE products_new RENAME TO products;
+
+CREATE INDEX IF NOT EXISTS idx_products_code ON products(code);
+
+-- 3. customer_product_prices
+CREATE TABLE customer_product_prices_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    customer_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    current_price_per_kg INTEGER NOT NULL CHECK(current_price_per_kg >= 0),
+    last_updated DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    is_active BOOLEAN NOT NULL DEFAULT 1,
+    FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+    UNIQUE(customer_id, product_id)
+);
+
+INSERT INTO customer_product_prices_new (
+    id, customer_id, product_id, current_price_per_kg, last_updated, is_active
+)
+SELECT
+    id, customer_id, product_id, CAST(ROUND(current_price_per_kg) AS INTEGER), last_updated,
+    is_active
+FROM customer_product_prices;
+
+DROP TABLE customer_product_prices;
+ALTER TABLE customer_product_prices_new RENAME TO customer_product_prices;
+
+-- 4. price_history
+CREATE TABLE price_history_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    customer_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    old_price INTEGER NOT NULL,
+    new_price INTEGER NOT NULL,
+    changed_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    transaction_id INTEGER,
+    notes TEXT,
+    FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE SET NULL
+);
+
+INSERT INTO price_history_new (
+    id, customer_id, product_id, old_price, new_price, changed_date, transaction_id, notes
+)
+SELECT
+    id, customer_id, product_id, CAST(ROUND(old_price) AS INTEGER),
+    CAST(ROUND(new_price) AS INTEGER), changed_date, transaction_id, notes
+FROM price_history;
+
+DROP TABLE price_history;
+ALTER TABLE price_history_new RENAME TO price_history;
+
+CREATE INDEX IF NOT EXISTS idx_price_history_customer_product ON price_history(customer_id, product_id, changed_date);
+CREATE INDEX IF NOT EXISTS idx_price_history_transaction ON price_history(transaction_id);
+
+-- 5. transactions
+CREATE TABLE transactions_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_number TEXT UNIQUE NOT NULL,
+    customer_id INTEGER NOT NULL,
+    transaction_type TEXT NOT NULL CHECK(transaction_type IN ('sale', 'purchase', 'payment_in', 'payment_out')),
+    transaction_date DATETIME NOT NULL,
+    subtotal INTEGER NOT NULL DEFAULT 0,
+    tax_amount INTEGER NOT NULL DEFAULT 0,
+    total_amount INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL CHECK(status IN ('draft', 'confirmed', 'cancelled')) DEFAULT 'draft',
+    notes TEXT,
+    reference_payment_id INTEGER,
+    current_balance INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    due_date DATETIME,
+    return_of_transaction_id INTEGER REFERENCES transactions(id),
+    FOREIGN KEY (customer_id) REFERENCES customers(id),
+    FOREIGN KEY (reference_payment_id) REFERENCES transactions(id)
+);
+
+INSERT INTO transactions_new (
+    id, transaction_number, customer_id, transaction_type, transaction_date, subtotal,
+    tax_amount, total_amount, status, notes, reference_payment_id, current_balance,
+    created_at, updated_at, due_date, return_of_transaction_id
+)
+SELECT
+    id, transaction_number, customer_id, transaction_type, transaction_date,
+    CAST(ROUND(subtotal) AS INTEGER), CAST(ROUND(tax_amount) AS INTEGER),
+    CAST(ROUND(total_amount) AS INTEGER), status, notes, reference_payment_id,
+    CAST(ROUND(current_balance) AS INTEGER), created_at, updated_at, due_date,
+    return_of_transaction_id
+FROM transactions;
+
+DROP TABLE transactions;
+ALTER TABLE transactions_new RENAME TO transactions;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_date ON transactions(transaction_date);
+CREATE INDEX IF NOT EXISTS idx_transactions_customer ON transactions(customer_id);
+CREATE INDEX IF NOT EXISTS idx_transactions_reference_payment ON transactions(reference_payment_id);
+CREATE INDEX IF NOT EXISTS idx_transactions_customer_date ON transactions(customer_id, transaction_date, id);
+CREATE INDEX IF NOT EXISTS idx_transactions_type ON transactions(transaction_type);
+CREATE INDEX IF NOT EXISTS idx_transactions_due_date ON transactions(due_date);
+CREATE INDEX IF NOT EXISTS idx_transactions_return_of ON transactions(return_of_transaction_id);
+
+-- 6. transaction_items
+CREATE TABLE transaction_items_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    quantity REAL NOT NULL,
+    unit_price INTEGER NOT NULL,
+    tax_rate REAL NOT NULL DEFAULT 0,
+    subtotal INTEGER NOT NULL,
+    tax_amount INTEGER NOT NULL DEFAULT 0,
+    total_amount INTEGER NOT NULL,
+    traceability_number TEXT,
+    origin TEXT,
+    slaughterhouse TEXT,
+    notes TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expiry_date DATE,
+    sales_order_item_id INTEGER REFERENCES sales_order_items(id),
+    purchase_order_item_id INTEGER REFERENCES purchase_order_items(id),
+    returned_item_id INTEGER REFERENCES transaction_items(id),
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+INSERT INTO transaction_items_new (
+    id, transaction_id, product_id, quantity, unit_price, tax_rate, subtotal, tax_amount,
+    total_amount, traceability_number, origin, slaughterhouse, notes, created_at,
+    expiry_date, sales_order_item_id, purchase_order_item_id, returned_item_id
+)
+SELECT
+    id, transaction_id, product_id, quantity, CAST(ROUND(unit_price) AS INTEGER), tax_rate,
+    CAST(ROUND(subtotal) AS INTEGER), CAST(ROUND(tax_amount) AS INTEGER),
+    CAST(ROUND(total_amount) AS INTEGER), traceability_number, origin, slaughterhouse,
+    notes, created_at, expiry_date, sales_order_item_id, purchase_order_item_id,
+    returned_item_id
+FROM transaction_items;
+
+DROP TABLE transaction_items;
+ALTER TABLE transaction_items_new RENAME TO transaction_items;
+
+CREATE INDEX IF NOT EXISTS idx_transaction_items_transaction ON transaction_items(transaction_id);
+CREATE INDEX IF NOT EXISTS idx_transaction_items_sales_order_item ON transaction_items(sales_order_item_id);
+CREATE INDEX IF NOT EXISTS idx_transaction_items_purchase_order_item ON transaction_items(purchase_order_item_id);
+CREATE INDEX IF NOT EXISTS idx_transaction_items_returned_item ON transaction_items(returned_item_id);
+
+-- 7. tax_invoices
+CREATE TABLE tax_invoices_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_id INTEGER NOT NULL,
+    invoice_number TEXT UNIQUE NOT NULL,
+    issue_date DATETIME NOT NULL,
+    supplier_business_number TEXT NOT NULL,
+    supplier_name TEXT NOT NULL,
+    buyer_business_number TEXT NOT NULL,
+    buyer_name TEXT NOT NULL,
+    supply_amount INTEGER NOT NULL,
+    tax_amount INTEGER NOT NULL,
+    total_amount INTEGER NOT NULL,
+    status TEXT NOT NULL CHECK(status IN ('issued', 'sent', 'received', 'void')) DEFAULT 'issued',
+    pdf_path TEXT,
+    sent_at DATETIME,
+    voided_at DATETIME,
+    void_reason TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    original_invoice_id INTEGER REFERENCES tax_invoices(id),
+    amendment_reason TEXT CHECK(amendment_reason IN ('return', 'price_change', 'cancellation', 'duplicate')),
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id)
+);
+
+INSERT INTO tax_invoices_new (
+    id, transaction_id, invoice_number, issue_date, supplier_business_number, supplier_name,
+    buyer_business_number, buyer_name, supply_amount, tax_amount, total_amount, status,
+    pdf_path, sent_at, voided_at, void_reason, created_at, updated_at, original_invoice_id,
+    amendment_reason
+)
+SELECT
+    id, transaction_id, invoice_number, issue_date, supplier_business_number, supplier_name,
+    buyer_business_number, buyer_name, CAST(ROUND(supply_amount) AS INTEGER),
+    CAST(ROUND(tax_amount) AS INTEGER), CAST(ROUND(total_amount) AS INTEGER), status,
+    pdf_path, sent_at, voided_at, void_reason, created_at, updated_at, original_invoice_id,
+    amendment_reason
+FROM tax_invoices;
+
+DROP TABLE tax_invoices;
+ALTER TABLE tax_invoices_new RENAME TO tax_invoices;
+
+CREATE INDEX IF NOT EXISTS idx_tax_invoices_transaction ON tax_invoices(transaction_id);
+CREATE INDEX IF NOT EXISTS idx_tax_invoices_issue_date ON tax_invoices(issue_date);
+CREATE INDEX IF NOT EXISTS idx_tax_invoices_original ON tax_invoices(original_invoice_id);
+
+-- 8. payment_allocations
+CREATE TABLE payment_allocations_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    payment_id INTEGER NOT NULL,
+    transaction_id INTEGER NOT NULL,
+    amount INTEGER NOT NULL CHECK(amount > 0),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (payment_id) REFERENCES transactions(id) ON DELETE CASCADE,
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE CASCADE
+);
+
+INSERT INTO payment_allocations_new (
+    id, payment_id, transaction_id, amount, created_at
+)
+SELECT
+    id, payment_id, transaction_id, CAST(ROUND(amount) AS INTEGER), created_at
+FROM payment_allocations;
+
+DROP TABLE payment_allocations;
+ALTER TABLE payment_allocations_new RENAME TO payment_allocations;
+
+CREATE INDEX IF NOT EXISTS idx_payment_allocations_payment ON payment_allocations(payment_id);
+CREATE INDEX IF NOT EXISTS idx_payment_allocations_transaction ON payment_allocations(transaction_id);
+
+-- 9. stock_movements
+CREATE TABLE stock_movements_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    product_id INTEGER NOT NULL,
+    movement_type TEXT CHECK(movement_type IN ('in', 'out', 'adjust', 'expired')) NOT NULL,
+    quantity REAL NOT NULL,
+    unit_price INTEGER,
+    lot_number TEXT,
+    expiry_date DATE,
+    traceability_number TEXT,
+    origin TEXT,
+    slaughterhouse TEXT,
+    transaction_id INTEGER,
+    reference_type TEXT CHECK(reference_type IN ('purchase', 'sales', 'manual', 'adjustment', 'cancellation')),
+    reference_id INTEGER,
+    notes TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by TEXT,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE SET NULL
+);
+
+INSERT INTO stock_movements_new (
+    id, product_id, movement_type, quantity, unit_price, lot_number, expiry_date,
+    traceability_number, origin, slaughterhouse, transaction_id, reference_type,
+    reference_id, notes, created_at, created_by
+)
+SELECT
+    id, product_id, movement_type, quantity, CAST(ROUND(unit_price) AS INTEGER), lot_number,
+    expiry_date, traceability_number, origin, slaughterhouse, transaction_id,
+    reference_type, reference_id, notes, created_at, created_by
+FROM stock_movements;
+
+DROP TABLE stock_movements;
+ALTER TABLE stock_movements_new RENAME TO stock_movements;
+
+CREATE INDEX IF NOT EXISTS idx_stock_movements_product ON stock_movements(product_id);
+CREATE INDEX IF NOT EXISTS idx_stock_movements_transaction ON stock_movements(transaction_id);
+CREATE INDEX IF NOT EXISTS idx_stock_movements_date ON stock_movements(created_at);
+
+-- 10. quotations
+CREATE TABLE quotations_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    quotation_number TEXT NOT NULL UNIQUE,
+    customer_id INTEGER NOT NULL,
+    quotation_date DATETIME NOT NULL,
+    valid_until DATE NOT NULL,
+    subtotal INTEGER NOT NULL DEFAULT 0,
+    tax_amount INTEGER NOT NULL DEFAULT 0,
+    total_amount INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL DEFAULT 'draft' CHECK(status IN ('draft', 'sent', 'accepted', 'expired', 'rejected')),
+    transaction_id INTEGER,
+    notes TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (customer_id) REFERENCES customers(id),
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE SET NULL
+);
+
+INSERT INTO quotations_new (
+    id, quotation_number, customer_id, quotation_date, valid_until, subtotal, tax_amount,
+    total_amount, status, transaction_id, notes, created_at, updated_at
+)
+SELECT
+    id, quotation_number, customer_id, quotation_date, valid_until,
+    CAST(ROUND(subtotal) AS INTEGER), CAST(ROUND(tax_amount) AS INTEGER),
+    CAST(ROUND(total_amount) AS INTEGER), status, transaction_id, notes, created_at,
+    updated_at
+FROM quotations;
+
+DROP TABLE quotations;
+ALTER TABLE quotations_new RENAME TO quotations;
+
+CREATE INDEX IF NOT EXISTS idx_quotations_customer ON quotations(customer_id);
+CREATE INDEX IF NOT EXISTS idx_quotations_status_valid ON quotations(status, valid_until);
+
+-- 11. quotation_items
+CREATE TABLE quotation_items_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    quotation_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    quantity REAL NOT NULL CHECK(quantity > 0),
+    unit_price INTEGER NOT NULL,
+    tax_rate REAL NOT NULL DEFAULT 0.1,
+    subtotal INTEGER NOT NULL,
+    tax_amount INTEGER NOT NULL,
+    total_amount INTEGER NOT NULL,
+    notes TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (quotation_id) REFERENCES quotations(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+INSERT INTO quotation_items_new (
+    id, quotation_id, product_id, quantity, unit_price, tax_rate, subtotal, tax_amount,
+    total_amount, notes, created_at
+)
+SELECT
+    id, quotation_id, product_id, quantity, CAST(ROUND(unit_price) AS INTEGER), tax_rate,
+    CAST(ROUND(subtotal) AS INTEGER), CAST(ROUND(tax_amount) AS INTEGER),
+    CAST(ROUND(total_amount) AS INTEGER), notes, created_at
+FROM quotation_items;
+
+DROP TABLE quotation_items;
+ALTER TABLE quotation_items_new RENAME TO quotation_items;
+
+CREATE INDEX IF NOT EXISTS idx_quotation_items_quotation ON quotation_items(quotation_id);
+
+-- 12. sales_order_items
+CREATE TABLE sales_order_items_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    quantity REAL NOT NULL CHECK(quantity > 0),
+    delivered_quantity REAL NOT NULL DEFAULT 0,
+    unit_price INTEGER NOT NULL,
+    tax_rate REAL NOT NULL DEFAULT 0.1,
+    notes TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (order_id) REFERENCES sales_orders(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+INSERT INTO sales_order_items_new (
+    id, order_id, product_id, quantity, delivered_quantity, unit_price, tax_rate, notes,
+    created_at
+)
+SELECT
+    id, order_id, product_id, quantity, delivered_quantity,
+    CAST(ROUND(unit_price) AS INTEGER), tax_rate, notes, created_at
+FROM sales_order_items;
+
+DROP TABLE sales_order_items;
+ALTER TABLE sales_order_items_new RENAME TO sales_order_items;
+
+CREATE INDEX IF NOT EXISTS idx_sales_order_items_order ON sales_order_items(order_id);
+
+-- 13. purchase_order_items
+CREATE TABLE purchase_order_items_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    quantity REAL NOT NULL CHECK(quantity > 0),
+    delivered_quantity REAL NOT NULL DEFAULT 0,
+    unit_price INTEGER NOT NULL,
+    tax_rate REAL NOT NULL DEFAULT 0.1,
+    notes TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (order_id) REFERENCES purchase_orders(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+INSERT INTO purchase_order_items_new (
+    id, order_id, product_id, quantity, delivered_quantity, unit_price, tax_rate, notes,
+    created_at
+)
+SELECT
+    id, order_id, product_id, quantity, delivered_quantity,
+    CAST(ROUND(unit_price) AS INTEGER), tax_rate, notes, created_at
+FROM purchase_order_items;
+
+DROP TABLE purchase_order_items;
+ALTER TABLE purchase_order_items_new RENAME TO purchase_order_items;
+
+CREATE INDEX IF NOT EXISTS idx_purchase_order_items_order ON purchase_order_items(order_id);
+
+-- 14. 부가세 반올림 정책
+-- vat_rounding_scope 'line': 품목별로 부가세를 반올림해 합산, 'document': 공급가액 합계에 대해 한 번 계산
+-- vat_rounding_mode 'round': 반올림, 'floor': 원 미만 절사
+ALTER TABLE companies ADD COLUMN vat_rounding_scope TEXT NOT NULL DEFAULT 'line' CHECK(vat_rounding_scope IN ('line', 'document'));
+ALTER TABLE companies ADD COLUMN vat_rounding_mode TEXT NOT NULL DEFAULT 'round' CHECK(vat_rounding_mode IN ('round', 'floor'));
@@ -0,0 +1,18 @@
+-- 과세 구분과 단가 부가세 포함 여부
+-- tax_category 'taxable': 과세, 'zero_rated': 영세율(세율 0), 'exempt': 면세 (계산서 대상, 세금계산서 제외)
+-- price_mode 'exclusive': 단가에 부가세 별도, 'inclusive': 단가에 부가세 포함 (공급가액 = 금액 - 세액)
+-- 거래/견적의 subtotal은 과세·영세율 공급가액, exempt_amount는 면세 금액
+-- total_amount = subtotal + tax_amount + exempt_amount
+ALTER TABLE products ADD COLUMN tax_category TEXT NOT NULL DEFAULT 'taxable' CHECK(tax_category IN ('taxable', 'zero_rated', 'exempt'));
+
+-- 세율 0으로 등록된 상품은 면세로 분류 (새 거래부터 적용)
+UPDATE products SET tax_category = 'exempt' WHERE tax_rate = 0;
+
+-- 기존 거래 품목은 과세로 두어 발행된 세금계산서와 금액이 그대로 유지됨
+ALTER TABLE transaction_items ADD COLUMN tax_category TEXT NOT NULL DEFAULT 'taxable' CHECK(tax_category IN ('taxable', 'zero_rated', 'exempt'));
+ALTER TABLE transactions ADD COLUMN price_mode TEXT NOT NULL DEFAULT 'exclusive' CHECK(price_mode IN ('exclusive', 'inclusive'));
+ALTER TABLE transactions ADD COLUMN exempt_amount INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE quotation_items ADD COLUMN tax_category TEXT NOT NULL DEFAULT 'taxable' CHECK(tax_category IN ('taxable', 'zero_rated', 'exempt'));
+ALTER TABLE quotations ADD COLUMN price_mode TEXT NOT NULL DEFAULT 'exclusive' CHECK(price_mode IN ('exclusive', 'inclusive'));
+ALTER TABLE quotations ADD COLUMN exempt_amount INTEGER NOT NULL DEFAULT 0;
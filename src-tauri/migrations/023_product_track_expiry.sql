@@ -0,0 +1,4 @@
+-- 유통기한 관리 여부
+-- track_expiry 1: 매입 확정 시 유통기한이 필요하고 로트가 생성됨 (기존 동작, 축산물)
+-- track_expiry 0: 포장재·소모품처럼 유통기한이 없는 재고, 로트 없이 입고되고 판매 시 로트 다음에 출고됨
+ALTER TABLE products ADD COLUMN track_expiry BOOLEAN NOT NULL DEFAULT 1;
@@ -0,0 +1,3 @@
+-- 주문서 단가의 부가세 포함 여부 (견적서·거래와 같은 값), 납품 거래로 그대로 이어짐
+ALTER TABLE sales_orders ADD COLUMN price_mode TEXT NOT NULL DEFAULT 'exclusive' CHECK(price_mode IN ('exclusive', 'inclusive'));
+ALTER TABLE purchase_orders ADD COLUMN price_mode TEXT NOT NULL DEFAULT 'exclusive' CHECK(price_mode IN ('exclusive', 'inclusive'));
@@ -0,0 +1,4 @@
+-- 이력번호 검사가 'warn' 모드일 때 통과시킨 형식 오류 메시지 (정상 번호면 NULL)
+ALTER TABLE products ADD COLUMN traceability_warning TEXT;
+ALTER TABLE transaction_items ADD COLUMN traceability_warning TEXT;
+ALTER TABLE stock_movements ADD COLUMN traceability_warning TEXT;
@@ -0,0 +1,2 @@
+-- 거래명세서 등 PDF 문서에 포함(임베드)할 TrueType 글꼴 파일 경로 (NULL이면 시스템 한글 글꼴을 찾아 사용)
+ALTER TABLE companies ADD COLUMN document_font_path TEXT;
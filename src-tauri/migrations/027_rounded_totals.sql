@@ -0,0 +1,54 @@
+-- 021 이후 합계 재계산
+-- 021에서 금액마다 따로 반올림하면서 합계가 1원씩 어긋날 수 있으므로 품목 합계를 공급가액 + 세액으로 맞추고,
+-- 품목이 있는 거래/견적서의 헤더 합계를 반올림된 품목에서 다시 계산한 뒤 잔액을 다시 쌓습니다.
+-- 헤더의 subtotal/tax_amount는 과세·영세율 품목, exempt_amount는 면세 품목 합계 (022, transaction::recompute_totals와 동일)
+UPDATE transaction_items SET total_amount = subtotal + tax_amount;
+
+UPDATE transactions SET
+    subtotal = COALESCE((SELECT SUM(ti.subtotal) FROM transaction_items ti
+        WHERE ti.transaction_id = transactions.id AND ti.tax_category != 'exempt'), 0),
+    tax_amount = COALESCE((SELECT SUM(ti.tax_amount) FROM transaction_items ti
+        WHERE ti.transaction_id = transactions.id AND ti.tax_category != 'exempt'), 0),
+    exempt_amount = COALESCE((SELECT SUM(ti.subtotal) FROM transaction_items ti
+        WHERE ti.transaction_id = transactions.id AND ti.tax_category = 'exempt'), 0)
+WHERE EXISTS (SELECT 1 FROM transaction_items ti WHERE ti.transaction_id = transactions.id);
+
+-- 수금/지급처럼 품목이 없는 거래도 포함해 모든 거래의 합계를 공급가액 + 세액 + 면세 금액으로 맞춤
+UPDATE transactions SET total_amount = subtotal + tax_amount + exempt_amount;
+
+UPDATE quotation_items SET total_amount = subtotal + tax_amount;
+
+UPDATE quotations SET
+    subtotal = COALESCE((SELECT SUM(qi.subtotal) FROM quotation_items qi
+        WHERE qi.quotation_id = quotations.id AND qi.tax_category != 'exempt'), 0),
+    tax_amount = COALESCE((SELECT SUM(qi.tax_amount) FROM quotation_items qi
+        WHERE qi.quotation_id = quotations.id AND qi.tax_category != 'exempt'), 0),
+    exempt_amount = COALESCE((SELECT SUM(qi.subtotal) FROM quotation_items qi
+        WHERE qi.quotation_id = quotations.id AND qi.tax_category = 'exempt'), 0)
+WHERE EXISTS (SELECT 1 FROM quotation_items qi WHERE qi.quotation_id = quotations.id);
+
+UPDATE quotations SET total_amount = subtotal + tax_amount + exempt_amount;
+
+-- 거래별 잔액: 같은 거래처의 (거래일, id) 순서로 이 거래까지 확정된 거래의 누계 (balance::recompute_customer_balance와 동일)
+UPDATE transactions SET current_balance = (
+    SELECT COALESCE(SUM(CASE
+        WHEN t.transaction_type IN ('sale', 'purchase') THEN t.total_amount
+        WHEN t.transaction_type IN ('payment_in', 'payment_out') THEN -t.total_amount
+        ELSE 0
+    END), 0)
+    FROM transactions t
+    WHERE t.customer_id = transactions.customer_id
+      AND t.status = 'confirmed'
+      AND (t.transaction_date < transactions.transaction_date
+           OR (t.transaction_date = transactions.transaction_date AND t.id <= transactions.id))
+);
+
+UPDATE customers SET outstanding_balance = (
+    SELECT COALESCE(SUM(CASE
+        WHEN t.transaction_type IN ('sale', 'purchase') THEN t.total_amount
+        WHEN t.transaction_type IN ('payment_in', 'payment_out') THEN -t.total_amount
+        ELSE 0
+    END), 0)
+    FROM transactions t
+    WHERE t.customer_id = customers.id AND t.status = 'confirmed'
+);
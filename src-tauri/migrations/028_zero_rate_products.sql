@@ -0,0 +1,8 @@
+-- 022는 처음에 세율 0으로 등록된 상품을 모두 면세로 분류했으나, 세율 0만으로는 면세인지 영세율인지 알 수 없음.
+-- 022가 면세로 바꾼 뒤 수정되지 않은 상품은 과세로 되돌리고, 과세이면서 세율 0인 상품을
+-- 검토 대상으로 조회 (product::get_products_for_tax_review). 022 이후 직접 면세로 지정한 상품은 그대로 둠
+UPDATE products SET tax_category = 'taxable'
+WHERE tax_category = 'exempt' AND tax_rate = 0
+  AND julianday(COALESCE(updated_at, created_at)) <= (
+      SELECT julianday(applied_at) FROM _migrations WHERE name = '022_vat_categories.sql'
+  );
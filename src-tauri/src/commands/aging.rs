@@ -0,0 +1,16 @@
+use crate::database::DbPool;
+use crate::errors::{CommandResult, ErrorResponse};
+use crate::services::aging::{self, AgingReport};
+use chrono::{DateTime, Utc};
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_aging_report(
+    db: State<'_, DbPool>,
+    as_of: Option<DateTime<Utc>>,
+    basis: Option<String>
+) -> CommandResult<AgingReport> {
+    aging::get_aging_report(&db, as_of, basis)
+        .await
+        .map_err(ErrorResponse::from)
+}
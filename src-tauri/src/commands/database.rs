@@ -0,0 +1,8 @@
+use crate::database::{self, DatabaseInfo, DbLocation, DbPool};
+use crate::errors::{CommandResult, ErrorResponse};
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_database_info(db: State<'_, DbPool>, location: State<'_, DbLocation>) -> CommandResult<DatabaseInfo> {
+    database::get_database_info(&db, &location).await.map_err(ErrorResponse::from)
+}
@@ -0,0 +1,31 @@
+use crate::database::DbPool;
+use crate::errors::{CommandResult, ErrorResponse};
+use crate::models::DeliveryNoteOptions;
+use crate::services::documents;
+use chrono::{DateTime, Utc};
+use tauri::State;
+
+#[tauri::command]
+pub async fn export_delivery_note(
+    db: State<'_, DbPool>,
+    id: i64,
+    path: String,
+    options: Option<DeliveryNoteOptions>
+) -> CommandResult<()> {
+    documents::export_delivery_note(&db, id, &path, options.unwrap_or_default())
+        .await
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn export_delivery_notes(
+    db: State<'_, DbPool>,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    directory: String,
+    options: Option<DeliveryNoteOptions>
+) -> CommandResult<Vec<String>> {
+    documents::export_delivery_notes(&db, start_date, end_date, &directory, options.unwrap_or_default())
+        .await
+        .map_err(ErrorResponse::from)
+}
@@ -0,0 +1,15 @@
+use crate::database::DbPool;
+use crate::errors::{CommandResult, ErrorResponse};
+use crate::services::expiry::{self, ExpiryAlertGroup, ExpiryCheckResult};
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_expiry_alerts(db: State<'_, DbPool>, days: i64) -> CommandResult<Vec<ExpiryAlertGroup>> {
+    expiry::get_expiry_alerts(&db, days).await.map_err(ErrorResponse::from)
+}
+
+/// Runs the daily expiry check on demand
+#[tauri::command]
+pub async fn run_expiry_check(db: State<'_, DbPool>) -> CommandResult<ExpiryCheckResult> {
+    expiry::run_expiry_check(&db).await.map_err(ErrorResponse::from)
+}
@@ -0,0 +1,48 @@
+use crate::database::DbPool;
+use crate::errors::{CommandResult, ErrorResponse};
+use crate::models::{
+    CreateStockMovementRequest, InventoryWithProduct, StockMovement, UpdateInventoryRequest,
+};
+use crate::services::inventory;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_inventory(db: State<'_, DbPool>) -> CommandResult<Vec<InventoryWithProduct>> {
+    inventory::get_inventory(&db).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_inventory_by_product(
+    db: State<'_, DbPool>,
+    product_id: i64
+) -> CommandResult<Option<InventoryWithProduct>> {
+    inventory::get_inventory_by_product(&db, product_id)
+        .await
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn update_inventory(
+    db: State<'_, DbPool>,
+    request: UpdateInventoryRequest
+) -> CommandResult<InventoryWithProduct> {
+    inventory::update_inventory(&db, request).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn create_stock_movement(
+    db: State<'_, DbPool>,
+    request: CreateStockMovementRequest
+) -> CommandResult<StockMovement> {
+    inventory::create_stock_movement(&db, request).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_stock_movements(
+    db: State<'_, DbPool>,
+    product_id: Option<i64>
+) -> CommandResult<Vec<StockMovement>> {
+    inventory::get_stock_movements(&db, product_id)
+        .await
+        .map_err(ErrorResponse::from)
+}
@@ -0,0 +1,25 @@
+use crate::database::DbPool;
+use crate::errors::{CommandResult, ErrorResponse};
+use crate::models::StockLot;
+use crate::services::lots::{self, ItemLotConsumption};
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_active_lots(db: State<'_, DbPool>, product_id: i64) -> CommandResult<Vec<StockLot>> {
+    lots::get_active_lots(&db, product_id).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_all_lots(db: State<'_, DbPool>, product_id: Option<i64>) -> CommandResult<Vec<StockLot>> {
+    lots::get_all_lots(&db, product_id).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_transaction_lot_consumptions(
+    db: State<'_, DbPool>,
+    transaction_id: i64
+) -> CommandResult<Vec<ItemLotConsumption>> {
+    lots::get_transaction_lot_consumptions(&db, transaction_id)
+        .await
+        .map_err(ErrorResponse::from)
+}
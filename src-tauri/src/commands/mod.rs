@@ -1,9 +1,37 @@
+pub mod aging;
 pub mod company;
 pub mod customer;
+pub mod database;
+pub mod documents;
+pub mod expiry;
+pub mod inventory;
+pub mod lots;
+pub mod numbering;
+pub mod orders;
+pub mod payment;
+pub mod pricing;
 pub mod product;
+pub mod quotation;
+pub mod returns;
+pub mod tax_invoice;
+pub mod trace;
 pub mod transaction;
 
+pub use aging::*;
 pub use company::*;
 pub use customer::*;
+pub use database::*;
+pub use documents::*;
+pub use expiry::*;
+pub use inventory::*;
+pub use lots::*;
+pub use numbering::*;
+pub use orders::*;
+pub use payment::*;
+pub use pricing::*;
 pub use product::*;
+pub use quotation::*;
+pub use returns::*;
+pub use tax_invoice::*;
+pub use trace::*;
 pub use transaction::*;
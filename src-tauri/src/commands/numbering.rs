@@ -0,0 +1,36 @@
+use crate::database::DbPool;
+use crate::errors::{CommandResult, ErrorResponse};
+use crate::models::{TransactionNumberSetting, UpdateTransactionNumberSettingRequest};
+use crate::services::numbering::{self, RenumberedTransaction};
+use tauri::State;
+use chrono::{DateTime, Utc};
+
+#[tauri::command]
+pub async fn preview_transaction_number(
+    db: State<'_, DbPool>,
+    transaction_type: String,
+    transaction_date: Option<DateTime<Utc>>
+) -> CommandResult<String> {
+    let transaction_date = transaction_date.unwrap_or_else(Utc::now);
+    numbering::preview_transaction_number(&db, &transaction_type, transaction_date)
+        .await
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn renumber_draft_transactions(db: State<'_, DbPool>) -> CommandResult<Vec<RenumberedTransaction>> {
+    numbering::renumber_draft_transactions(&db).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_transaction_number_settings(db: State<'_, DbPool>) -> CommandResult<Vec<TransactionNumberSetting>> {
+    numbering::get_transaction_number_settings(&db).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn update_transaction_number_setting(
+    db: State<'_, DbPool>,
+    request: UpdateTransactionNumberSettingRequest
+) -> CommandResult<TransactionNumberSetting> {
+    numbering::update_transaction_number_setting(&db, request).await.map_err(ErrorResponse::from)
+}
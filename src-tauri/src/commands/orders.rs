@@ -0,0 +1,35 @@
+use crate::database::DbPool;
+use crate::errors::{CommandResult, ErrorResponse};
+use crate::models::{CreateOrderRequest, FulfilOrderRequest, OrderWithItems, TransactionWithItems};
+use crate::services::orders;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_orders(
+    db: State<'_, DbPool>,
+    order_type: String,
+    status: Option<String>,
+    customer_id: Option<i64>
+) -> CommandResult<Vec<OrderWithItems>> {
+    orders::get_orders(&db, order_type, status, customer_id).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_order_by_id(db: State<'_, DbPool>, order_type: String, id: i64) -> CommandResult<Option<OrderWithItems>> {
+    orders::get_order_by_id(&db, order_type, id).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn create_order(db: State<'_, DbPool>, request: CreateOrderRequest) -> CommandResult<OrderWithItems> {
+    orders::create_order(&db, request).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn fulfil_order(db: State<'_, DbPool>, request: FulfilOrderRequest) -> CommandResult<TransactionWithItems> {
+    orders::fulfil_order(&db, request).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn close_order(db: State<'_, DbPool>, order_type: String, id: i64) -> CommandResult<OrderWithItems> {
+    orders::close_order(&db, order_type, id).await.map_err(ErrorResponse::from)
+}
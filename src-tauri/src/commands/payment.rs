@@ -0,0 +1,42 @@
+use crate::database::DbPool;
+use crate::errors::{CommandResult, ErrorResponse};
+use crate::models::{PaymentWithAllocations, RecordPaymentRequest};
+use crate::services::payment::{self, LedgerEntry, OpenTransaction};
+use tauri::State;
+
+#[tauri::command]
+pub async fn record_receipt(db: State<'_, DbPool>, request: RecordPaymentRequest) -> CommandResult<PaymentWithAllocations> {
+    payment::record_receipt(&db, request).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn record_disbursement(db: State<'_, DbPool>, request: RecordPaymentRequest) -> CommandResult<PaymentWithAllocations> {
+    payment::record_disbursement(&db, request).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_payment_by_id(db: State<'_, DbPool>, id: i64) -> CommandResult<Option<PaymentWithAllocations>> {
+    payment::get_payment_by_id(&db, id).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_open_transactions(
+    db: State<'_, DbPool>,
+    customer_id: i64,
+    transaction_type: String
+) -> CommandResult<Vec<OpenTransaction>> {
+    payment::get_open_transactions(&db, customer_id, &transaction_type)
+        .await
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_customer_ledger(
+    db: State<'_, DbPool>,
+    customer_id: i64,
+    ledger_type: String
+) -> CommandResult<Vec<LedgerEntry>> {
+    payment::get_customer_ledger(&db, customer_id, &ledger_type)
+        .await
+        .map_err(ErrorResponse::from)
+}
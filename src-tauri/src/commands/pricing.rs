@@ -0,0 +1,40 @@
+use crate::database::DbPool;
+use crate::errors::{CommandResult, ErrorResponse};
+use crate::models::{CustomerProductPrice, PriceHistory, SetCustomerProductPriceRequest};
+use crate::services::pricing;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_customer_product_prices(db: State<'_, DbPool>, customer_id: i64) -> CommandResult<Vec<CustomerProductPrice>> {
+    pricing::get_customer_product_prices(&db, customer_id).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_customer_product_price(
+    db: State<'_, DbPool>,
+    customer_id: i64,
+    product_id: i64
+) -> CommandResult<Option<CustomerProductPrice>> {
+    pricing::get_customer_product_price(&db, customer_id, product_id)
+        .await
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn set_customer_product_price(
+    db: State<'_, DbPool>,
+    request: SetCustomerProductPriceRequest
+) -> CommandResult<CustomerProductPrice> {
+    pricing::set_customer_product_price(&db, request).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_price_history(
+    db: State<'_, DbPool>,
+    customer_id: i64,
+    product_id: Option<i64>
+) -> CommandResult<Vec<PriceHistory>> {
+    pricing::get_price_history(&db, customer_id, product_id)
+        .await
+        .map_err(ErrorResponse::from)
+}
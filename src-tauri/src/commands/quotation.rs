@@ -0,0 +1,51 @@
+use crate::database::DbPool;
+use crate::errors::{CommandResult, ErrorResponse};
+use crate::models::{CreateQuotationRequest, QuotationWithItems, TransactionWithItems};
+use crate::services::quotation;
+use chrono::{DateTime, Utc};
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_quotations(
+    db: State<'_, DbPool>,
+    status: Option<String>,
+    customer_id: Option<i64>
+) -> CommandResult<Vec<QuotationWithItems>> {
+    quotation::get_quotations(&db, status, customer_id).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_quotation_by_id(db: State<'_, DbPool>, id: i64) -> CommandResult<Option<QuotationWithItems>> {
+    quotation::get_quotation_by_id(&db, id).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn create_quotation(db: State<'_, DbPool>, request: CreateQuotationRequest) -> CommandResult<QuotationWithItems> {
+    quotation::create_quotation(&db, request).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn update_quotation_status(db: State<'_, DbPool>, id: i64, status: String) -> CommandResult<QuotationWithItems> {
+    quotation::update_quotation_status(&db, id, status).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn delete_quotation(db: State<'_, DbPool>, id: i64) -> CommandResult<()> {
+    quotation::delete_quotation(&db, id).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn convert_quotation_to_transaction(
+    db: State<'_, DbPool>,
+    id: i64,
+    transaction_date: Option<DateTime<Utc>>
+) -> CommandResult<TransactionWithItems> {
+    quotation::convert_quotation_to_transaction(&db, id, transaction_date)
+        .await
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn expire_quotations(db: State<'_, DbPool>) -> CommandResult<i64> {
+    quotation::expire_quotations(&db).await.map_err(ErrorResponse::from)
+}
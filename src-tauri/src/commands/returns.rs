@@ -0,0 +1,24 @@
+use crate::database::DbPool;
+use crate::errors::{CommandResult, ErrorResponse};
+use crate::models::{ReturnItemRequest, TransactionWithItems};
+use crate::services::returns;
+use chrono::{DateTime, Utc};
+use tauri::State;
+
+#[tauri::command]
+pub async fn create_return(
+    db: State<'_, DbPool>,
+    transaction_id: i64,
+    items: Vec<ReturnItemRequest>,
+    return_date: Option<DateTime<Utc>>,
+    notes: Option<String>
+) -> CommandResult<TransactionWithItems> {
+    returns::create_return(&db, transaction_id, items, return_date, notes)
+        .await
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_transaction_returns(db: State<'_, DbPool>, transaction_id: i64) -> CommandResult<Vec<TransactionWithItems>> {
+    returns::get_transaction_returns(&db, transaction_id).await.map_err(ErrorResponse::from)
+}
@@ -0,0 +1,49 @@
+use crate::database::DbPool;
+use crate::errors::{CommandResult, ErrorResponse};
+use crate::models::{AmendTaxInvoiceRequest, TaxInvoice};
+use crate::services::{tax_invoice, tax_invoice_xml};
+use chrono::{DateTime, Utc};
+use tauri::State;
+
+#[tauri::command]
+pub async fn issue_tax_invoice(db: State<'_, DbPool>, transaction_id: i64) -> CommandResult<TaxInvoice> {
+    tax_invoice::issue_tax_invoice(&db, transaction_id).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn list_tax_invoices(
+    db: State<'_, DbPool>,
+    status: Option<String>,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>
+) -> CommandResult<Vec<TaxInvoice>> {
+    tax_invoice::list_tax_invoices(&db, status, start_date, end_date)
+        .await
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn void_tax_invoice(db: State<'_, DbPool>, id: i64, reason: Option<String>) -> CommandResult<TaxInvoice> {
+    tax_invoice::void_tax_invoice(&db, id, reason).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn amend_tax_invoice(db: State<'_, DbPool>, request: AmendTaxInvoiceRequest) -> CommandResult<TaxInvoice> {
+    tax_invoice::amend_tax_invoice(&db, request).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn get_tax_invoice_chain(db: State<'_, DbPool>, id: i64) -> CommandResult<Vec<TaxInvoice>> {
+    tax_invoice::get_tax_invoice_chain(&db, id).await.map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn mark_invoice_sent(db: State<'_, DbPool>, id: i64) -> CommandResult<TaxInvoice> {
+    tax_invoice::mark_invoice_sent(&db, id).await.map_err(ErrorResponse::from)
+}
+
+/// Writes the invoice as an e-tax invoice XML file to the path the user picked
+#[tauri::command]
+pub async fn export_tax_invoice_xml(db: State<'_, DbPool>, id: i64, path: String) -> CommandResult<()> {
+    tax_invoice_xml::export_tax_invoice_xml(&db, id, &path).await.map_err(ErrorResponse::from)
+}
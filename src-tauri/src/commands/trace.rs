@@ -0,0 +1,21 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, CommandResult, ErrorResponse};
+use crate::services::trace::{self, TraceReport};
+use crate::services::traceability::{self, TraceabilityNumber};
+use tauri::State;
+
+#[tauri::command]
+pub async fn trace_by_traceability_number(
+    db: State<'_, DbPool>,
+    traceability_number: String
+) -> CommandResult<TraceReport> {
+    trace::trace_by_traceability_number(&db, &traceability_number)
+        .await
+        .map_err(ErrorResponse::from)
+}
+
+#[tauri::command]
+pub async fn validate_traceability_number(traceability_number: String) -> CommandResult<TraceabilityNumber> {
+    traceability::parse(&traceability_number)
+        .map_err(|message| AppError::invalid_field("traceability_number", message).into())
+}
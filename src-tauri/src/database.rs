@@ -1,112 +1,252 @@
-use sqlx::{Pool, Sqlite, SqlitePool};
-use std::path::PathBuf;
-use tauri::AppHandle;
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
+use sqlx::{ConnectOptions, Pool, Sqlite, SqlitePool};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::api::path;
+use tauri::{AppHandle, Config, Env, Manager, PackageInfo};
+
+use crate::errors::AppResult;
+use crate::{migrations, schema};
 
 pub type DbPool = Pool<Sqlite>;
 
-pub async fn init_db(app_handle: &AppHandle) -> Result<DbPool> {
-    // 개발 중에는 프로젝트 루트의 data 폴더 사용
-    let app_dir = app_handle
-        .path_resolver()
-        .resource_dir()
-        .map(|p| p.parent().unwrap().to_path_buf())
-        .unwrap_or_else(|| std::env::current_dir().unwrap())
-        .join("data");
-    
+/// Command-line flag naming the database file, e.g. `--db-path D:\erp\simple_erp.db`
+const DB_PATH_FLAG: &str = "--db-path";
+/// Environment variable naming the database file
+const DB_PATH_ENV: &str = "SIMPLE_ERP_DB_PATH";
+/// Settings file in the app config directory, e.g. `{ "database_path": "..." }`
+const SETTINGS_FILE: &str = "settings.json";
+const DB_FILE_NAME: &str = "simple_erp.db";
+
+/// How long a connection waits for a lock held by another connection
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The database file in use and where that choice came from
+#[derive(Debug, Clone, Serialize)]
+pub struct DbLocation {
+    pub path: PathBuf,
+    pub source: String, // cli, env, settings, legacy or default
+}
+
+#[derive(Debug, Serialize)]
+pub struct DatabaseInfo {
+    pub path: String,
+    pub source: String,
+    pub journal_mode: String,
+    pub foreign_keys: bool,
+    pub busy_timeout_ms: i64,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Settings {
+    database_path: Option<PathBuf>,
+}
+
+pub async fn init_db(app_handle: &AppHandle) -> Result<(DbPool, DbLocation)> {
+    let location = resolve_db_location(&app_handle.config(), app_handle.package_info(), &app_handle.env())?;
+
     // Ensure the data directory exists
-    tokio::fs::create_dir_all(&app_dir).await?;
-    
-    let db_path = app_dir.join("simple_erp.db");
-    let db_url = format!("sqlite:{}", db_path.display());
-    
-    println!("Connecting to database at: {}", db_url);
-    
-    // SQLite 연결 옵션 추가
-    let pool = SqlitePool::connect_with(
-        sqlx::sqlite::SqliteConnectOptions::new()
-            .filename(&db_path)
-            .create_if_missing(true)
-    ).await?;
-    
+    if let Some(dir) = location.path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+
+    println!("Connecting to database at: {} ({})", location.path.display(), location.source);
+
+    let pool = SqlitePool::connect_with(connect_options(&location.path)).await?;
+
     // Run migrations
-    run_migrations(&pool).await?;
-    
-    Ok(pool)
+    migrations::run_migrations(&pool, &location.path).await?;
+
+    // Refuse to start if the models no longer match the migrated tables
+    schema::verify_schema(&pool).await?;
+
+    // Databases from before enforcement may hold rows pointing at deleted
+    // parents; they stay readable, so report them rather than refuse to start
+    let orphans = sqlx::query("PRAGMA foreign_key_check").fetch_all(&pool).await?;
+    if !orphans.is_empty() {
+        eprintln!("Warning: {} rows reference missing parent rows (see PRAGMA foreign_key_check)", orphans.len());
+    }
+
+    Ok((pool, location))
 }
 
-async fn run_migrations(pool: &DbPool) -> Result<()> {
-    println!("Running database migrations...");
-
-    // Create migrations tracking table
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS _migrations (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL UNIQUE,
-            applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
-        )"
-    ).execute(pool).await?;
-
-    // Define all migration files in order
-    let migrations = vec![
-        ("001_initial.sql", include_str!("../migrations/001_initial.sql")),
-        ("002_add_payment_fields.sql", include_str!("../migrations/002_add_payment_fields.sql")),
-        ("003_add_current_balance.sql", include_str!("../migrations/003_add_current_balance.sql")),
-        ("004_add_payment_types.sql", include_str!("../migrations/004_add_payment_types.sql")),
-        ("005_add_inventory_tables.sql", include_str!("../migrations/005_add_inventory_tables.sql")),
-        ("006_add_missing_columns.sql", include_str!("../migrations/006_add_missing_columns.sql")),
-    ];
-
-    for (name, migration_sql) in migrations {
-        // Check if migration already applied
-        let applied: Option<(i64,)> = sqlx::query_as(
-            "SELECT id FROM _migrations WHERE name = ?"
-        )
-        .bind(name)
-        .fetch_optional(pool)
-        .await?;
+/// Every pooled connection enforces foreign keys (ON DELETE CASCADE in
+/// transaction_items, stock_movements, ...), lets readers run alongside the
+/// writer (WAL) and waits for locks instead of failing at once
+fn connect_options(path: &Path) -> SqliteConnectOptions {
+    SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .foreign_keys(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(BUSY_TIMEOUT)
+}
 
-        if applied.is_some() {
-            println!("Migration {} already applied, skipping", name);
-            continue;
+/// Picks the database file: the `--db-path` flag, then the
+/// `SIMPLE_ERP_DB_PATH` environment variable, then `database_path` in the
+/// settings file, then the OS app-data directory. A database left in the
+/// old `data/` folder next to the app is kept in use until one of the
+/// first three is set. Takes the app's config rather than its handle so
+/// `--dry-run` can resolve the same file before the app is built.
+pub fn resolve_db_location(config: &Config, package_info: &PackageInfo, env: &Env) -> Result<DbLocation> {
+    if let Some(path) = db_path_from_args(std::env::args()) {
+        return Ok(DbLocation { path, source: "cli".to_string() });
+    }
+
+    if let Some(path) = std::env::var_os(DB_PATH_ENV).filter(|value| !value.is_empty()) {
+        return Ok(DbLocation { path: PathBuf::from(path), source: "env".to_string() });
+    }
+
+    if let Some(config_dir) = path::app_config_dir(config) {
+        let settings_path = config_dir.join(SETTINGS_FILE);
+        if settings_path.exists() {
+            let content = std::fs::read_to_string(&settings_path)?;
+            let settings: Settings = serde_json::from_str(&content)
+                .with_context(|| format!("Invalid settings file {}", settings_path.display()))?;
+            if let Some(path) = settings.database_path {
+                // Relative paths are taken from the settings file's folder
+                return Ok(DbLocation { path: config_dir.join(path), source: "settings".to_string() });
+            }
         }
+    }
 
-        println!("Applying migration: {}", name);
+    let legacy_path = path::resource_dir(package_info, env)
+        .and_then(|dir| dir.parent().map(|parent| parent.join("data").join(DB_FILE_NAME)));
+    if let Some(path) = legacy_path.filter(|path| path.exists()) {
+        return Ok(DbLocation { path, source: "legacy".to_string() });
+    }
 
-        // Split the migration into individual statements
-        let statements: Vec<&str> = migration_sql
-            .split(';')
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty() && !s.starts_with("--"))
-            .collect();
+    let data_dir = path::app_data_dir(config).ok_or_else(|| anyhow!(
+        "Could not determine the app data directory; set {} or {}", DB_PATH_FLAG, DB_PATH_ENV
+    ))?;
 
-        for statement in statements {
-            if !statement.is_empty() {
-                println!("  Executing: {}", &statement[..50.min(statement.len())]);
-                sqlx::query(statement).execute(pool).await?;
-            }
+    Ok(DbLocation { path: data_dir.join(DB_FILE_NAME), source: "default".to_string() })
+}
+
+/// Value of `--db-path <path>` or `--db-path=<path>`
+fn db_path_from_args(mut args: impl Iterator<Item = String>) -> Option<PathBuf> {
+    while let Some(arg) = args.next() {
+        if arg == DB_PATH_FLAG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix(DB_PATH_FLAG).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(PathBuf::from(path));
         }
+    }
+    None
+}
+
+/// `--dry-run`: prints which migrations the database has and which are
+/// pending, opening it read-only so nothing is created or changed. Runs
+/// from main() before the app is built, so no window opens.
+pub async fn migration_dry_run(config: &Config, package_info: &PackageInfo) -> Result<()> {
+    let location = resolve_db_location(config, package_info, &Env::default())?;
+    println!("Database: {} ({})", location.path.display(), location.source);
 
-        // Record migration as applied
-        sqlx::query("INSERT INTO _migrations (name) VALUES (?)")
-            .bind(name)
-            .execute(pool)
+    let statuses = if location.path.exists() {
+        let mut conn = SqliteConnectOptions::new()
+            .filename(&location.path)
+            .read_only(true)
+            .connect()
             .await?;
+        migrations::migration_status(&mut conn).await?
+    } else {
+        println!("Database file does not exist yet");
+        migrations::new_database_status()
+    };
 
-        println!("Migration {} completed", name);
+    for status in &statuses {
+        println!("  {:<8} {}", status.state.label(), status.name);
     }
 
-    println!("All database migrations completed successfully!");
+    let pending = statuses
+        .iter()
+        .filter(|status| status.state == migrations::MigrationState::Pending)
+        .count();
+    println!("{} pending migration(s)", pending);
+
     Ok(())
 }
 
-// Helper function to get database path for external tools
-pub fn get_db_path(app_handle: &AppHandle) -> PathBuf {
-    let app_dir = app_handle
-        .path_resolver()
-        .resource_dir()
-        .map(|p| p.parent().unwrap().to_path_buf())
-        .unwrap_or_else(|| std::env::current_dir().unwrap())
-        .join("data");
-    app_dir.join("simple_erp.db")
+/// Location of the database and the settings its connections run with
+pub async fn get_database_info(pool: &DbPool, location: &DbLocation) -> AppResult<DatabaseInfo> {
+    let (journal_mode,): (String,) = sqlx::query_as("PRAGMA journal_mode").fetch_one(pool).await?;
+    let (foreign_keys,): (i64,) = sqlx::query_as("PRAGMA foreign_keys").fetch_one(pool).await?;
+    let (busy_timeout_ms,): (i64,) = sqlx::query_as("PRAGMA busy_timeout").fetch_one(pool).await?;
+    let size_bytes = tokio::fs::metadata(&location.path).await?.len();
+
+    Ok(DatabaseInfo {
+        path: location.path.display().to_string(),
+        source: location.source.clone(),
+        journal_mode,
+        foreign_keys: foreign_keys != 0,
+        busy_timeout_ms,
+        size_bytes,
+    })
+}
+
+/// A fresh in-memory database with every migration applied, for tests.
+/// Each `:memory:` connection is a separate database, so the pool keeps
+/// exactly one connection open for its whole life.
+#[cfg(test)]
+pub async fn test_pool() -> DbPool {
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(
+            "sqlite::memory:"
+                .parse::<SqliteConnectOptions>()
+                .expect("in-memory database options")
+                .foreign_keys(true)
+        )
+        .await
+        .expect("open in-memory database");
+
+    migrations::run_migrations(&pool, Path::new(":memory:"))
+        .await
+        .expect("apply migrations");
+    schema::verify_schema(&pool).await.expect("models match the migrated schema");
+
+    pool
+}
+
+/// A migrated database file in the temp directory, opened like `init_db`
+/// opens the real one, for tests that need several connections at once.
+/// The file is removed when this is dropped.
+#[cfg(test)]
+pub struct TestDatabase {
+    pub pool: DbPool,
+    path: PathBuf,
+}
+
+#[cfg(test)]
+impl TestDatabase {
+    pub async fn new(name: &str) -> TestDatabase {
+        let path = std::env::temp_dir().join(format!("simple_erp_{}_{}.db", name, std::process::id()));
+        let database = TestDatabase { pool: SqlitePool::connect_lazy_with(connect_options(&path)), path };
+        database.remove_files();
+
+        migrations::run_migrations(&database.pool, &database.path)
+            .await
+            .expect("apply migrations");
+        database
+    }
+
+    fn remove_files(&self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.path.display(), suffix));
+        }
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        self.remove_files();
+    }
 }
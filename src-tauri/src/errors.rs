@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::error::ErrorKind;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -15,8 +17,11 @@ pub enum AppError {
     #[error("Not found: {0}")]
     NotFound(String),
     
-    #[error("Validation error: {0}")]
-    Validation(String),
+    #[error("Validation error: {message}")]
+    Validation {
+        message: String,
+        field: Option<String>, // request field that failed, e.g. "items[1].quantity"
+    },
     
     #[error("Business logic error: {0}")]
     Business(String),
@@ -25,30 +30,54 @@ pub enum AppError {
     External(String),
 }
 
+impl AppError {
+    /// Validation error that is not about one particular field
+    pub fn validation(message: impl Into<String>) -> Self {
+        AppError::Validation { message: message.into(), field: None }
+    }
+
+    /// Validation error about one request field
+    pub fn invalid_field(field: impl Into<String>, message: impl Into<String>) -> Self {
+        AppError::Validation { message: message.into(), field: Some(field.into()) }
+    }
+
+    /// Places a field-level validation error under `prefix`, so an error on
+    /// `quantity` from the second item reads `items[1].quantity`
+    pub fn within(self, prefix: &str) -> Self {
+        match self {
+            AppError::Validation { message, field: Some(field) } => AppError::Validation {
+                message,
+                field: Some(format!("{}.{}", prefix, field)),
+            },
+            other => other,
+        }
+    }
+}
+
+/// Error sent to the frontend when a command fails. `code` is stable and
+/// meant for branching on; `error` is a message for display; `details`
+/// carries whatever else is known (the failing field, the violated
+/// constraint or the underlying error text).
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub error: String,
     pub code: String,
-    pub details: Option<String>,
+    pub details: Option<serde_json::Value>,
 }
 
 impl From<AppError> for ErrorResponse {
     fn from(err: AppError) -> Self {
         match err {
-            AppError::Database(e) => ErrorResponse {
-                error: "Database operation failed".to_string(),
-                code: "DATABASE_ERROR".to_string(),
-                details: Some(e.to_string()),
-            },
+            AppError::Database(e) => database_error_response(e),
             AppError::NotFound(msg) => ErrorResponse {
                 error: msg,
                 code: "NOT_FOUND".to_string(),
                 details: None,
             },
-            AppError::Validation(msg) => ErrorResponse {
-                error: msg,
+            AppError::Validation { message, field } => ErrorResponse {
+                error: message,
                 code: "VALIDATION_ERROR".to_string(),
-                details: None,
+                details: field.map(|field| json!({ "field": field })),
             },
             AppError::Business(msg) => ErrorResponse {
                 error: msg,
@@ -63,15 +92,188 @@ impl From<AppError> for ErrorResponse {
             AppError::Io(e) => ErrorResponse {
                 error: "File system operation failed".to_string(),
                 code: "IO_ERROR".to_string(),
-                details: Some(e.to_string()),
+                details: Some(json!(e.to_string())),
             },
             AppError::Serialization(e) => ErrorResponse {
                 error: "Data serialization failed".to_string(),
                 code: "SERIALIZATION_ERROR".to_string(),
-                details: Some(e.to_string()),
+                details: Some(json!(e.to_string())),
             },
         }
     }
 }
 
+/// Constraint violations get their own codes so the frontend can tell e.g.
+/// a duplicate product code (UNIQUE_VIOLATION on products.code) from an
+/// invalid status (CHECK_VIOLATION) without parsing SQLite's message.
+fn database_error_response(err: sqlx::Error) -> ErrorResponse {
+    let db_err = match &err {
+        sqlx::Error::RowNotFound => {
+            return ErrorResponse {
+                error: "Record not found".to_string(),
+                code: "NOT_FOUND".to_string(),
+                details: None,
+            };
+        }
+        sqlx::Error::Database(db_err) => db_err,
+        _ => {
+            return ErrorResponse {
+                error: "Database operation failed".to_string(),
+                code: "DATABASE_ERROR".to_string(),
+                details: Some(json!(err.to_string())),
+            };
+        }
+    };
+
+    let message = db_err.message().to_string();
+    // SQLite reports the columns after the colon, e.g.
+    // "UNIQUE constraint failed: products.code" or
+    // "CHECK constraint failed: status IN ('draft', 'confirmed', 'cancelled')"
+    let target = message.split_once(": ").map(|(_, target)| target.to_string());
+    let columns: Vec<String> = target
+        .as_deref()
+        .map(|target| target.split(", ").map(str::to_string).collect())
+        .unwrap_or_default();
+
+    let (code, error) = match db_err.kind() {
+        ErrorKind::UniqueViolation => ("UNIQUE_VIOLATION", "A record with the same value already exists"),
+        ErrorKind::CheckViolation => ("CHECK_VIOLATION", "A value is not allowed"),
+        ErrorKind::ForeignKeyViolation => (
+            "FOREIGN_KEY_VIOLATION",
+            "The referenced record does not exist or is still in use",
+        ),
+        ErrorKind::NotNullViolation => ("NOT_NULL_VIOLATION", "A required value is missing"),
+        _ => {
+            return ErrorResponse {
+                error: "Database operation failed".to_string(),
+                code: "DATABASE_ERROR".to_string(),
+                details: Some(json!(err.to_string())),
+            };
+        }
+    };
+
+    let details = match db_err.kind() {
+        ErrorKind::UniqueViolation | ErrorKind::NotNullViolation => json!({
+            "message": message,
+            "columns": columns,
+        }),
+        ErrorKind::CheckViolation => json!({
+            "message": message,
+            "constraint": target,
+        }),
+        _ => json!({ "message": message }),
+    };
+
+    ErrorResponse {
+        error: error.to_string(),
+        code: code.to_string(),
+        details: Some(details),
+    }
+}
+
 pub type AppResult<T> = Result<T, AppError>;
+
+/// Result type of Tauri commands
+pub type CommandResult<T> = Result<T, ErrorResponse>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{test_pool, DbPool};
+
+    /// The response a failing statement produces in a command
+    async fn response_to(pool: &DbPool, sql: &str) -> ErrorResponse {
+        let err = sqlx::query(sql).execute(pool).await.expect_err("statement should fail");
+        ErrorResponse::from(AppError::from(err))
+    }
+
+    #[test]
+    fn invalid_field_names_the_field() {
+        let response = ErrorResponse::from(AppError::invalid_field("quantity", "Quantity must be positive"));
+
+        assert_eq!(response.code, "VALIDATION_ERROR");
+        assert_eq!(response.error, "Quantity must be positive");
+        assert_eq!(response.details, Some(json!({ "field": "quantity" })));
+
+        let response = ErrorResponse::from(AppError::validation("Select at least one item"));
+        assert_eq!(response.code, "VALIDATION_ERROR");
+        assert_eq!(response.details, None);
+    }
+
+    #[test]
+    fn within_prefixes_field_errors_only() {
+        let nested = AppError::invalid_field("quantity", "Quantity must be positive")
+            .within("items[1]")
+            .within("orders[0]");
+        assert!(matches!(
+            &nested,
+            AppError::Validation { message, field: Some(field) }
+                if message == "Quantity must be positive" && field == "orders[0].items[1].quantity"
+        ));
+
+        assert!(matches!(
+            AppError::validation("Select at least one item").within("items[0]"),
+            AppError::Validation { field: None, .. }
+        ));
+        assert!(matches!(
+            AppError::Business("Only drafts can be edited".to_string()).within("items[0]"),
+            AppError::Business(message) if message == "Only drafts can be edited"
+        ));
+    }
+
+    #[tokio::test]
+    async fn unique_violation_lists_the_columns() {
+        let pool = test_pool().await;
+        sqlx::query("INSERT INTO products (name, code) VALUES ('한우 등심', 'BEEF-001')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let response = response_to(&pool, "INSERT INTO products (name, code) VALUES ('한우 안심', 'BEEF-001')").await;
+
+        assert_eq!(response.code, "UNIQUE_VIOLATION");
+        assert_eq!(response.error, "A record with the same value already exists");
+        let details = response.details.unwrap();
+        assert_eq!(details["columns"], json!(["products.code"]));
+        assert_eq!(details["message"], json!("UNIQUE constraint failed: products.code"));
+    }
+
+    #[tokio::test]
+    async fn check_violation_names_the_constraint() {
+        let pool = test_pool().await;
+
+        let response = response_to(
+            &pool,
+            "INSERT INTO customers (name, customer_type) VALUES ('맛있는식당', 'partner')",
+        ).await;
+
+        assert_eq!(response.code, "CHECK_VIOLATION");
+        assert_eq!(response.error, "A value is not allowed");
+        assert_eq!(
+            response.details.unwrap()["constraint"],
+            json!("customer_type IN ('customer', 'supplier')")
+        );
+    }
+
+    #[tokio::test]
+    async fn foreign_key_violation_has_its_own_code() {
+        let pool = test_pool().await;
+
+        let response = response_to(
+            &pool,
+            "INSERT INTO transactions (transaction_number, customer_id, transaction_type, transaction_date) \
+             VALUES ('S-TEST-0001', 999999, 'sale', '2026-03-02T01:00:00Z')",
+        ).await;
+
+        assert_eq!(response.code, "FOREIGN_KEY_VIOLATION");
+        assert_eq!(response.error, "The referenced record does not exist or is still in use");
+        assert_eq!(response.details, Some(json!({ "message": "FOREIGN KEY constraint failed" })));
+    }
+
+    #[test]
+    fn row_not_found_is_not_found() {
+        let response = ErrorResponse::from(AppError::from(sqlx::Error::RowNotFound));
+        assert_eq!(response.code, "NOT_FOUND");
+        assert_eq!(response.details, None);
+    }
+}
@@ -0,0 +1,99 @@
+//! Records for service tests, created through the services the way the UI
+//! creates them. Pair with `database::test_pool()`.
+
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+
+use crate::database::DbPool;
+use crate::models::{Company, Customer, Product, TransactionWithItems};
+use crate::services::{company, customer, product, transaction};
+
+/// Date of the transactions below: 2026-03-02 10:00 KST
+pub const TRANSACTION_DATE: &str = "2026-03-02T01:00:00Z";
+
+/// The supplier company, 한우상회 (105-87-12345). Migration 001 seeds a
+/// sample company under 123-45-67890, so that number cannot be reused.
+pub async fn company(pool: &DbPool) -> Company {
+    company::create_company(pool, serde_json::from_value(json!({
+        "name": "한우상회",
+        "business_number": "105-87-12345",
+        "address": "서울시 중구 세종대로 1",
+        "representative": "홍길동",
+        "business_type": "도소매",
+        "business_item": "정육",
+    })).unwrap()).await.unwrap()
+}
+
+/// A restaurant buying from the company
+pub async fn buyer(pool: &DbPool) -> Customer {
+    customer::create_customer(pool, serde_json::from_value(json!({
+        "name": "맛있는식당",
+        "business_number": "234-56-78901",
+        "representative": "김철수",
+        "customer_type": "customer",
+    })).unwrap()).await.unwrap()
+}
+
+/// A farm the company buys from
+pub async fn supplier(pool: &DbPool) -> Customer {
+    customer::create_customer(pool, serde_json::from_value(json!({
+        "name": "한우농장",
+        "business_number": "345-67-89012",
+        "representative": "박영희",
+        "customer_type": "supplier",
+    })).unwrap()).await.unwrap()
+}
+
+/// 한우 등심 at 10,000 won/kg with 10% VAT, not stocked
+pub async fn beef(pool: &DbPool) -> Product {
+    product_with(pool, json!({})).await
+}
+
+/// 한우 등심 with inventory management, received into lots by expiry date
+pub async fn stocked_beef(pool: &DbPool) -> Product {
+    product_with(pool, json!({ "use_inventory_management": true })).await
+}
+
+/// 한우 등심 with the fields of `overrides` replaced
+pub async fn product_with(pool: &DbPool, overrides: Value) -> Product {
+    let mut request = json!({
+        "name": "한우 등심",
+        "unit_price": 10000,
+        "unit": "kg",
+        "tax_rate": 0.1,
+    });
+    request.as_object_mut().unwrap().extend(overrides.as_object().unwrap().clone());
+    product::create_product(pool, serde_json::from_value(request).unwrap()).await.unwrap()
+}
+
+/// A draft transaction of `items`, dated `TRANSACTION_DATE`
+pub async fn draft(pool: &DbPool, transaction_type: &str, customer_id: i64, items: Value) -> TransactionWithItems {
+    transaction::create_transaction(pool, serde_json::from_value(json!({
+        "customer_id": customer_id,
+        "transaction_type": transaction_type,
+        "transaction_date": TRANSACTION_DATE,
+        "items": items,
+    })).unwrap()).await.unwrap()
+}
+
+/// A confirmed sale of `quantity` at the product's price
+pub async fn confirmed_sale(pool: &DbPool, customer_id: i64, product_id: i64, quantity: f64) -> TransactionWithItems {
+    let sale = draft(pool, "sale", customer_id, json!([
+        { "product_id": product_id, "quantity": quantity, "tax_rate": 0.1 },
+    ])).await;
+    transaction::confirm_transaction(pool, sale.transaction.id).await.unwrap()
+}
+
+/// A confirmed purchase of `quantity`, received into a lot expiring on `expiry_date`
+pub async fn confirmed_purchase(
+    pool: &DbPool,
+    supplier_id: i64,
+    product_id: i64,
+    quantity: f64,
+    expiry_date: NaiveDate,
+) -> TransactionWithItems {
+    let purchase = draft(pool, "purchase", supplier_id, json!([
+        { "product_id": product_id, "quantity": quantity, "tax_rate": 0.1, "expiry_date": expiry_date },
+    ])).await;
+    transaction::confirm_transaction(pool, purchase.transaction.id).await.unwrap()
+}
@@ -3,7 +3,12 @@
 
 mod database;
 mod errors;
+#[cfg(test)]
+mod fixtures;
+mod migrations;
 mod models;
+mod money;
+mod schema;
 mod services;
 mod commands;
 
@@ -13,15 +18,50 @@ use tauri::Manager;
 
 #[tokio::main]
 async fn main() {
+    let context = tauri::generate_context!();
+
+    // List pending migrations and exit without touching the database or opening a window
+    if migrations::dry_run_requested() {
+        let code = match database::migration_dry_run(context.config(), context.package_info()).await {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Failed to read migration status: {}", e);
+                1
+            }
+        };
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
         .setup(|app| {
             // Initialize database
             let handle = app.handle();
             tauri::async_runtime::spawn(async move {
                 match init_db(&handle).await {
-                    Ok(pool) => {
-                        // Store the database pool in app state
+                    Ok((pool, location)) => {
+                        // Daily stock and quotation expiry check: once at startup, then every 24 hours
+                        let expiry_pool = pool.clone();
+                        tauri::async_runtime::spawn(async move {
+                            let mut interval = tokio::time::interval(services::expiry::EXPIRY_CHECK_INTERVAL);
+                            loop {
+                                interval.tick().await;
+                                match services::expiry::run_expiry_check(&expiry_pool).await {
+                                    Ok(result) => println!(
+                                        "Expiry check: {} lots and {} inventories expired, {} written off",
+                                        result.lots_expired, result.inventories_expired, result.quantity_written_off
+                                    ),
+                                    Err(e) => eprintln!("Expiry check failed: {}", e),
+                                }
+                                match services::quotation::expire_quotations(&expiry_pool).await {
+                                    Ok(expired) => println!("Quotation check: {} quotations expired", expired),
+                                    Err(e) => eprintln!("Quotation expiry check failed: {}", e),
+                                }
+                            }
+                        });
+                        
+                        // Store the database pool and its location in app state
                         handle.manage(pool);
+                        handle.manage(location);
                         println!("Database initialized successfully!");
                     }
                     Err(e) => {
@@ -38,6 +78,9 @@ async fn main() {
             create_company,
             update_company,
             
+            // Database commands
+            get_database_info,
+            
             // Customer commands
             get_customers,
             get_customer_by_id,
@@ -54,17 +97,91 @@ async fn main() {
             delete_product,
             search_products,
             get_products_by_category,
+            get_products_for_tax_review,
             
             // Transaction commands
             get_transactions,
             get_transaction_by_id,
             create_transaction,
             update_transaction,
+            update_transaction_items,
             delete_transaction,
             confirm_transaction,
             cancel_transaction,
             get_transaction_summary,
+            rebuild_balances,
+            
+            // Transaction numbering commands
+            preview_transaction_number,
+            renumber_draft_transactions,
+            get_transaction_number_settings,
+            update_transaction_number_setting,
+            
+            // Payment commands
+            record_receipt,
+            record_disbursement,
+            get_payment_by_id,
+            get_open_transactions,
+            get_customer_ledger,
+            
+            // Price book commands
+            get_customer_product_prices,
+            get_customer_product_price,
+            set_customer_product_price,
+            get_price_history,
+            
+            // Inventory commands
+            get_inventory,
+            get_inventory_by_product,
+            update_inventory,
+            create_stock_movement,
+            get_stock_movements,
+            get_active_lots,
+            get_all_lots,
+            get_transaction_lot_consumptions,
+            get_expiry_alerts,
+            run_expiry_check,
+            
+            // Tax invoice commands
+            issue_tax_invoice,
+            list_tax_invoices,
+            void_tax_invoice,
+            amend_tax_invoice,
+            get_tax_invoice_chain,
+            mark_invoice_sent,
+            export_tax_invoice_xml,
+            
+            // Traceability commands
+            trace_by_traceability_number,
+            validate_traceability_number,
+            
+            // Return commands
+            create_return,
+            get_transaction_returns,
+            
+            // Quotation commands
+            get_quotations,
+            get_quotation_by_id,
+            create_quotation,
+            update_quotation_status,
+            delete_quotation,
+            convert_quotation_to_transaction,
+            expire_quotations,
+            
+            // Order commands
+            get_orders,
+            get_order_by_id,
+            create_order,
+            fulfil_order,
+            close_order,
+            
+            // Document commands
+            export_delivery_note,
+            export_delivery_notes,
+            
+            // Report commands
+            get_aging_report,
         ])
-        .run(tauri::generate_context!())
+        .run(context)
         .expect("error while running tauri application");
 }
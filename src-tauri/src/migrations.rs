@@ -0,0 +1,269 @@
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use sqlx::{Connection, Executor, SqliteConnection};
+use std::path::{Path, PathBuf};
+
+use crate::database::DbPool;
+
+/// Command-line flag that lists pending migrations and exits without applying them
+pub const DRY_RUN_FLAG: &str = "--dry-run";
+
+/// All migrations in the order they are applied. Applied files are pinned by
+/// checksum, so a schema change always goes into a new file.
+const MIGRATIONS: &[(&str, &str)] = &[
+    ("001_initial.sql", include_str!("../migrations/001_initial.sql")),
+    ("002_add_payment_fields.sql", include_str!("../migrations/002_add_payment_fields.sql")),
+    ("003_add_current_balance.sql", include_str!("../migrations/003_add_current_balance.sql")),
+    ("004_add_payment_types.sql", include_str!("../migrations/004_add_payment_types.sql")),
+    ("005_add_inventory_tables.sql", include_str!("../migrations/005_add_inventory_tables.sql")),
+    ("006_add_missing_columns.sql", include_str!("../migrations/006_add_missing_columns.sql")),
+    ("007_reconcile_schema.sql", include_str!("../migrations/007_reconcile_schema.sql")),
+    ("008_transaction_numbering.sql", include_str!("../migrations/008_transaction_numbering.sql")),
+    ("009_payment_allocations.sql", include_str!("../migrations/009_payment_allocations.sql")),
+    ("010_add_due_date.sql", include_str!("../migrations/010_add_due_date.sql")),
+    ("011_price_book.sql", include_str!("../migrations/011_price_book.sql")),
+    ("012_inventory_real_columns.sql", include_str!("../migrations/012_inventory_real_columns.sql")),
+    ("013_stock_lots.sql", include_str!("../migrations/013_stock_lots.sql")),
+    ("014_expiry_write_off.sql", include_str!("../migrations/014_expiry_write_off.sql")),
+    ("015_traceability_validation.sql", include_str!("../migrations/015_traceability_validation.sql")),
+    ("016_tax_invoice_lifecycle.sql", include_str!("../migrations/016_tax_invoice_lifecycle.sql")),
+    ("017_tax_invoice_amendments.sql", include_str!("../migrations/017_tax_invoice_amendments.sql")),
+    ("018_quotations.sql", include_str!("../migrations/018_quotations.sql")),
+    ("019_orders.sql", include_str!("../migrations/019_orders.sql")),
+    ("020_returns.sql", include_str!("../migrations/020_returns.sql")),
+    ("021_integer_money.sql", include_str!("../migrations/021_integer_money.sql")),
+    ("022_vat_categories.sql", include_str!("../migrations/022_vat_categories.sql")),
+    ("023_product_track_expiry.sql", include_str!("../migrations/023_product_track_expiry.sql")),
+    ("024_order_price_mode.sql", include_str!("../migrations/024_order_price_mode.sql")),
+    ("025_traceability_warnings.sql", include_str!("../migrations/025_traceability_warnings.sql")),
+    ("026_document_font.sql", include_str!("../migrations/026_document_font.sql")),
+    ("027_rounded_totals.sql", include_str!("../migrations/027_rounded_totals.sql")),
+    ("028_zero_rate_products.sql", include_str!("../migrations/028_zero_rate_products.sql")),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    Modified, // file changed after it was applied
+    Unknown,  // applied by another version of the app, no such file here
+}
+
+impl MigrationState {
+    pub fn label(self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "MODIFIED",
+            MigrationState::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub name: String,
+    pub state: MigrationState,
+}
+
+pub fn dry_run_requested() -> bool {
+    std::env::args().any(|arg| arg == DRY_RUN_FLAG)
+}
+
+/// SHA-256 of a migration file. Line endings are normalised so a checkout
+/// with CRLF endings matches one with LF.
+fn checksum(sql: &str) -> String {
+    Sha256::digest(sql.replace("\r\n", "\n").as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub async fn run_migrations(pool: &DbPool, db_path: &Path) -> Result<()> {
+    println!("Running database migrations...");
+
+    // Table-rebuild migrations (004, 007, 021) drop and recreate parent tables.
+    // With foreign keys enforced, the implicit DELETE of DROP TABLE would
+    // cascade into child tables, so all migrations run on one dedicated
+    // connection with enforcement switched off (PRAGMA is per-connection
+    // and has no effect inside a transaction, so it is set out here).
+    let mut conn = pool.acquire().await?;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *conn).await?;
+
+    let result = apply_migrations(&mut conn, db_path).await;
+
+    sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *conn).await?;
+    result?;
+
+    println!("All database migrations completed successfully!");
+    Ok(())
+}
+
+async fn apply_migrations(conn: &mut SqliteConnection, db_path: &Path) -> Result<()> {
+    // Create migrations tracking table
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS _migrations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            checksum TEXT,
+            applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )"
+    ).execute(&mut *conn).await?;
+
+    if !has_checksum_column(conn).await? {
+        sqlx::query("ALTER TABLE _migrations ADD COLUMN checksum TEXT")
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    // Migrations applied before checksums were recorded are taken as they are now
+    for (name, sql) in MIGRATIONS {
+        sqlx::query("UPDATE _migrations SET checksum = ? WHERE name = ? AND checksum IS NULL")
+            .bind(checksum(sql))
+            .bind(*name)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let statuses = compare(&applied_migrations(conn).await?);
+
+    let problems: Vec<String> = statuses
+        .iter()
+        .filter_map(|status| match status.state {
+            MigrationState::Modified => Some(format!("{} was changed after it was applied", status.name)),
+            MigrationState::Unknown => Some(format!("{} is not known to this version of the app", status.name)),
+            _ => None,
+        })
+        .collect();
+    if !problems.is_empty() {
+        bail!("Refusing to migrate the database:\n  {}", problems.join("\n  "));
+    }
+
+    let pending: Vec<&str> = statuses
+        .iter()
+        .filter(|status| status.state == MigrationState::Pending)
+        .map(|status| status.name.as_str())
+        .collect();
+
+    if pending.is_empty() {
+        println!("No pending migrations");
+        return Ok(());
+    }
+
+    // A new database has nothing to lose
+    if pending.len() < MIGRATIONS.len() {
+        let backup_path = backup_database(conn, db_path).await?;
+        println!("Backed up database to {}", backup_path.display());
+    }
+
+    for (name, sql) in MIGRATIONS.iter().filter(|(name, _)| pending.contains(name)) {
+        println!("Applying migration: {}", name);
+
+        // The whole file runs as one multi-statement batch, so semicolons in
+        // triggers and string literals stay intact. If any statement fails
+        // the transaction is dropped and none of the migration remains.
+        let mut tx = conn.begin().await?;
+
+        (&mut *tx).execute(*sql)
+            .await
+            .with_context(|| format!("Migration {} failed and was rolled back", name))?;
+
+        sqlx::query("INSERT INTO _migrations (name, checksum) VALUES (?, ?)")
+            .bind(*name)
+            .bind(checksum(sql))
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        println!("Migration {} completed", name);
+    }
+
+    Ok(())
+}
+
+/// Status of every migration, without writing to the database
+pub async fn migration_status(conn: &mut SqliteConnection) -> Result<Vec<MigrationStatus>> {
+    let table: Option<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = '_migrations'"
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if table.is_none() {
+        return Ok(new_database_status());
+    }
+
+    Ok(compare(&applied_migrations(conn).await?))
+}
+
+/// Status for a database that does not exist yet: everything is pending
+pub fn new_database_status() -> Vec<MigrationStatus> {
+    compare(&[])
+}
+
+async fn has_checksum_column(conn: &mut SqliteConnection) -> Result<bool> {
+    let columns: Vec<(String,)> = sqlx::query_as("SELECT name FROM pragma_table_info('_migrations')")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(columns.iter().any(|(name,)| name == "checksum"))
+}
+
+/// Applied migration names with their recorded checksums, in the order applied
+async fn applied_migrations(conn: &mut SqliteConnection) -> Result<Vec<(String, Option<String>)>> {
+    let query = if has_checksum_column(conn).await? {
+        "SELECT name, checksum FROM _migrations ORDER BY id"
+    } else {
+        "SELECT name, NULL FROM _migrations ORDER BY id"
+    };
+
+    Ok(sqlx::query_as(query).fetch_all(&mut *conn).await?)
+}
+
+fn compare(applied: &[(String, Option<String>)]) -> Vec<MigrationStatus> {
+    let mut statuses: Vec<MigrationStatus> = MIGRATIONS
+        .iter()
+        .map(|(name, sql)| {
+            let state = match applied.iter().find(|(applied_name, _)| applied_name == name) {
+                None => MigrationState::Pending,
+                Some((_, Some(recorded))) if *recorded != checksum(sql) => MigrationState::Modified,
+                Some(_) => MigrationState::Applied,
+            };
+            MigrationStatus { name: name.to_string(), state }
+        })
+        .collect();
+
+    statuses.extend(
+        applied
+            .iter()
+            .filter(|(applied_name, _)| !MIGRATIONS.iter().any(|(name, _)| name == applied_name))
+            .map(|(applied_name, _)| MigrationStatus {
+                name: applied_name.clone(),
+                state: MigrationState::Unknown,
+            }),
+    );
+
+    statuses
+}
+
+/// Copies the database into `backups/` next to it. VACUUM INTO writes a
+/// consistent snapshot, including pages still in the WAL file.
+async fn backup_database(conn: &mut SqliteConnection, db_path: &Path) -> Result<PathBuf> {
+    let backup_dir = db_path.parent().unwrap_or_else(|| Path::new(".")).join("backups");
+    tokio::fs::create_dir_all(&backup_dir).await?;
+
+    let stem = db_path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("simple_erp");
+    let backup_path = backup_dir.join(format!(
+        "{}-pre-migration-{}.db", stem, Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(backup_path.to_string_lossy().into_owned())
+        .execute(&mut *conn)
+        .await
+        .with_context(|| format!("Could not back up the database to {}", backup_path.display()))?;
+
+    Ok(backup_path)
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::money::Money;
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Company {
@@ -10,8 +12,14 @@ pub struct Company {
     pub phone: Option<String>,
     pub email: Option<String>,
     pub representative: String,
-    pub business_type: String,
-    pub business_category: String,
+    pub business_type: Option<String>, // 업태
+    pub business_item: Option<String>, // 종목
+    pub default_invoice_memo: Option<String>,
+    pub write_off_expired_stock: bool, // daily expiry check removes expired stock
+    pub traceability_validation: String, // 'reject' or 'warn' on malformed 이력번호
+    pub vat_rounding_scope: String, // 'line' (VAT rounded per item) or 'document' (once per document)
+    pub vat_rounding_mode: String, // 'round' or 'floor' (절사)
+    pub document_font_path: Option<String>, // TrueType font embedded in PDF documents
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -25,7 +33,12 @@ pub struct Customer {
     pub address: Option<String>,
     pub phone: Option<String>,
     pub email: Option<String>,
+    pub business_type: Option<String>,
+    pub business_item: Option<String>,
+    pub contact_person: Option<String>,
     pub customer_type: String, // 'customer' or 'supplier'
+    pub outstanding_balance: Money,
+    pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -36,10 +49,18 @@ pub struct Product {
     pub name: String,
     pub code: Option<String>,
     pub description: Option<String>,
-    pub unit_price: f64,
+    pub unit_price: Money,
     pub unit: String, // 'unit', 'kg', 'box', etc.
-    pub tax_rate: f64, // 0.1 for 10%
+    pub tax_rate: f64, // 0.1 for 10%; applies to taxable items only
+    pub tax_category: String, // 'taxable', 'zero_rated' (영세율) or 'exempt' (면세)
     pub category: Option<String>,
+    pub traceability_number: Option<String>, // 기본 이력번호
+    pub traceability_warning: Option<String>, // 형식 검사 경고 (warn 모드)
+    pub origin: Option<String>,
+    pub slaughterhouse: Option<String>,
+    pub use_inventory_management: bool,
+    pub lot_consumption: String, // 'fefo' (earliest expiry first) or 'fifo' (oldest lot first)
+    pub track_expiry: bool, // purchases need an expiry date and create lots
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -48,15 +69,23 @@ pub struct Product {
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Transaction {
     pub id: i64,
+    pub transaction_number: String,
     pub customer_id: i64,
-    pub transaction_type: String, // 'sale' or 'purchase'
+    pub transaction_type: String, // 'sale', 'purchase', 'payment_in', 'payment_out'
     pub transaction_date: DateTime<Utc>,
-    pub total_amount: f64,
-    pub tax_amount: f64,
+    pub due_date: Option<DateTime<Utc>>,
+    pub price_mode: String, // 'exclusive' (VAT on top of unit prices) or 'inclusive'
+    pub subtotal: Money,      // supply value of taxable and zero-rated items
+    pub tax_amount: Money,
+    pub exempt_amount: Money, // value of exempt items
+    pub total_amount: Money,  // subtotal + tax_amount + exempt_amount
     pub status: String, // 'draft', 'confirmed', 'cancelled'
     pub notes: Option<String>,
+    pub reference_payment_id: Option<i64>,
+    pub current_balance: Money,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub return_of_transaction_id: Option<i64>, // set on returns: the sale or purchase being returned
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -65,11 +94,22 @@ pub struct TransactionItem {
     pub transaction_id: i64,
     pub product_id: i64,
     pub quantity: f64,
-    pub unit_price: f64,
+    pub unit_price: Money,
     pub tax_rate: f64,
-    pub subtotal: f64,
-    pub tax_amount: f64,
-    pub total_amount: f64,
+    pub tax_category: String, // copied from the product unless overridden
+    pub subtotal: Money,      // supply value (VAT taken out for inclusive prices)
+    pub tax_amount: Money,
+    pub total_amount: Money,
+    pub traceability_number: Option<String>,
+    pub traceability_warning: Option<String>,
+    pub origin: Option<String>,
+    pub slaughterhouse: Option<String>,
+    pub expiry_date: Option<NaiveDate>, // purchases: expiry of the lot the item creates
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub sales_order_item_id: Option<i64>,    // order line this sale item delivers
+    pub purchase_order_item_id: Option<i64>, // order line this purchase item receives
+    pub returned_item_id: Option<i64>,       // on return items: the original item
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -97,13 +137,228 @@ pub struct TaxInvoice {
     pub supplier_name: String,
     pub buyer_business_number: String,
     pub buyer_name: String,
-    pub total_amount: f64,
-    pub tax_amount: f64,
-    pub status: String, // 'issued', 'sent', 'received'
+    pub supply_amount: Money,
+    pub tax_amount: Money,
+    pub total_amount: Money,
+    pub status: String, // 'issued', 'sent', 'received', 'void'
     pub pdf_path: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub voided_at: Option<DateTime<Utc>>,
+    pub void_reason: Option<String>,
+    pub original_invoice_id: Option<i64>, // set on amended invoices (수정세금계산서)
+    pub amendment_reason: Option<String>, // 'return', 'price_change', 'cancellation', 'duplicate'
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TransactionNumberSetting {
+    pub company_id: i64,
+    pub transaction_type: String,
+    pub prefix: String, // 'S' -> S-20261017-0001
+    pub sequence_digits: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PaymentAllocation {
+    pub id: i64,
+    pub payment_id: i64,     // payment_in / payment_out transaction
+    pub transaction_id: i64, // sale / purchase it pays off
+    pub amount: Money,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentWithAllocations {
+    #[serde(flatten)]
+    pub transaction: Transaction,
+    pub allocations: Vec<PaymentAllocation>,
+    pub customer: Customer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CustomerProductPrice {
+    pub id: i64,
+    pub customer_id: i64,
+    pub product_id: i64,
+    pub current_price_per_kg: Money,
+    pub last_updated: DateTime<Utc>,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PriceHistory {
+    pub id: i64,
+    pub customer_id: i64,
+    pub product_id: i64,
+    pub old_price: Money,
+    pub new_price: Money,
+    pub changed_date: DateTime<Utc>,
+    pub transaction_id: Option<i64>, // set when a confirmed transaction changed the price
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProductInventory {
+    pub id: i64,
+    pub product_id: i64,
+    pub current_stock: f64,
+    pub safety_stock: f64,
+    pub location: String, // 'frozen', 'cold', 'room'
+    pub expiry_date: Option<NaiveDate>,
+    pub last_updated: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryWithProduct {
+    #[serde(flatten)]
+    pub inventory: ProductInventory,
+    pub product_name: String,
+    pub product_unit: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StockMovement {
+    pub id: i64,
+    pub product_id: i64,
+    pub movement_type: String, // 'in', 'out', 'adjust', 'expired'
+    pub quantity: f64,         // positive; signed stock change for 'adjust'
+    pub unit_price: Option<Money>,
+    pub lot_number: Option<String>,
+    pub expiry_date: Option<NaiveDate>,
+    pub traceability_number: Option<String>,
+    pub traceability_warning: Option<String>,
+    pub origin: Option<String>,
+    pub slaughterhouse: Option<String>,
+    pub transaction_id: Option<i64>,
+    pub reference_type: Option<String>, // 'purchase', 'sales', 'manual', 'adjustment', 'cancellation'
+    pub reference_id: Option<i64>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StockLot {
+    pub id: i64,
+    pub product_id: i64,
+    pub lot_number: String, // LOT-20261017-001
+    pub initial_quantity: f64,
+    pub remaining_quantity: f64,
+    pub expiry_date: NaiveDate,
+    pub traceability_number: Option<String>,
+    pub origin: Option<String>,
+    pub slaughterhouse: Option<String>,
+    pub supplier_id: Option<i64>,
+    pub status: String, // 'active', 'expired', 'finished', 'cancelled'
+    pub created_at: DateTime<Utc>,
+    pub transaction_item_id: Option<i64>, // purchase item that brought the lot in
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StockLotConsumption {
+    pub id: i64,
+    pub transaction_item_id: i64, // sale item
+    pub lot_id: i64,
+    pub quantity: f64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Quotation {
+    pub id: i64,
+    pub quotation_number: String, // Q-20261017-0001
+    pub customer_id: i64,
+    pub quotation_date: DateTime<Utc>,
+    pub valid_until: NaiveDate, // last day the quote can be accepted
+    pub price_mode: String, // 'exclusive' or 'inclusive', carried over to the sale
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub exempt_amount: Money,
+    pub total_amount: Money,
+    pub status: String, // 'draft', 'sent', 'accepted', 'expired', 'rejected'
+    pub transaction_id: Option<i64>, // draft sale created on conversion
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct QuotationItem {
+    pub id: i64,
+    pub quotation_id: i64,
+    pub product_id: i64,
+    pub quantity: f64,
+    pub unit_price: Money,
+    pub tax_rate: f64,
+    pub tax_category: String, // copied from the product unless overridden
+    pub subtotal: Money,      // supply value (VAT taken out for inclusive prices)
+    pub tax_amount: Money,
+    pub total_amount: Money,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotationWithItems {
+    #[serde(flatten)]
+    pub quotation: Quotation,
+    pub items: Vec<QuotationItemWithProduct>,
+    pub customer: Customer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotationItemWithProduct {
+    #[serde(flatten)]
+    pub item: QuotationItem,
+    pub product: Product,
+}
+
+/// A sales order (수주) or purchase order (발주); both tables share this shape
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Order {
+    pub id: i64,
+    pub order_number: String, // SO-20261017-0001 / PO-20261017-0001
+    pub customer_id: i64,
+    pub order_date: DateTime<Utc>,
+    pub expected_date: Option<NaiveDate>,
+    pub status: String, // 'open', 'partially_fulfilled', 'fulfilled', 'closed'
+    pub price_mode: String, // 'exclusive' or 'inclusive', carried over to fulfilling transactions
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OrderItem {
+    pub id: i64,
+    pub order_id: i64,
+    pub product_id: i64,
+    pub quantity: f64,
+    pub delivered_quantity: f64, // on non-cancelled transactions made from the order
+    pub unit_price: Money,
+    pub tax_rate: f64,
+    pub notes: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderWithItems {
+    #[serde(flatten)]
+    pub order: Order,
+    pub order_type: String, // 'sale' or 'purchase'
+    pub items: Vec<OrderItemWithProduct>,
+    pub customer: Customer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderItemWithProduct {
+    #[serde(flatten)]
+    pub item: OrderItem,
+    pub open_quantity: f64,
+    pub product: Product,
+}
+
 // Request DTOs for creating/updating entities
 #[derive(Debug, Deserialize)]
 pub struct CreateCompanyRequest {
@@ -113,8 +368,14 @@ pub struct CreateCompanyRequest {
     pub phone: Option<String>,
     pub email: Option<String>,
     pub representative: String,
-    pub business_type: String,
-    pub business_category: String,
+    pub business_type: Option<String>,
+    pub business_item: Option<String>,
+    pub default_invoice_memo: Option<String>,
+    pub write_off_expired_stock: Option<bool>,
+    pub traceability_validation: Option<String>,
+    pub vat_rounding_scope: Option<String>,
+    pub vat_rounding_mode: Option<String>,
+    pub document_font_path: Option<String>, // blank clears it
 }
 
 #[derive(Debug, Deserialize)]
@@ -126,16 +387,26 @@ pub struct UpdateCompanyRequest {
     pub email: Option<String>,
     pub representative: Option<String>,
     pub business_type: Option<String>,
-    pub business_category: Option<String>,
+    pub business_item: Option<String>,
+    pub default_invoice_memo: Option<String>,
+    pub write_off_expired_stock: Option<bool>,
+    pub traceability_validation: Option<String>,
+    pub vat_rounding_scope: Option<String>,
+    pub vat_rounding_mode: Option<String>,
+    pub document_font_path: Option<String>, // blank clears it
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateCustomerRequest {
     pub name: String,
     pub business_number: Option<String>,
+    pub representative: Option<String>,
     pub address: Option<String>,
     pub phone: Option<String>,
     pub email: Option<String>,
+    pub business_type: Option<String>,
+    pub business_item: Option<String>,
+    pub contact_person: Option<String>,
     pub customer_type: String,
 }
 
@@ -143,10 +414,15 @@ pub struct CreateCustomerRequest {
 pub struct UpdateCustomerRequest {
     pub name: Option<String>,
     pub business_number: Option<String>,
+    pub representative: Option<String>,
     pub address: Option<String>,
     pub phone: Option<String>,
     pub email: Option<String>,
+    pub business_type: Option<String>,
+    pub business_item: Option<String>,
+    pub contact_person: Option<String>,
     pub customer_type: Option<String>,
+    pub is_active: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -154,10 +430,17 @@ pub struct CreateProductRequest {
     pub name: String,
     pub code: Option<String>,
     pub description: Option<String>,
-    pub unit_price: f64,
+    pub unit_price: Money,
     pub unit: String,
     pub tax_rate: f64,
+    pub tax_category: Option<String>,
     pub category: Option<String>,
+    pub traceability_number: Option<String>,
+    pub origin: Option<String>,
+    pub slaughterhouse: Option<String>,
+    pub use_inventory_management: Option<bool>,
+    pub lot_consumption: Option<String>,
+    pub track_expiry: Option<bool>, // default true
 }
 
 #[derive(Debug, Deserialize)]
@@ -165,10 +448,17 @@ pub struct UpdateProductRequest {
     pub name: Option<String>,
     pub code: Option<String>,
     pub description: Option<String>,
-    pub unit_price: Option<f64>,
+    pub unit_price: Option<Money>,
     pub unit: Option<String>,
     pub tax_rate: Option<f64>,
+    pub tax_category: Option<String>,
     pub category: Option<String>,
+    pub traceability_number: Option<String>,
+    pub origin: Option<String>,
+    pub slaughterhouse: Option<String>,
+    pub use_inventory_management: Option<bool>,
+    pub lot_consumption: Option<String>,
+    pub track_expiry: Option<bool>,
     pub is_active: Option<bool>,
 }
 
@@ -177,6 +467,8 @@ pub struct CreateTransactionRequest {
     pub customer_id: i64,
     pub transaction_type: String,
     pub transaction_date: DateTime<Utc>,
+    pub due_date: Option<DateTime<Utc>>,
+    pub price_mode: Option<String>, // defaults to 'exclusive'
     pub items: Vec<CreateTransactionItemRequest>,
     pub notes: Option<String>,
 }
@@ -185,14 +477,187 @@ pub struct CreateTransactionRequest {
 pub struct CreateTransactionItemRequest {
     pub product_id: i64,
     pub quantity: f64,
-    pub unit_price: f64,
-    pub tax_rate: f64,
+    pub unit_price: Option<Money>, // defaults to the customer's price book, then the product price
+    pub tax_rate: f64, // stored as 0 for zero-rated and exempt items
+    pub tax_category: Option<String>, // defaults to the product's
+    pub traceability_number: Option<String>,
+    pub origin: Option<String>,
+    pub slaughterhouse: Option<String>,
+    pub expiry_date: Option<NaiveDate>,
+    pub notes: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateTransactionRequest {
     pub customer_id: Option<i64>,
     pub transaction_date: Option<DateTime<Utc>>,
-    pub status: Option<String>,
+    pub due_date: Option<DateTime<Utc>>,
+    // status changes go through confirm_transaction / cancel_transaction
+    pub price_mode: Option<String>, // drafts only; totals are recomputed
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTransactionItemsRequest {
+    #[serde(default)]
+    pub add: Vec<CreateTransactionItemRequest>,
+    #[serde(default)]
+    pub update: Vec<UpdateTransactionItemRequest>,
+    #[serde(default)]
+    pub remove: Vec<i64>, // transaction item ids
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTransactionItemRequest {
+    pub id: i64,
+    pub quantity: Option<f64>,
+    pub unit_price: Option<Money>,
+    pub tax_rate: Option<f64>,
+    pub tax_category: Option<String>,
+    pub traceability_number: Option<String>,
+    pub origin: Option<String>,
+    pub slaughterhouse: Option<String>,
+    pub expiry_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTransactionNumberSettingRequest {
+    pub transaction_type: String,
+    pub prefix: String,
+    pub sequence_digits: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecordPaymentRequest {
+    pub customer_id: i64,
+    pub amount: Money,
+    pub payment_date: DateTime<Utc>,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub allocations: Vec<PaymentAllocationRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PaymentAllocationRequest {
+    pub transaction_id: i64,
+    pub amount: Money,
+}
+#[derive(Debug, Deserialize)]
+pub struct SetCustomerProductPriceRequest {
+    pub customer_id: i64,
+    pub product_id: i64,
+    pub price: Money,
     pub notes: Option<String>,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateInventoryRequest {
+    pub product_id: i64,
+    pub safety_stock: Option<f64>,
+    pub location: Option<String>,
+    pub expiry_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateStockMovementRequest {
+    pub product_id: i64,
+    pub movement_type: String,
+    pub quantity: f64,
+    pub unit_price: Option<Money>,
+    pub lot_number: Option<String>,
+    pub expiry_date: Option<NaiveDate>,
+    pub traceability_number: Option<String>,
+    pub origin: Option<String>,
+    pub slaughterhouse: Option<String>,
+    pub notes: Option<String>,
+    pub created_by: Option<String>,
+    /// Let an 'out', 'expired' or negative 'adjust' movement take stock below zero
+    #[serde(default)]
+    pub allow_negative: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AmendTaxInvoiceRequest {
+    pub original_invoice_id: i64,
+    pub reason: String, // 'return', 'price_change', 'cancellation', 'duplicate'
+    /// Change in supply value; negative for a return. Required for 'return' and 'price_change'
+    pub supply_amount: Option<Money>,
+    /// Defaults to the original invoice's tax ratio applied to `supply_amount`
+    pub tax_amount: Option<Money>,
+    pub issue_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DeliveryNoteOptions {
+    pub template: Option<String>, // 'default' (plain rows) or 'striped'; defaults to 'default'
+    /// Show the customer's balance before and after the transaction
+    #[serde(default)]
+    pub show_balance: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateQuotationRequest {
+    pub customer_id: i64,
+    pub quotation_date: DateTime<Utc>,
+    pub valid_until: NaiveDate,
+    pub price_mode: Option<String>, // defaults to 'exclusive'
+    pub items: Vec<CreateQuotationItemRequest>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateQuotationItemRequest {
+    pub product_id: i64,
+    pub quantity: f64,
+    pub unit_price: Option<Money>, // defaults to the customer's price book, then the product price
+    pub tax_rate: f64,
+    pub tax_category: Option<String>, // defaults to the product's
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateOrderRequest {
+    pub order_type: String, // 'sale' (수주) or 'purchase' (발주)
+    pub customer_id: i64,
+    pub order_date: DateTime<Utc>,
+    pub expected_date: Option<NaiveDate>,
+    pub price_mode: Option<String>, // default 'exclusive'
+    pub items: Vec<CreateOrderItemRequest>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateOrderItemRequest {
+    pub product_id: i64,
+    pub quantity: f64,
+    pub unit_price: Option<Money>, // defaults to the customer's price book, then the product price
+    pub tax_rate: f64,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FulfilOrderRequest {
+    pub order_type: String, // 'sale' or 'purchase'
+    pub order_id: i64,
+    pub transaction_date: DateTime<Utc>,
+    pub due_date: Option<DateTime<Utc>>,
+    pub items: Vec<FulfilOrderItemRequest>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FulfilOrderItemRequest {
+    pub order_item_id: i64,
+    pub quantity: f64, // at most the line's open quantity
+    pub traceability_number: Option<String>,
+    pub origin: Option<String>,
+    pub slaughterhouse: Option<String>,
+    pub expiry_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReturnItemRequest {
+    pub transaction_item_id: i64, // item of the original transaction
+    pub quantity: f64,            // positive quantity being returned
+}
@@ -0,0 +1,565 @@
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use sqlx::decode::Decode;
+use sqlx::encode::{Encode, IsNull};
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{Sqlite, SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef};
+use sqlx::{Type, ValueRef};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+use crate::errors::{AppError, AppResult};
+
+/// An amount of money in whole won.
+///
+/// Stored as INTEGER in SQLite and sent to the frontend as a plain JSON
+/// integer. Fractions only arise when a unit price is multiplied by a
+/// quantity in kg or by a tax rate; those go through `times` (rounded) or
+/// `VatPolicy` (rounded by the company's VAT rule), never through `f64`
+/// sums.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn as_won(self) -> i64 {
+        self.0
+    }
+
+    /// Rounds to the nearest won, halves away from zero
+    pub fn from_f64(amount: f64) -> Self {
+        Money(amount.round() as i64)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64
+    }
+
+    /// Amount for `quantity` units at this unit price, rounded to the nearest won
+    pub fn times(self, quantity: f64) -> Money {
+        Money::from_f64(self.0 as f64 * quantity)
+    }
+
+    /// This VAT-inclusive price without its VAT at `tax_rate`, to the nearest won
+    pub fn excluding_vat(self, tax_rate: f64) -> Money {
+        Money(round_half_away(self.0 as i128 * BASIS, BASIS + basis_points(tax_rate)) as i64)
+    }
+
+    /// This VAT-exclusive price with VAT at `tax_rate` added, to the nearest won
+    pub fn including_vat(self, tax_rate: f64) -> Money {
+        Money(round_half_away(self.0 as i128 * (BASIS + basis_points(tax_rate)), BASIS) as i64)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        Money(iter.map(|m| m.0).sum())
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.0)
+    }
+}
+
+/// Accepts integers and, for older frontends, floats (rounded to the won)
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MoneyVisitor;
+
+        impl Visitor<'_> for MoneyVisitor {
+            type Value = Money;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an amount in won")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Money, E> {
+                Ok(Money(v))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Money, E> {
+                i64::try_from(v)
+                    .map(Money)
+                    .map_err(|_| E::custom("amount is too large"))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Money, E> {
+                if !v.is_finite() || v.abs() >= i64::MAX as f64 {
+                    return Err(E::custom("amount is not a finite number of won"));
+                }
+                Ok(Money::from_f64(v))
+            }
+        }
+
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+impl Type<Sqlite> for Money {
+    fn type_info() -> SqliteTypeInfo {
+        <i64 as Type<Sqlite>>::type_info()
+    }
+
+    // REAL is accepted for computed values such as SUM(quantity * unit_price)
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <i64 as Type<Sqlite>>::compatible(ty) || <f64 as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for Money {
+    fn encode_by_ref(&self, args: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        <i64 as Encode<'q, Sqlite>>::encode_by_ref(&self.0, args)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for Money {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        if <f64 as Type<Sqlite>>::compatible(&value.type_info()) {
+            return Ok(Money::from_f64(<f64 as Decode<Sqlite>>::decode(value)?));
+        }
+        Ok(Money(<i64 as Decode<Sqlite>>::decode(value)?))
+    }
+}
+
+/// Whether VAT is rounded on each line or once on the document's supply total
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VatScope {
+    Line,
+    Document,
+}
+
+/// How VAT is brought to whole won: nearest won, or fractions dropped (절사)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VatRounding {
+    Round,
+    Floor,
+}
+
+/// The company's VAT rounding rule (companies.vat_rounding_scope and
+/// vat_rounding_mode). Floor drops the fraction toward zero so a return
+/// mirrors the sale it reverses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VatPolicy {
+    pub scope: VatScope,
+    pub rounding: VatRounding,
+}
+
+impl Default for VatPolicy {
+    fn default() -> Self {
+        VatPolicy { scope: VatScope::Line, rounding: VatRounding::Round }
+    }
+}
+
+impl VatPolicy {
+    pub fn parse(scope: &str, rounding: &str) -> AppResult<Self> {
+        let scope = match scope {
+            "line" => VatScope::Line,
+            "document" => VatScope::Document,
+            _ => {
+                return Err(AppError::invalid_field(
+                    "vat_rounding_scope",
+                    "VAT rounding scope must be 'line' or 'document'",
+                ))
+            }
+        };
+        let rounding = match rounding {
+            "round" => VatRounding::Round,
+            "floor" => VatRounding::Floor,
+            _ => {
+                return Err(AppError::invalid_field(
+                    "vat_rounding_mode",
+                    "VAT rounding mode must be 'round' or 'floor'",
+                ))
+            }
+        };
+        Ok(VatPolicy { scope, rounding })
+    }
+
+    fn round(self, exact: ExactTax) -> i64 {
+        let whole = match self.rounding {
+            VatRounding::Round => round_half_away(exact.numerator, exact.denominator),
+            // Integer division already drops the fraction toward zero
+            VatRounding::Floor => exact.numerator / exact.denominator,
+        };
+        whole as i64
+    }
+
+    /// Supply value and VAT of each line, given its amount (unit price ×
+    /// quantity) and tax rate.
+    ///
+    /// With `prices_include_vat` the amount already contains the VAT, which
+    /// is taken out of it (amount × rate / (1 + rate)); otherwise the amount
+    /// is the supply value and VAT comes on top. Exempt and zero-rated lines
+    /// have a rate of 0 and get no VAT. The rate is taken in basis points
+    /// and the VAT kept as an exact fraction until it is rounded, so
+    /// inclusive 330 at 10% is exactly 30 and not 29.999….
+    ///
+    /// Per line, each line's VAT is rounded on its own. Per document, the
+    /// VAT is rounded once on the whole document and then spread over the
+    /// lines (largest remainder first), so the lines still add up to the
+    /// document's VAT.
+    pub fn split_lines(self, lines: &[(Money, f64)], prices_include_vat: bool) -> Vec<(Money, Money)> {
+        let exact: Vec<ExactTax> = lines
+            .iter()
+            .map(|(amount, tax_rate)| {
+                let rate = basis_points(*tax_rate);
+                let denominator = if prices_include_vat { BASIS + rate } else { BASIS };
+                ExactTax::new(amount.0 as i128 * rate, denominator)
+            })
+            .collect();
+
+        lines
+            .iter()
+            .zip(self.distribute(&exact))
+            .map(|((amount, _), tax)| {
+                let supply = if prices_include_vat { *amount - tax } else { *amount };
+                (supply, tax)
+            })
+            .collect()
+    }
+
+    /// `amount` × `part` / `whole`, brought to whole won by this policy's
+    /// rounding; the VAT of an amendment in proportion to the invoice it
+    /// amends. `whole` must not be zero.
+    pub fn prorate(self, amount: Money, part: Money, whole: Money) -> Money {
+        let numerator = amount.0 as i128 * part.0 as i128 * whole.0.signum() as i128;
+        Money(self.round(ExactTax::new(numerator, (whole.0 as i128).abs())))
+    }
+
+    fn distribute(self, exact: &[ExactTax]) -> Vec<Money> {
+        if self.scope == VatScope::Line {
+            return exact.iter().map(|tax| Money(self.round(*tax))).collect();
+        }
+
+        let document = exact.iter().fold(ExactTax::new(0, 1), |sum, tax| sum.add(*tax));
+        let document_tax = self.round(document);
+        let mut taxes: Vec<i64> = exact.iter().map(|tax| tax.whole() as i64).collect();
+        let mut remaining = document_tax - taxes.iter().sum::<i64>();
+
+        let mut order: Vec<usize> = (0..exact.len()).collect();
+        order.sort_by(|a, b| exact[*b].fraction_cmp(&exact[*a]));
+        // Truncating leaves less than one won per line to hand out
+        for index in order {
+            if remaining == 0 {
+                break;
+            }
+            let step = remaining.signum();
+            taxes[index] += step;
+            remaining -= step;
+        }
+
+        taxes.into_iter().map(Money).collect()
+    }
+}
+
+/// Basis points in a whole (10% is 1,000 bp)
+const BASIS: i128 = 10_000;
+
+fn basis_points(tax_rate: f64) -> i128 {
+    (tax_rate * BASIS as f64).round() as i128
+}
+
+/// numerator / denominator to the nearest whole, halves away from zero
+/// (denominator positive)
+fn round_half_away(numerator: i128, denominator: i128) -> i128 {
+    (2 * numerator.abs() + denominator) / (2 * denominator) * numerator.signum()
+}
+
+/// VAT in won as an exact fraction, numerator / denominator with a
+/// positive denominator
+#[derive(Debug, Clone, Copy)]
+struct ExactTax {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl ExactTax {
+    fn new(numerator: i128, denominator: i128) -> Self {
+        let divisor = gcd(numerator.abs(), denominator).max(1);
+        ExactTax { numerator: numerator / divisor, denominator: denominator / divisor }
+    }
+
+    fn add(self, other: ExactTax) -> ExactTax {
+        ExactTax::new(
+            self.numerator * other.denominator + other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+
+    /// Whole won, fraction dropped toward zero
+    fn whole(self) -> i128 {
+        self.numerator / self.denominator
+    }
+
+    /// Compares the size of the dropped fractions
+    fn fraction_cmp(&self, other: &ExactTax) -> std::cmp::Ordering {
+        let own = (self.numerator % self.denominator).abs();
+        let others = (other.numerator % other.denominator).abs();
+        (own * other.denominator).cmp(&(others * self.denominator))
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE_ROUND: VatPolicy = VatPolicy { scope: VatScope::Line, rounding: VatRounding::Round };
+    const LINE_FLOOR: VatPolicy = VatPolicy { scope: VatScope::Line, rounding: VatRounding::Floor };
+    const DOCUMENT_ROUND: VatPolicy = VatPolicy { scope: VatScope::Document, rounding: VatRounding::Round };
+    const DOCUMENT_FLOOR: VatPolicy = VatPolicy { scope: VatScope::Document, rounding: VatRounding::Floor };
+
+    fn split(policy: VatPolicy, lines: &[(i64, f64)], inclusive: bool) -> Vec<(i64, i64)> {
+        let lines: Vec<(Money, f64)> = lines.iter().map(|(amount, rate)| (Money(*amount), *rate)).collect();
+        policy
+            .split_lines(&lines, inclusive)
+            .into_iter()
+            .map(|(supply, tax)| (supply.as_won(), tax.as_won()))
+            .collect()
+    }
+
+    #[test]
+    fn serializes_as_integer_won() {
+        assert_eq!(serde_json::to_string(&Money(1_234)).unwrap(), "1234");
+        assert_eq!(serde_json::to_string(&Money(-50)).unwrap(), "-50");
+    }
+
+    #[test]
+    fn deserializes_integers_and_rounds_floats() {
+        let cases = [("1234", 1_234), ("-7", -7), ("1234.5", 1_235), ("-0.5", -1), ("99.4", 99)];
+        for (json, won) in cases {
+            assert_eq!(serde_json::from_str::<Money>(json).unwrap(), Money(won), "{}", json);
+        }
+        assert!(serde_json::from_str::<Money>("1e30").is_err());
+        assert!(serde_json::from_str::<Money>("\"100\"").is_err());
+    }
+
+    #[tokio::test]
+    async fn encodes_as_integer_and_decodes_integer_or_real() {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE amounts (amount INTEGER, price REAL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO amounts (amount, price) VALUES (?, 2.5)")
+            .bind(Money(1_500))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let (stored_type,): (String,) = sqlx::query_as("SELECT typeof(amount) FROM amounts")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stored_type, "integer");
+
+        let (amount, price, computed): (Money, Money, Money) =
+            sqlx::query_as("SELECT amount, price, amount * 1.0005 FROM amounts")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(amount, Money(1_500));
+        assert_eq!(price, Money(3));
+        assert_eq!(computed, Money(1_501));
+    }
+
+    #[test]
+    fn parses_vat_policy() {
+        assert_eq!(VatPolicy::parse("line", "round").unwrap(), LINE_ROUND);
+        assert_eq!(VatPolicy::parse("document", "floor").unwrap(), DOCUMENT_FLOOR);
+
+        for (scope, rounding, field) in [("page", "round", "vat_rounding_scope"), ("line", "ceil", "vat_rounding_mode")] {
+            match VatPolicy::parse(scope, rounding) {
+                Err(AppError::Validation { field: Some(f), .. }) => assert_eq!(f, field),
+                other => panic!("{} / {}: {:?}", scope, rounding, other),
+            }
+        }
+    }
+
+    #[test]
+    fn converts_prices_between_inclusive_and_exclusive() {
+        let cases = [
+            // inclusive, exclusive at 10%
+            (11_000, 10_000),
+            (330, 300),
+            (1_000, 909),
+            (15, 14),
+        ];
+        for (inclusive, exclusive) in cases {
+            assert_eq!(Money(inclusive).excluding_vat(0.1), Money(exclusive), "{}", inclusive);
+        }
+        assert_eq!(Money(10_000).including_vat(0.1), Money(11_000));
+        assert_eq!(Money(909).including_vat(0.1), Money(1_000));
+        assert_eq!(Money(5).including_vat(0.1), Money(6));
+        assert_eq!(Money(1_000).excluding_vat(0.0), Money(1_000));
+    }
+
+    #[test]
+    fn exclusive_line_vat() {
+        let cases = [
+            // amount, round, floor
+            (1_000, 100, 100),
+            (1_005, 101, 100),
+            (1_004, 100, 100),
+            (15, 2, 1),
+            (-1_005, -101, -100),
+        ];
+        for (amount, rounded, floored) in cases {
+            assert_eq!(split(LINE_ROUND, &[(amount, 0.1)], false), vec![(amount, rounded)], "{}", amount);
+            assert_eq!(split(LINE_FLOOR, &[(amount, 0.1)], false), vec![(amount, floored)], "{}", amount);
+        }
+    }
+
+    #[test]
+    fn inclusive_line_vat_is_exact() {
+        let cases = [
+            // amount, round, floor
+            (330, 30, 30),
+            (165, 15, 15),
+            (11_000, 1_000, 1_000),
+            (1_000, 91, 90),
+            (-330, -30, -30),
+        ];
+        for (amount, rounded, floored) in cases {
+            assert_eq!(split(LINE_ROUND, &[(amount, 0.1)], true), vec![(amount - rounded, rounded)], "{}", amount);
+            assert_eq!(split(LINE_FLOOR, &[(amount, 0.1)], true), vec![(amount - floored, floored)], "{}", amount);
+        }
+    }
+
+    #[test]
+    fn inclusive_multiples_of_eleven_never_drift() {
+        for policy in [LINE_ROUND, LINE_FLOOR, DOCUMENT_ROUND, DOCUMENT_FLOOR] {
+            for tax in 0..100_000 {
+                let amount = tax * 11;
+                assert_eq!(split(policy, &[(amount, 0.1)], true), vec![(amount - tax, tax)]);
+            }
+        }
+    }
+
+    #[test]
+    fn document_vat_is_rounded_once_and_spread() {
+        // Three lines of 0.5 won VAT each: per line 1 + 1 + 1, per document round(1.5) = 2
+        let lines = [(5, 0.1), (5, 0.1), (5, 0.1)];
+        assert_eq!(split(LINE_ROUND, &lines, false), vec![(5, 1), (5, 1), (5, 1)]);
+        assert_eq!(split(LINE_FLOOR, &lines, false), vec![(5, 0), (5, 0), (5, 0)]);
+
+        let rounded = split(DOCUMENT_ROUND, &lines, false);
+        assert_eq!(rounded.iter().map(|(_, tax)| tax).sum::<i64>(), 2);
+        let floored = split(DOCUMENT_FLOOR, &lines, false);
+        assert_eq!(floored.iter().map(|(_, tax)| tax).sum::<i64>(), 1);
+    }
+
+    #[test]
+    fn document_vat_goes_to_the_largest_remainder() {
+        // Exact VAT 1.9 and 0.4; round(2.3) = 2 goes 1 + 1, the extra won to the 0.9 remainder
+        assert_eq!(split(DOCUMENT_ROUND, &[(19, 0.1), (4, 0.1)], false), vec![(19, 2), (4, 0)]);
+        assert_eq!(split(DOCUMENT_FLOOR, &[(19, 0.1), (4, 0.1)], false), vec![(19, 2), (4, 0)]);
+    }
+
+    #[test]
+    fn document_vat_inclusive_mixed_rates() {
+        // 1,100 at 10% holds exactly 100; 1,000 at 0% holds none
+        for policy in [DOCUMENT_ROUND, DOCUMENT_FLOOR] {
+            assert_eq!(split(policy, &[(1_100, 0.1), (1_000, 0.0)], true), vec![(1_000, 100), (1_000, 0)]);
+        }
+        // 1,000 / 11 = 90.9 twice: per document round(181.8) = 182, floor 181
+        let rounded = split(DOCUMENT_ROUND, &[(1_000, 0.1), (1_000, 0.1)], true);
+        assert_eq!(rounded.iter().map(|(_, tax)| tax).sum::<i64>(), 182);
+        let floored = split(DOCUMENT_FLOOR, &[(1_000, 0.1), (1_000, 0.1)], true);
+        assert_eq!(floored.iter().map(|(_, tax)| tax).sum::<i64>(), 181);
+        for (supply, tax) in rounded.iter().chain(&floored) {
+            assert_eq!(supply + tax, 1_000);
+        }
+    }
+
+    #[test]
+    fn returns_mirror_sales() {
+        for policy in [LINE_ROUND, LINE_FLOOR, DOCUMENT_ROUND, DOCUMENT_FLOOR] {
+            for inclusive in [false, true] {
+                let sale = split(policy, &[(1_005, 0.1), (333, 0.1)], inclusive);
+                let refund = split(policy, &[(-1_005, 0.1), (-333, 0.1)], inclusive);
+                let negated: Vec<(i64, i64)> = sale.iter().map(|(supply, tax)| (-supply, -tax)).collect();
+                assert_eq!(refund, negated);
+            }
+        }
+    }
+
+    #[test]
+    fn prorates_by_the_policy_rounding() {
+        // A return of 12,345 against 25,000 + 2,500 VAT: exactly -1,234.5
+        assert_eq!(LINE_ROUND.prorate(Money(-12_345), Money(2_500), Money(25_000)), Money(-1_235));
+        assert_eq!(LINE_FLOOR.prorate(Money(-12_345), Money(2_500), Money(25_000)), Money(-1_234));
+        // 1,000 × 1,001 / 10,010 = 100 exactly, where f64 may land just below
+        for policy in [LINE_ROUND, LINE_FLOOR] {
+            assert_eq!(policy.prorate(Money(1_000), Money(1_001), Money(10_010)), Money(100));
+            assert_eq!(policy.prorate(Money(500), Money(0), Money(10_000)), Money::ZERO);
+        }
+    }
+}
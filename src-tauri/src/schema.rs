@@ -0,0 +1,150 @@
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use sqlx::Row;
+
+use crate::database::DbPool;
+use crate::models::{
+    Company, Customer, CustomerProductPrice, Order, OrderItem, PaymentAllocation, PriceHistory,
+    Product, ProductInventory, Quotation, QuotationItem, StockLot, StockLotConsumption,
+    StockMovement, TaxInvoice, Transaction, TransactionItem, TransactionNumberSetting,
+};
+
+/// Column drift between one `sqlx::FromRow` model and its table.
+#[derive(Debug, Default)]
+pub struct TableDrift {
+    pub table: &'static str,
+    pub model: &'static str,
+    /// Fields on the model that the table does not have (SELECT * would fail to decode)
+    pub missing_in_table: Vec<String>,
+    /// Columns in the table that the model does not map
+    pub missing_in_model: Vec<String>,
+}
+
+impl TableDrift {
+    fn is_empty(&self) -> bool {
+        self.missing_in_table.is_empty() && self.missing_in_model.is_empty()
+    }
+}
+
+/// Compares every model with `PRAGMA table_info` and fails with a report
+/// listing each table whose columns differ from the model's fields.
+pub async fn verify_schema(pool: &DbPool) -> anyhow::Result<()> {
+    let checks = [
+        ("companies", "Company", struct_fields::<Company>()),
+        ("customers", "Customer", struct_fields::<Customer>()),
+        ("products", "Product", struct_fields::<Product>()),
+        ("transactions", "Transaction", struct_fields::<Transaction>()),
+        ("transaction_items", "TransactionItem", struct_fields::<TransactionItem>()),
+        ("tax_invoices", "TaxInvoice", struct_fields::<TaxInvoice>()),
+        ("transaction_number_settings", "TransactionNumberSetting", struct_fields::<TransactionNumberSetting>()),
+        ("payment_allocations", "PaymentAllocation", struct_fields::<PaymentAllocation>()),
+        ("customer_product_prices", "CustomerProductPrice", struct_fields::<CustomerProductPrice>()),
+        ("price_history", "PriceHistory", struct_fields::<PriceHistory>()),
+        ("product_inventory", "ProductInventory", struct_fields::<ProductInventory>()),
+        ("stock_movements", "StockMovement", struct_fields::<StockMovement>()),
+        ("stock_lots", "StockLot", struct_fields::<StockLot>()),
+        ("stock_lot_consumptions", "StockLotConsumption", struct_fields::<StockLotConsumption>()),
+        ("quotations", "Quotation", struct_fields::<Quotation>()),
+        ("quotation_items", "QuotationItem", struct_fields::<QuotationItem>()),
+        ("sales_orders", "Order", struct_fields::<Order>()),
+        ("sales_order_items", "OrderItem", struct_fields::<OrderItem>()),
+        ("purchase_orders", "Order", struct_fields::<Order>()),
+        ("purchase_order_items", "OrderItem", struct_fields::<OrderItem>()),
+    ];
+
+    let mut drifts = Vec::new();
+    for (table, model, fields) in checks {
+        let drift = compare_table(pool, table, model, fields).await?;
+        if !drift.is_empty() {
+            drifts.push(drift);
+        }
+    }
+
+    if drifts.is_empty() {
+        println!("Schema check passed");
+        return Ok(());
+    }
+
+    let mut report = String::from("Database schema does not match the Rust models:");
+    for drift in &drifts {
+        report.push_str(&format!("\n  {} <-> models::{}", drift.table, drift.model));
+        if !drift.missing_in_table.is_empty() {
+            report.push_str(&format!(
+                "\n    fields missing in table: {}",
+                drift.missing_in_table.join(", ")
+            ));
+        }
+        if !drift.missing_in_model.is_empty() {
+            report.push_str(&format!(
+                "\n    columns missing in model: {}",
+                drift.missing_in_model.join(", ")
+            ));
+        }
+    }
+    anyhow::bail!(report)
+}
+
+async fn compare_table(
+    pool: &DbPool,
+    table: &'static str,
+    model: &'static str,
+    fields: &'static [&'static str],
+) -> anyhow::Result<TableDrift> {
+    let rows = sqlx::query(&format!("PRAGMA table_info({})", table))
+        .fetch_all(pool)
+        .await?;
+
+    let columns: Vec<String> = rows.iter().map(|row| row.get("name")).collect();
+
+    Ok(TableDrift {
+        table,
+        model,
+        missing_in_table: fields
+            .iter()
+            .filter(|f| !columns.iter().any(|c| c == *f))
+            .map(|f| f.to_string())
+            .collect(),
+        missing_in_model: columns
+            .iter()
+            .filter(|c| !fields.contains(&c.as_str()))
+            .cloned()
+            .collect(),
+    })
+}
+
+/// Returns the field names of a struct deriving `Deserialize`.
+///
+/// The models derive both `Deserialize` and `sqlx::FromRow` without renames,
+/// so serde's field list is exactly the set of columns `FromRow` reads.
+pub fn struct_fields<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    let mut collector = FieldCollector { fields: &[] };
+    let _ = T::deserialize(&mut collector);
+    collector.fields
+}
+
+struct FieldCollector {
+    fields: &'static [&'static str],
+}
+
+impl<'de> Deserializer<'de> for &mut FieldCollector {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("expected a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.fields = fields;
+        Err(de::Error::custom("fields collected"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
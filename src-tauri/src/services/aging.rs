@@ -0,0 +1,274 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::money::Money;
+use chrono::{DateTime, Local, Utc};
+use sqlx::Row;
+use std::collections::{BTreeMap, HashMap};
+
+/// Open amounts of one customer split by how many days they are past the basis date
+#[derive(Debug, Default, serde::Serialize)]
+pub struct AgingRow {
+    pub customer_id: i64,
+    pub customer_name: String,
+    pub current: Money,     // not yet due (0 days or less)
+    pub days_1_30: Money,
+    pub days_31_60: Money,
+    pub days_61_90: Money,
+    pub over_90: Money,
+    pub total: Money,
+    /// Payments left over after every open transaction was covered (a credit)
+    pub unapplied_payments: Money,
+    /// Stored customers.outstanding_balance, receivables and payables combined
+    pub outstanding_balance: Money,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct AgingReport {
+    pub as_of: DateTime<Utc>,
+    pub basis: String, // 'transaction_date' or 'due_date'
+    pub receivables: Vec<AgingRow>,
+    pub payables: Vec<AgingRow>,
+}
+
+/// A confirmed sale or purchase with the part not yet paid
+struct OpenItem {
+    id: i64,
+    aging_date: DateTime<Utc>,
+    open_amount: Money,
+}
+
+/// Receivable and payable aging of every customer as of a date.
+///
+/// Confirmed sales (receivables) and purchases (payables) dated on or before
+/// `as_of` are reduced first by the payment allocations made against them,
+/// then by the unallocated remainder of the customer's payments, oldest
+/// transaction first. What is left is bucketed by the number of local
+/// calendar days between `as_of` and the transaction date, or the due date
+/// when `basis` is 'due_date' (transactions without one fall back to their
+/// transaction date).
+pub async fn get_aging_report(
+    pool: &DbPool,
+    as_of: Option<DateTime<Utc>>,
+    basis: Option<String>,
+) -> AppResult<AgingReport> {
+    let as_of = as_of.unwrap_or_else(Utc::now);
+    let basis = basis.unwrap_or_else(|| "transaction_date".to_string());
+    if !matches!(basis.as_str(), "transaction_date" | "due_date") {
+        return Err(AppError::validation(
+            "Aging basis must be 'transaction_date' or 'due_date'".to_string()
+        ));
+    }
+
+    let customers: Vec<(i64, String, Money)> = sqlx::query_as(
+        "SELECT id, name, outstanding_balance FROM customers ORDER BY name, id"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT id, customer_id, transaction_type, transaction_date, due_date, total_amount
+        FROM transactions
+        WHERE status = 'confirmed'
+        ORDER BY transaction_date, id
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // (customer_id, transaction_type) -> open items in (transaction_date, id) order
+    let mut open_items: HashMap<(i64, String), Vec<OpenItem>> = HashMap::new();
+    // payment id -> (customer_id, payment_type, amount)
+    let mut payments: BTreeMap<i64, (i64, String, Money)> = BTreeMap::new();
+
+    for row in rows {
+        let transaction_date: DateTime<Utc> = row.get("transaction_date");
+        if transaction_date > as_of {
+            continue;
+        }
+
+        let id: i64 = row.get("id");
+        let customer_id: i64 = row.get("customer_id");
+        let transaction_type: String = row.get("transaction_type");
+        let total_amount: Money = row.get("total_amount");
+
+        match transaction_type.as_str() {
+            // A return (negative sale or purchase) is a credit like an unallocated payment
+            "sale" | "purchase" if total_amount.is_negative() => {
+                let credit_type = if transaction_type == "sale" { "payment_in" } else { "payment_out" };
+                payments.insert(id, (customer_id, credit_type.to_string(), -total_amount));
+            }
+            "sale" | "purchase" => {
+                let due_date: Option<DateTime<Utc>> = row.get("due_date");
+                let aging_date = match basis.as_str() {
+                    "due_date" => due_date.unwrap_or(transaction_date),
+                    _ => transaction_date,
+                };
+                open_items
+                    .entry((customer_id, transaction_type))
+                    .or_default()
+                    .push(OpenItem { id, aging_date, open_amount: total_amount });
+            }
+            "payment_in" | "payment_out" => {
+                payments.insert(id, (customer_id, transaction_type, total_amount));
+            }
+            _ => {}
+        }
+    }
+
+    let allocations: Vec<(i64, i64, Money)> = sqlx::query_as(
+        "SELECT payment_id, transaction_id, amount FROM payment_allocations ORDER BY id"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // Explicit allocations settle their own transaction; only counted when both sides are in the report
+    for (payment_id, transaction_id, amount) in allocations {
+        let Some((customer_id, payment_type, remaining)) = payments.get_mut(&payment_id) else {
+            continue;
+        };
+        let target_type = if payment_type == "payment_in" { "sale" } else { "purchase" };
+        let item = open_items
+            .get_mut(&(*customer_id, target_type.to_string()))
+            .and_then(|items| items.iter_mut().find(|i| i.id == transaction_id));
+
+        if let Some(item) = item {
+            let applied = amount.min(item.open_amount).min(*remaining);
+            item.open_amount -= applied;
+            *remaining -= applied;
+        }
+    }
+
+    // customer_id -> unallocated receipts, unallocated disbursements
+    let mut unallocated: HashMap<i64, (Money, Money)> = HashMap::new();
+    for (customer_id, payment_type, remaining) in payments.into_values() {
+        let entry = unallocated.entry(customer_id).or_default();
+        if payment_type == "payment_in" {
+            entry.0 += remaining;
+        } else {
+            entry.1 += remaining;
+        }
+    }
+
+    let as_of_day = as_of.with_timezone(&Local).date_naive();
+    let mut receivables = Vec::new();
+    let mut payables = Vec::new();
+
+    for (customer_id, customer_name, outstanding_balance) in customers {
+        let (receipts, disbursements) = unallocated.get(&customer_id).copied().unwrap_or_default();
+
+        for (transaction_type, credit, rows) in [
+            ("sale", receipts, &mut receivables),
+            ("purchase", disbursements, &mut payables),
+        ] {
+            let items = open_items.remove(&(customer_id, transaction_type.to_string())).unwrap_or_default();
+            let mut credit = credit;
+
+            let mut row = AgingRow {
+                customer_id,
+                customer_name: customer_name.clone(),
+                outstanding_balance,
+                ..Default::default()
+            };
+
+            // Oldest first, in the same order the running balance is kept
+            for mut item in items {
+                let applied = credit.min(item.open_amount);
+                item.open_amount -= applied;
+                credit -= applied;
+
+                if !item.open_amount.is_positive() {
+                    continue;
+                }
+
+                let days = (as_of_day - item.aging_date.with_timezone(&Local).date_naive()).num_days();
+                let bucket = match days {
+                    d if d <= 0 => &mut row.current,
+                    1..=30 => &mut row.days_1_30,
+                    31..=60 => &mut row.days_31_60,
+                    61..=90 => &mut row.days_61_90,
+                    _ => &mut row.over_90,
+                };
+                *bucket += item.open_amount;
+                row.total += item.open_amount;
+            }
+
+            if credit.is_positive() {
+                row.unapplied_payments = credit;
+            }
+
+            if row.total.is_positive() || row.unapplied_payments.is_positive() {
+                rows.push(row);
+            }
+        }
+    }
+
+    Ok(AgingReport {
+        as_of,
+        basis,
+        receivables,
+        payables,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use crate::services::{payment, transaction};
+    use serde_json::json;
+
+    /// A confirmed sale of `quantity` kg (11,000 won each) dated `date`
+    async fn sale_on(pool: &DbPool, customer_id: i64, product_id: i64, quantity: f64, date: &str) -> i64 {
+        let sale = fixtures::confirmed_sale(pool, customer_id, product_id, quantity).await;
+        transaction::update_transaction(pool, sale.transaction.id, serde_json::from_value(json!({
+            "transaction_date": date,
+            "due_date": "2026-06-30T01:00:00Z",
+        })).unwrap()).await.unwrap();
+        sale.transaction.id
+    }
+
+    fn as_of() -> Option<DateTime<Utc>> {
+        Some("2026-06-01T01:00:00Z".parse().unwrap())
+    }
+
+    #[tokio::test]
+    async fn open_sales_are_bucketed_after_unallocated_payments() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        sale_on(&pool, buyer.id, beef.id, 1.0, "2026-03-02T01:00:00Z").await;
+        sale_on(&pool, buyer.id, beef.id, 2.0, "2026-05-20T01:00:00Z").await;
+        sale_on(&pool, buyer.id, beef.id, 3.0, "2026-06-01T01:00:00Z").await;
+        sale_on(&pool, buyer.id, beef.id, 4.0, "2026-06-02T01:00:00Z").await;
+        payment::record_receipt(&pool, serde_json::from_value(json!({
+            "customer_id": buyer.id,
+            "amount": 5000,
+            "payment_date": "2026-04-01T01:00:00Z",
+        })).unwrap()).await.unwrap();
+
+        let report = get_aging_report(&pool, as_of(), None).await.unwrap();
+
+        assert!(report.payables.is_empty());
+        let row = &report.receivables[0];
+        assert_eq!(row.customer_id, buyer.id);
+        assert_eq!(row.over_90.as_won(), 6000);
+        assert_eq!(row.days_1_30.as_won(), 22000);
+        assert_eq!(row.current.as_won(), 33000);
+        assert_eq!(row.total.as_won(), 61000);
+        assert_eq!(row.unapplied_payments, Money::ZERO);
+
+        let report = get_aging_report(&pool, as_of(), Some("due_date".to_string())).await.unwrap();
+        assert_eq!(report.receivables[0].current.as_won(), 61000);
+    }
+
+    #[tokio::test]
+    async fn rejects_an_unknown_basis() {
+        let pool = test_pool().await;
+
+        let result = get_aging_report(&pool, as_of(), Some("invoice_date".to_string())).await;
+
+        assert!(matches!(result, Err(AppError::Validation { .. })), "{:?}", result.map(|r| r.basis));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::database::DbPool;
+use crate::errors::AppResult;
+use crate::money::Money;
+use chrono::Utc;
+use sqlx::{Row, SqliteConnection};
+
+/// A customer whose stored balances did not match the recomputed ones
+#[derive(Debug, serde::Serialize)]
+pub struct BalanceRepair {
+    pub customer_id: i64,
+    pub customer_name: String,
+    pub previous_outstanding_balance: Money,
+    pub outstanding_balance: Money,
+    pub transactions_repaired: i64,
+}
+
+struct Recomputation {
+    previous_outstanding_balance: Money,
+    outstanding_balance: Money,
+    transactions_repaired: i64,
+}
+
+/// Signed effect of a confirmed transaction on a customer's balance.
+///
+/// Sales raise what a customer owes us and purchases raise what we owe a
+/// supplier; payment_in / payment_out settle them.
+pub fn balance_effect(transaction_type: &str, total_amount: Money) -> Money {
+    match transaction_type {
+        "sale" | "purchase" => total_amount,
+        "payment_in" | "payment_out" => -total_amount,
+        _ => Money::ZERO,
+    }
+}
+
+/// Recomputes `transactions.current_balance` and `customers.outstanding_balance` for one customer.
+///
+/// Walks the customer's transactions in `(transaction_date, id)` order, the
+/// order of `idx_transactions_customer_date`. Only confirmed transactions move
+/// the running balance; each row stores the balance after it, so a draft or
+/// cancelled row shows the balance at that point without itself. Call it on
+/// the same DB transaction as any write that creates, confirms, cancels,
+/// re-dates or deletes a transaction of the customer.
+pub async fn recompute_customer_balance(conn: &mut SqliteConnection, customer_id: i64) -> AppResult<()> {
+    recompute(conn, customer_id).await?;
+    Ok(())
+}
+
+async fn recompute(conn: &mut SqliteConnection, customer_id: i64) -> AppResult<Recomputation> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, transaction_type, total_amount, status, current_balance
+        FROM transactions
+        WHERE customer_id = ?
+        ORDER BY transaction_date, id
+        "#
+    )
+    .bind(customer_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut running = Money::ZERO;
+    let mut transactions_repaired = 0;
+
+    for row in rows {
+        let status: String = row.get("status");
+        if status == "confirmed" {
+            let transaction_type: String = row.get("transaction_type");
+            running += balance_effect(&transaction_type, row.get("total_amount"));
+        }
+
+        let stored: Money = row.get("current_balance");
+        if stored != running {
+            sqlx::query("UPDATE transactions SET current_balance = ? WHERE id = ?")
+                .bind(running)
+                .bind(row.get::<i64, _>("id"))
+                .execute(&mut *conn)
+                .await?;
+            transactions_repaired += 1;
+        }
+    }
+
+    let previous: Option<(Money,)> = sqlx::query_as(
+        "SELECT outstanding_balance FROM customers WHERE id = ?"
+    )
+    .bind(customer_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let previous_outstanding_balance = previous.map(|p| p.0).unwrap_or_default();
+
+    if previous_outstanding_balance != running {
+        sqlx::query("UPDATE customers SET outstanding_balance = ?, updated_at = ? WHERE id = ?")
+            .bind(running)
+            .bind(Utc::now())
+            .bind(customer_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(Recomputation {
+        previous_outstanding_balance,
+        outstanding_balance: running,
+        transactions_repaired,
+    })
+}
+
+/// Recomputes the balances of every customer and reports the ones that were out of date
+pub async fn rebuild_balances(pool: &DbPool) -> AppResult<Vec<BalanceRepair>> {
+    let mut tx = pool.begin().await?;
+
+    let customers: Vec<(i64, String)> = sqlx::query_as("SELECT id, name FROM customers ORDER BY id")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let mut repairs = Vec::new();
+    for (customer_id, customer_name) in customers {
+        let result = recompute(&mut tx, customer_id).await?;
+
+        let balance_changed = result.previous_outstanding_balance != result.outstanding_balance;
+        if balance_changed || result.transactions_repaired > 0 {
+            repairs.push(BalanceRepair {
+                customer_id,
+                customer_name,
+                previous_outstanding_balance: result.previous_outstanding_balance,
+                outstanding_balance: result.outstanding_balance,
+                transactions_repaired: result.transactions_repaired,
+            });
+        }
+    }
+
+    tx.commit().await?;
+    Ok(repairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use crate::services::{customer, payment, transaction};
+    use serde_json::json;
+
+    async fn outstanding(pool: &DbPool, customer_id: i64) -> i64 {
+        customer::get_customer_by_id(pool, customer_id).await.unwrap().unwrap().outstanding_balance.as_won()
+    }
+
+    #[tokio::test]
+    async fn balances_follow_confirmed_transactions_in_date_order() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let first = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 1.0).await;
+        let draft = fixtures::draft(&pool, "sale", buyer.id, json!([
+            { "product_id": beef.id, "quantity": 5.0, "tax_rate": 0.1 },
+        ])).await;
+        let second = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 2.0).await;
+        assert_eq!(outstanding(&pool, buyer.id).await, 33000);
+
+        payment::record_receipt(&pool, serde_json::from_value(json!({
+            "customer_id": buyer.id,
+            "amount": 15000,
+            "payment_date": "2026-03-05T01:00:00Z",
+        })).unwrap()).await.unwrap();
+        assert_eq!(outstanding(&pool, buyer.id).await, 18000);
+
+        // The draft shows the balance at its place without counting itself
+        let draft = transaction::get_transaction_by_id(&pool, draft.transaction.id).await.unwrap().unwrap();
+        assert_eq!(draft.transaction.current_balance.as_won(), 11000);
+
+        transaction::cancel_transaction(&pool, first.transaction.id).await.unwrap();
+        assert_eq!(outstanding(&pool, buyer.id).await, 7000);
+        let second = transaction::get_transaction_by_id(&pool, second.transaction.id).await.unwrap().unwrap();
+        assert_eq!(second.transaction.current_balance.as_won(), 22000);
+    }
+
+    #[tokio::test]
+    async fn rebuild_repairs_only_drifted_balances() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let sale = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 1.0).await;
+        assert!(rebuild_balances(&pool).await.unwrap().is_empty());
+
+        sqlx::query("UPDATE customers SET outstanding_balance = 0 WHERE id = ?")
+            .bind(buyer.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE transactions SET current_balance = 0 WHERE id = ?")
+            .bind(sale.transaction.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let repairs = rebuild_balances(&pool).await.unwrap();
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].customer_id, buyer.id);
+        assert_eq!(repairs[0].previous_outstanding_balance, Money::ZERO);
+        assert_eq!(repairs[0].outstanding_balance.as_won(), 11000);
+        assert_eq!(repairs[0].transactions_repaired, 1);
+        assert_eq!(outstanding(&pool, buyer.id).await, 11000);
+        assert!(rebuild_balances(&pool).await.unwrap().is_empty());
+    }
+}
@@ -1,7 +1,11 @@
 use crate::database::DbPool;
 use crate::errors::{AppError, AppResult};
 use crate::models::{Company, CreateCompanyRequest, UpdateCompanyRequest};
+use crate::money::VatPolicy;
 use chrono::Utc;
+use sqlx::SqliteConnection;
+use std::path::Path;
+use super::pdf::PdfFont;
 
 
 pub async fn get_company(pool: &DbPool) -> AppResult<Option<Company>> {
@@ -14,16 +18,76 @@ pub async fn get_company(pool: &DbPool) -> AppResult<Option<Company>> {
     Ok(company)
 }
 
+fn validate_traceability_validation(traceability_validation: &str) -> AppResult<()> {
+    if !matches!(traceability_validation, "reject" | "warn") {
+        return Err(AppError::invalid_field("traceability_validation",
+            "Traceability validation must be 'reject' or 'warn'".to_string()
+        ));
+    }
+    Ok(())
+}
+
+/// The VAT rounding rule of the current company, or the default (per line,
+/// rounded) before a company has been set up
+pub async fn vat_policy(conn: &mut SqliteConnection) -> AppResult<VatPolicy> {
+    let settings: Option<(String, String)> = sqlx::query_as(
+        "SELECT vat_rounding_scope, vat_rounding_mode FROM companies ORDER BY created_at DESC LIMIT 1"
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    
+    match settings {
+        Some((scope, mode)) => VatPolicy::parse(&scope, &mode),
+        None => Ok(VatPolicy::default()),
+    }
+}
+
+/// Loads the font a `document_font_path` names
+pub(crate) fn load_document_font(path: &str) -> AppResult<PdfFont> {
+    PdfFont::load(Path::new(path)).map_err(|e| AppError::invalid_field(
+        "document_font_path",
+        format!("Document font {} cannot be used: {}", path, e),
+    ))
+}
+
+/// Checks that a document font path names a font PDFs can embed; a blank
+/// path clears the setting
+fn validate_document_font_path(path: &str) -> AppResult<()> {
+    let path = path.trim();
+    if !path.is_empty() {
+        load_document_font(path)?;
+    }
+    Ok(())
+}
+
+/// Checks the VAT rounding settings of a create or update request. Either
+/// may be given alone; the other falls back to its default for the check.
+fn validate_vat_rounding(scope: Option<&str>, mode: Option<&str>) -> AppResult<()> {
+    if scope.is_some() || mode.is_some() {
+        VatPolicy::parse(scope.unwrap_or("line"), mode.unwrap_or("round"))?;
+    }
+    Ok(())
+}
+
 pub async fn create_company(pool: &DbPool, request: CreateCompanyRequest) -> AppResult<Company> {
     let now = Utc::now();
     
+    if let Some(ref traceability_validation) = request.traceability_validation {
+        validate_traceability_validation(traceability_validation)?;
+    }
+    validate_vat_rounding(request.vat_rounding_scope.as_deref(), request.vat_rounding_mode.as_deref())?;
+    if let Some(ref document_font_path) = request.document_font_path {
+        validate_document_font_path(document_font_path)?;
+    }
+    
     let id = sqlx::query(
         r#"
         INSERT INTO companies (
             name, business_number, address, phone, email, 
-            representative, business_type, business_category,
-            created_at, updated_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            representative, business_type, business_item, default_invoice_memo,
+            write_off_expired_stock, traceability_validation, vat_rounding_scope, vat_rounding_mode,
+            document_font_path, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#
     )
     .bind(&request.name)
@@ -33,7 +97,13 @@ pub async fn create_company(pool: &DbPool, request: CreateCompanyRequest) -> App
     .bind(&request.email)
     .bind(&request.representative)
     .bind(&request.business_type)
-    .bind(&request.business_category)
+    .bind(&request.business_item)
+    .bind(&request.default_invoice_memo)
+    .bind(request.write_off_expired_stock.unwrap_or(false))
+    .bind(request.traceability_validation.as_deref().unwrap_or("reject"))
+    .bind(request.vat_rounding_scope.as_deref().unwrap_or("line"))
+    .bind(request.vat_rounding_mode.as_deref().unwrap_or("round"))
+    .bind(&request.document_font_path)
     .bind(now)
     .bind(now)
     .execute(pool)
@@ -49,6 +119,14 @@ pub async fn create_company(pool: &DbPool, request: CreateCompanyRequest) -> App
 pub async fn update_company(pool: &DbPool, id: i64, request: UpdateCompanyRequest) -> AppResult<Company> {
     let now = Utc::now();
     
+    if let Some(ref traceability_validation) = request.traceability_validation {
+        validate_traceability_validation(traceability_validation)?;
+    }
+    validate_vat_rounding(request.vat_rounding_scope.as_deref(), request.vat_rounding_mode.as_deref())?;
+    if let Some(ref document_font_path) = request.document_font_path {
+        validate_document_font_path(document_font_path)?;
+    }
+    
     // Build dynamic update query
     let mut query_parts = Vec::new();
     
@@ -73,12 +151,30 @@ pub async fn update_company(pool: &DbPool, id: i64, request: UpdateCompanyReques
     if request.business_type.is_some() {
         query_parts.push("business_type = ?");
     }
-    if request.business_category.is_some() {
-        query_parts.push("business_category = ?");
+    if request.business_item.is_some() {
+        query_parts.push("business_item = ?");
+    }
+    if request.default_invoice_memo.is_some() {
+        query_parts.push("default_invoice_memo = ?");
+    }
+    if request.write_off_expired_stock.is_some() {
+        query_parts.push("write_off_expired_stock = ?");
+    }
+    if request.traceability_validation.is_some() {
+        query_parts.push("traceability_validation = ?");
+    }
+    if request.vat_rounding_scope.is_some() {
+        query_parts.push("vat_rounding_scope = ?");
+    }
+    if request.vat_rounding_mode.is_some() {
+        query_parts.push("vat_rounding_mode = ?");
+    }
+    if request.document_font_path.is_some() {
+        query_parts.push("document_font_path = ?");
     }
     
     if query_parts.is_empty() {
-        return Err(AppError::Validation("No fields to update".to_string()));
+        return Err(AppError::validation("No fields to update".to_string()));
     }
     
     query_parts.push("updated_at = ?");
@@ -112,8 +208,26 @@ pub async fn update_company(pool: &DbPool, id: i64, request: UpdateCompanyReques
     if let Some(business_type) = &request.business_type {
         query = query.bind(business_type);
     }
-    if let Some(business_category) = &request.business_category {
-        query = query.bind(business_category);
+    if let Some(business_item) = &request.business_item {
+        query = query.bind(business_item);
+    }
+    if let Some(default_invoice_memo) = &request.default_invoice_memo {
+        query = query.bind(default_invoice_memo);
+    }
+    if let Some(write_off_expired_stock) = request.write_off_expired_stock {
+        query = query.bind(write_off_expired_stock);
+    }
+    if let Some(traceability_validation) = &request.traceability_validation {
+        query = query.bind(traceability_validation);
+    }
+    if let Some(vat_rounding_scope) = &request.vat_rounding_scope {
+        query = query.bind(vat_rounding_scope);
+    }
+    if let Some(vat_rounding_mode) = &request.vat_rounding_mode {
+        query = query.bind(vat_rounding_mode);
+    }
+    if let Some(document_font_path) = &request.document_font_path {
+        query = query.bind(document_font_path);
     }
     
     query = query.bind(now).bind(id);
@@ -35,7 +35,7 @@ pub async fn create_customer(pool: &DbPool, request: CreateCustomerRequest) -> A
     
     // Validate customer type
     if !matches!(request.customer_type.as_str(), "customer" | "supplier") {
-        return Err(AppError::Validation(
+        return Err(AppError::invalid_field("customer_type",
             "Customer type must be 'customer' or 'supplier'".to_string()
         ));
     }
@@ -43,16 +43,21 @@ pub async fn create_customer(pool: &DbPool, request: CreateCustomerRequest) -> A
     let id = sqlx::query(
         r#"
         INSERT INTO customers (
-            name, business_number, address, phone, email, 
+            name, business_number, representative, address, phone, email, 
+            business_type, business_item, contact_person,
             customer_type, created_at, updated_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#
     )
     .bind(&request.name)
     .bind(&request.business_number)
+    .bind(&request.representative)
     .bind(&request.address)
     .bind(&request.phone)
     .bind(&request.email)
+    .bind(&request.business_type)
+    .bind(&request.business_item)
+    .bind(&request.contact_person)
     .bind(&request.customer_type)
     .bind(now)
     .bind(now)
@@ -72,7 +77,7 @@ pub async fn update_customer(pool: &DbPool, id: i64, request: UpdateCustomerRequ
     // Validate customer type if provided
     if let Some(ref ctype) = request.customer_type {
         if !matches!(ctype.as_str(), "customer" | "supplier") {
-            return Err(AppError::Validation(
+            return Err(AppError::invalid_field("customer_type",
                 "Customer type must be 'customer' or 'supplier'".to_string()
             ));
         }
@@ -87,6 +92,9 @@ pub async fn update_customer(pool: &DbPool, id: i64, request: UpdateCustomerRequ
     if request.business_number.is_some() {
         query_parts.push("business_number = ?");
     }
+    if request.representative.is_some() {
+        query_parts.push("representative = ?");
+    }
     if request.address.is_some() {
         query_parts.push("address = ?");
     }
@@ -96,12 +104,24 @@ pub async fn update_customer(pool: &DbPool, id: i64, request: UpdateCustomerRequ
     if request.email.is_some() {
         query_parts.push("email = ?");
     }
+    if request.business_type.is_some() {
+        query_parts.push("business_type = ?");
+    }
+    if request.business_item.is_some() {
+        query_parts.push("business_item = ?");
+    }
+    if request.contact_person.is_some() {
+        query_parts.push("contact_person = ?");
+    }
     if request.customer_type.is_some() {
         query_parts.push("customer_type = ?");
     }
+    if request.is_active.is_some() {
+        query_parts.push("is_active = ?");
+    }
     
     if query_parts.is_empty() {
-        return Err(AppError::Validation("No fields to update".to_string()));
+        return Err(AppError::validation("No fields to update".to_string()));
     }
     
     query_parts.push("updated_at = ?");
@@ -120,6 +140,9 @@ pub async fn update_customer(pool: &DbPool, id: i64, request: UpdateCustomerRequ
     if let Some(business_number) = &request.business_number {
         query = query.bind(business_number);
     }
+    if let Some(representative) = &request.representative {
+        query = query.bind(representative);
+    }
     if let Some(address) = &request.address {
         query = query.bind(address);
     }
@@ -129,9 +152,21 @@ pub async fn update_customer(pool: &DbPool, id: i64, request: UpdateCustomerRequ
     if let Some(email) = &request.email {
         query = query.bind(email);
     }
+    if let Some(business_type) = &request.business_type {
+        query = query.bind(business_type);
+    }
+    if let Some(business_item) = &request.business_item {
+        query = query.bind(business_item);
+    }
+    if let Some(contact_person) = &request.contact_person {
+        query = query.bind(contact_person);
+    }
     if let Some(customer_type) = &request.customer_type {
         query = query.bind(customer_type);
     }
+    if let Some(is_active) = request.is_active {
+        query = query.bind(is_active);
+    }
     
     query = query.bind(now).bind(id);
     
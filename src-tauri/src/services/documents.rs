@@ -0,0 +1,461 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::{Company, DeliveryNoteOptions, TransactionItemWithProduct, TransactionWithItems};
+use crate::money::Money;
+use chrono::{DateTime, Local, Utc};
+use std::path::Path;
+use super::pdf::{Align, Color, Page, PdfDocument, PdfFont, TextStyle, PAGE_HEIGHT_MM};
+use super::{balance, company, transaction};
+
+const TEMPLATES: [&str; 2] = ["default", "striped"];
+
+/// Korean TrueType fonts embedded when the company has not set
+/// `document_font_path`, tried in order (맑은 고딕, then 나눔고딕)
+const SYSTEM_FONTS: [&str; 3] = [
+    "C:\\Windows\\Fonts\\malgun.ttf",
+    "/usr/share/fonts/truetype/nanum/NanumGothic.ttf",
+    "/Library/Fonts/NanumGothic.ttf",
+];
+
+/// Item rows per copy; longer transactions continue on further pages
+const ROWS_PER_PAGE: usize = 14;
+
+const LEFT: f64 = 10.0;
+const RIGHT: f64 = 200.0;
+const HALF_HEIGHT: f64 = PAGE_HEIGHT_MM / 2.0;
+
+const ITEM_COLUMNS: [(&str, f64, Align); 10] = [
+    ("NO", 8.0, Align::Center),
+    ("품목", 30.0, Align::Left),
+    ("이력번호", 26.0, Align::Center),
+    ("원산지", 14.0, Align::Center),
+    ("도축장", 22.0, Align::Center),
+    ("수량", 16.0, Align::Right),
+    ("단위", 10.0, Align::Center),
+    ("단가", 20.0, Align::Right),
+    ("공급가액", 24.0, Align::Right),
+    ("기타", 20.0, Align::Left),
+];
+
+/// Top half is kept by the supplier (red), bottom half by the buyer (blue)
+struct NoteCopy {
+    label: &'static str,
+    border: Color,
+    tint: Color,
+}
+
+const SUPPLIER_COPY: NoteCopy = NoteCopy {
+    label: "공급자 보관용",
+    border: Color(220, 38, 38),
+    tint: Color(254, 202, 202),
+};
+
+const BUYER_COPY: NoteCopy = NoteCopy {
+    label: "공급받는자 보관용",
+    border: Color(37, 99, 235),
+    tint: Color(219, 234, 254),
+};
+
+struct Party<'a> {
+    name: &'a str,
+    representative: &'a str,
+    business_number: &'a str,
+    business_type: &'a str,
+    business_item: &'a str,
+    address: &'a str,
+}
+
+/// Everything drawn on one page, shared by both copies
+struct NoteContent<'a> {
+    transaction: &'a TransactionWithItems,
+    supplier: Party<'a>,
+    buyer: Party<'a>,
+    items: &'a [TransactionItemWithProduct],
+    first_row: usize,
+    page: usize,
+    page_count: usize,
+    striped: bool,
+    memo: &'a str,
+    /// (label, amount) boxes of the summary row; each box has one or two rows
+    summary: Vec<Vec<(&'static str, Money)>>,
+}
+
+fn style(size: f64, bold: bool) -> TextStyle {
+    TextStyle { size, color: Color::BLACK, bold }
+}
+
+/// Baseline that centres text of `size` points vertically in a cell
+fn baseline(y: f64, height: f64, size: f64) -> f64 {
+    y + height / 2.0 + size * 0.13
+}
+
+/// Whole won with thousands separators
+fn format_won(value: Money) -> String {
+    let digits = value.as_won().unsigned_abs().to_string();
+    let groups: Vec<&str> = digits
+        .as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    let grouped = groups.join(",");
+    if value.is_negative() {
+        format!("-{}", grouped)
+    } else {
+        grouped
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cell(page: &mut Page, x: f64, y: f64, width: f64, height: f64, text: &str, align: Align, text_style: TextStyle, fill: Option<Color>, stroke: Color) {
+    page.rect(x, y, width, height, fill, Some(stroke));
+    let text = page.fit_text(text, text_style.size, width - 2.0);
+    let anchor = match align {
+        Align::Left => x + 1.0,
+        Align::Center => x + width / 2.0,
+        Align::Right => x + width - 1.0,
+    };
+    page.text(anchor, baseline(y, height, text_style.size), &text, align, text_style);
+}
+
+fn draw_party(page: &mut Page, x: f64, y: f64, title: &str, party: &Party, copy: &NoteCopy, stamp: bool) {
+    const ROW: f64 = 7.0;
+    const WIDTH: f64 = 93.0;
+    const STRIP: f64 = 7.0;
+
+    // Vertical title strip ("공급자" / "공급받는자")
+    page.rect(x, y, STRIP, ROW * 4.0, Some(copy.tint), Some(copy.border));
+    let chars: Vec<char> = title.chars().collect();
+    let step = ROW * 4.0 / (chars.len() as f64 + 1.0);
+    for (index, c) in chars.iter().enumerate() {
+        page.text(x + STRIP / 2.0, y + step * (index as f64 + 1.0) + 1.2, &c.to_string(), Align::Center, style(9.0, true));
+    }
+
+    let x = x + STRIP;
+    let label = |page: &mut Page, x: f64, y: f64, width: f64, text: &str| {
+        cell(page, x, y, width, ROW, text, Align::Center, style(8.0, true), None, copy.border);
+    };
+    let value = |page: &mut Page, x: f64, y: f64, width: f64, text: &str| {
+        cell(page, x, y, width, ROW, text, Align::Left, style(8.0, false), None, copy.border);
+    };
+
+    let rest = WIDTH - STRIP;
+    label(page, x, y, 14.0, "상호");
+    value(page, x + 14.0, y, 30.0, party.name);
+    label(page, x + 44.0, y, 10.0, "성명");
+    value(page, x + 54.0, y, rest - 54.0, party.representative);
+    if stamp {
+        page.text(x + rest - 1.5, baseline(y, ROW, 8.0), "(인)", Align::Right, TextStyle { color: Color::GRAY, ..style(8.0, false) });
+    }
+
+    label(page, x, y + ROW, 14.0, "등록번호");
+    value(page, x + 14.0, y + ROW, rest - 14.0, party.business_number);
+
+    label(page, x, y + ROW * 2.0, 14.0, "업태");
+    value(page, x + 14.0, y + ROW * 2.0, 30.0, party.business_type);
+    label(page, x + 44.0, y + ROW * 2.0, 10.0, "종목");
+    value(page, x + 54.0, y + ROW * 2.0, rest - 54.0, party.business_item);
+
+    label(page, x, y + ROW * 3.0, 14.0, "주소");
+    value(page, x + 14.0, y + ROW * 3.0, rest - 14.0, party.address);
+}
+
+fn draw_copy(page: &mut Page, top: f64, content: &NoteContent, copy: &NoteCopy) {
+    let transaction = &content.transaction.transaction;
+
+    // Header: date, title box, transaction number
+    let date = transaction.transaction_date.with_timezone(&Local).format("%Y-%m-%d").to_string();
+    page.text(LEFT + 2.0, top + 14.0, &format!("작성일: {}", date), Align::Left, style(9.0, false));
+
+    let mut number = format!("No. {}", transaction.transaction_number);
+    if content.page_count > 1 {
+        number.push_str(&format!(" ({}/{})", content.page, content.page_count));
+    }
+    page.text(RIGHT - 2.0, top + 14.0, &number, Align::Right, style(9.0, false));
+
+    let title_width = 90.0;
+    page.rect(105.0 - title_width / 2.0, top + 8.0, title_width, 9.0, Some(Color::WHITE), Some(copy.border));
+    page.text(92.0, top + 14.5, "거래명세서", Align::Right, style(15.0, true));
+    page.text(94.0, top + 14.2, &format!("({})", copy.label), Align::Left, TextStyle { color: copy.border, ..style(11.0, false) });
+
+    // Parties
+    draw_party(page, LEFT, top + 20.0, "공급자", &content.supplier, copy, true);
+    draw_party(page, RIGHT - 93.0, top + 20.0, "공급받는자", &content.buyer, copy, false);
+
+    // Items
+    let table_top = top + 52.0;
+    let mut x = LEFT;
+    for (title, width, _) in ITEM_COLUMNS {
+        cell(page, x, table_top, width, 6.0, title, Align::Center, style(7.5, true), Some(copy.tint), Color::LIGHT_GRAY);
+        x += width;
+    }
+
+    for row in 0..ROWS_PER_PAGE {
+        let y = table_top + 6.0 + row as f64 * 5.0;
+        let fill = if content.striped && row % 2 == 1 { copy.tint } else { Color::WHITE };
+
+        let values: Vec<String> = match content.items.get(row) {
+            Some(line) => vec![
+                (content.first_row + row + 1).to_string(),
+                line.product.name.clone(),
+                line.item.traceability_number.clone().unwrap_or_default(),
+                line.item.origin.clone().unwrap_or_default(),
+                line.item.slaughterhouse.clone().unwrap_or_default(),
+                format!("{:.2}", line.item.quantity),
+                line.product.unit.clone(),
+                format_won(line.item.unit_price),
+                format_won(line.item.subtotal),
+                line.item.notes.clone().unwrap_or_default(),
+            ],
+            None => vec![String::new(); ITEM_COLUMNS.len()],
+        };
+
+        let mut x = LEFT;
+        for ((_, width, align), value) in ITEM_COLUMNS.iter().zip(&values) {
+            cell(page, x, y, *width, 5.0, value, *align, style(7.5, false), Some(fill), Color::LIGHT_GRAY);
+            x += width;
+        }
+    }
+    page.rect(LEFT, table_top, RIGHT - LEFT, 6.0 + ROWS_PER_PAGE as f64 * 5.0, None, Some(copy.border));
+
+    // Summary: memo, then the amount boxes; only the last page carries the totals
+    let summary_top = table_top + 6.0 + ROWS_PER_PAGE as f64 * 5.0 + 3.0;
+    let height = 10.0;
+
+    if content.page < content.page_count {
+        page.text(RIGHT, summary_top + 6.0, "다음 장에 계속", Align::Right, TextStyle { color: Color::GRAY, ..style(9.0, false) });
+        return;
+    }
+
+    const BOX_WIDTH: f64 = 40.0;
+    const GAP: f64 = 2.0;
+    let boxes_left = RIGHT - content.summary.len() as f64 * (BOX_WIDTH + GAP) + GAP;
+
+    page.rect(LEFT, summary_top, boxes_left - GAP - LEFT, height, None, Some(copy.border));
+    let memo = page.fit_text(&format!("메모: {}", content.memo), 8.0, boxes_left - GAP - LEFT - 4.0);
+    page.text(LEFT + 2.0, baseline(summary_top, height, 8.0), &memo, Align::Left, style(8.0, false));
+
+    for (index, rows) in content.summary.iter().enumerate() {
+        let x = boxes_left + index as f64 * (BOX_WIDTH + GAP);
+        let row_height = height / rows.len() as f64;
+        for (row, (label, amount)) in rows.iter().enumerate() {
+            let y = summary_top + row as f64 * row_height;
+            let emphasised = *label == "합계액" || *label == "현잔액";
+            cell(page, x, y, 16.0, row_height, label, Align::Center, style(8.0, true), Some(copy.tint), copy.border);
+            cell(page, x + 16.0, y, BOX_WIDTH - 16.0, row_height, &format_won(*amount), Align::Right, style(8.5, emphasised), None, copy.border);
+        }
+    }
+}
+
+/// The font documents are set in: the company's `document_font_path`, else
+/// the first of `SYSTEM_FONTS` that loads, else the non-embedded reference
+/// font. A configured font that cannot be loaded is an error rather than a
+/// silent fallback.
+pub fn document_font(company: &Company) -> AppResult<PdfFont> {
+    if let Some(path) = company.document_font_path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        return company::load_document_font(path);
+    }
+
+    Ok(SYSTEM_FONTS
+        .iter()
+        .map(Path::new)
+        .filter(|path| path.exists())
+        .find_map(|path| PdfFont::load(path).ok())
+        .unwrap_or(PdfFont::Reference))
+}
+
+/// Renders a delivery note (거래명세서) as a PDF: one A4 page per 14 items,
+/// each with the supplier's copy on top and the buyer's copy below a cut
+/// line. The 'striped' template shades every other item row.
+///
+/// For a sale the company is the supplier; for a purchase the supplier is
+/// the customer record and the company receives the goods. With
+/// `show_balance` the summary adds the balance before the transaction and
+/// the balance after it (`transactions.current_balance`).
+pub fn render_delivery_note(
+    transaction: &TransactionWithItems,
+    company: &Company,
+    options: &DeliveryNoteOptions,
+) -> AppResult<Vec<u8>> {
+    let template = options.template.as_deref().unwrap_or("default");
+    if !TEMPLATES.contains(&template) {
+        return Err(AppError::validation("Template must be 'default' or 'striped'".to_string()));
+    }
+
+    let header = &transaction.transaction;
+    if header.transaction_type != "sale" && header.transaction_type != "purchase" {
+        return Err(AppError::validation(
+            "Delivery notes can only be rendered for sales and purchases".to_string()
+        ));
+    }
+
+    let customer = &transaction.customer;
+    let company_party = Party {
+        name: &company.name,
+        representative: &company.representative,
+        business_number: &company.business_number,
+        business_type: company.business_type.as_deref().unwrap_or(""),
+        business_item: company.business_item.as_deref().unwrap_or(""),
+        address: &company.address,
+    };
+    let customer_party = Party {
+        name: &customer.name,
+        representative: customer.representative.as_deref().unwrap_or(""),
+        business_number: customer.business_number.as_deref().unwrap_or(""),
+        business_type: customer.business_type.as_deref().unwrap_or(""),
+        business_item: customer.business_item.as_deref().unwrap_or(""),
+        address: customer.address.as_deref().unwrap_or(""),
+    };
+    let is_sale = header.transaction_type == "sale";
+    let (supplier, buyer) = if is_sale {
+        (company_party, customer_party)
+    } else {
+        (customer_party, company_party)
+    };
+
+    let mut summary = vec![
+        vec![("공급가액", header.subtotal), ("세액", header.tax_amount)],
+        vec![("합계액", header.total_amount)],
+    ];
+    if options.show_balance {
+        let effect = if header.status == "confirmed" {
+            balance::balance_effect(&header.transaction_type, header.total_amount)
+        } else {
+            Money::ZERO
+        };
+        summary[1].push((if is_sale { "미수금" } else { "미지급금" }, header.current_balance - effect));
+        summary.push(vec![("현잔액", header.current_balance)]);
+    }
+    // Exempt items are outside the supply value and VAT
+    if !header.exempt_amount.is_zero() {
+        summary.insert(1, vec![("면세", header.exempt_amount)]);
+    }
+
+    let memo = header
+        .notes
+        .as_deref()
+        .filter(|n| !n.trim().is_empty())
+        .or(company.default_invoice_memo.as_deref())
+        .unwrap_or("");
+
+    let chunks: Vec<&[TransactionItemWithProduct]> = if transaction.items.is_empty() {
+        vec![&[]]
+    } else {
+        transaction.items.chunks(ROWS_PER_PAGE).collect()
+    };
+
+    let mut content = NoteContent {
+        transaction,
+        supplier,
+        buyer,
+        items: &[],
+        first_row: 0,
+        page: 0,
+        page_count: chunks.len(),
+        striped: template == "striped",
+        memo,
+        summary,
+    };
+
+    let mut document = PdfDocument::new(&format!("거래명세서 {}", header.transaction_number), document_font(company)?);
+    for (index, items) in chunks.into_iter().enumerate() {
+        content.items = items;
+        content.first_row = index * ROWS_PER_PAGE;
+        content.page = index + 1;
+
+        let page = document.add_page();
+        draw_copy(page, 0.0, &content, &SUPPLIER_COPY);
+        page.line((LEFT, HALF_HEIGHT), (RIGHT, HALF_HEIGHT), Color::GRAY, 0.5, true);
+        draw_copy(page, HALF_HEIGHT, &content, &BUYER_COPY);
+    }
+
+    document.to_bytes().map_err(|e| AppError::Business(format!("Document font cannot be embedded: {}", e)))
+}
+
+/// `거래명세서_{customer}_{date}_{number}.pdf`, with characters file systems reject replaced
+pub fn delivery_note_file_name(transaction: &TransactionWithItems) -> String {
+    let name = format!(
+        "거래명세서_{}_{}_{}.pdf",
+        transaction.customer.name,
+        transaction.transaction.transaction_date.with_timezone(&Local).format("%Y%m%d"),
+        transaction.transaction.transaction_number
+    );
+    name.chars()
+        .map(|c| if "/\\:*?\"<>|".contains(c) || c.is_control() { '_' } else { c })
+        .collect()
+}
+
+async fn registered_company(pool: &DbPool) -> AppResult<Company> {
+    company::get_company(pool)
+        .await?
+        .ok_or_else(|| AppError::validation("Company information is not registered".to_string()))
+}
+
+/// Renders one transaction's delivery note and writes it to `path`
+pub async fn export_delivery_note(
+    pool: &DbPool,
+    transaction_id: i64,
+    path: &str,
+    options: DeliveryNoteOptions,
+) -> AppResult<()> {
+    if path.trim().is_empty() {
+        return Err(AppError::validation("Export path is required".to_string()));
+    }
+
+    let transaction = transaction::get_transaction_by_id(pool, transaction_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+    let company = registered_company(pool).await?;
+
+    let pdf = render_delivery_note(&transaction, &company, &options)?;
+    std::fs::write(path, pdf)?;
+    Ok(())
+}
+
+/// Writes one delivery note per sale and purchase (except cancelled ones)
+/// dated within the range into `directory`, named by
+/// `delivery_note_file_name`. Returns the paths written, in date order.
+pub async fn export_delivery_notes(
+    pool: &DbPool,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    directory: &str,
+    options: DeliveryNoteOptions,
+) -> AppResult<Vec<String>> {
+    if start_date > end_date {
+        return Err(AppError::validation("Start date must not be after end date".to_string()));
+    }
+    if directory.trim().is_empty() {
+        return Err(AppError::validation("Export directory is required".to_string()));
+    }
+
+    let ids: Vec<(i64,)> = sqlx::query_as(
+        r#"
+        SELECT id FROM transactions
+        WHERE transaction_type IN ('sale', 'purchase') AND status != 'cancelled'
+            AND transaction_date >= ? AND transaction_date <= ?
+        ORDER BY transaction_date, id
+        "#
+    )
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+
+    let company = registered_company(pool).await?;
+    std::fs::create_dir_all(directory)?;
+
+    let mut written = Vec::new();
+    for (id,) in ids {
+        let transaction = transaction::get_transaction_by_id(pool, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+
+        let pdf = render_delivery_note(&transaction, &company, &options)?;
+        let path = Path::new(directory).join(delivery_note_file_name(&transaction));
+        std::fs::write(&path, pdf)?;
+        written.push(path.to_string_lossy().into_owned());
+    }
+
+    Ok(written)
+}
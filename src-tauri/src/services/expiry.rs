@@ -0,0 +1,296 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::{CreateStockMovementRequest, StockLot};
+use chrono::{Local, NaiveDate};
+use sqlx::Row;
+use std::time::Duration;
+use super::inventory;
+
+/// How often main.rs runs the background expiry check
+pub const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Quantities below this are treated as zero
+const QUANTITY_EPSILON: f64 = 0.0005;
+
+/// Outcome of one expiry check
+#[derive(Debug, Default, serde::Serialize)]
+pub struct ExpiryCheckResult {
+    pub checked_on: Option<NaiveDate>,
+    pub lots_expired: i64,
+    pub inventories_expired: i64,
+    /// Total quantity removed by 'expired' stock movements (0 unless write-off is enabled)
+    pub quantity_written_off: f64,
+}
+
+/// An active lot whose expiry date falls within the alert window
+#[derive(Debug, serde::Serialize)]
+pub struct ExpiringLot {
+    #[serde(flatten)]
+    pub lot: StockLot,
+    pub product_name: String,
+    pub days_left: i64, // negative once the date has passed
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ExpiryAlertGroup {
+    pub location: String, // 'frozen', 'cold', 'room'
+    pub lots: Vec<ExpiringLot>,
+}
+
+/// Marks lots past their expiry date as 'expired'.
+///
+/// When the company has `write_off_expired_stock` enabled, the remaining
+/// quantity of each such lot is removed with an 'expired' stock movement,
+/// and so is the unlotted stock of products whose
+/// `product_inventory.expiry_date` has passed (that date is then cleared).
+/// Without write-off the expired stock stays on hand for the user to deal
+/// with. Safe to run repeatedly on the same day.
+pub async fn run_expiry_check(pool: &DbPool) -> AppResult<ExpiryCheckResult> {
+    let today = Local::now().date_naive();
+
+    let mut tx = pool.begin().await?;
+
+    // Take the write lock first so the lots read below cannot change underneath
+    sqlx::query("UPDATE stock_lots SET status = status WHERE 0")
+        .execute(&mut *tx)
+        .await?;
+
+    let write_off: Option<(bool,)> = sqlx::query_as(
+        "SELECT write_off_expired_stock FROM companies ORDER BY created_at DESC LIMIT 1"
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let write_off = write_off.map(|w| w.0).unwrap_or(false);
+
+    let expired_lots = sqlx::query_as::<_, StockLot>(
+        "SELECT * FROM stock_lots WHERE status = 'active' AND expiry_date < ? ORDER BY id"
+    )
+    .bind(today)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut result = ExpiryCheckResult {
+        checked_on: Some(today),
+        lots_expired: expired_lots.len() as i64,
+        ..Default::default()
+    };
+
+    for lot in expired_lots {
+        let written_off = write_off && lot.remaining_quantity > QUANTITY_EPSILON;
+
+        sqlx::query("UPDATE stock_lots SET status = 'expired', remaining_quantity = ? WHERE id = ?")
+            .bind(if written_off { 0.0 } else { lot.remaining_quantity })
+            .bind(lot.id)
+            .execute(&mut *tx)
+            .await?;
+
+        if written_off {
+            let movement = CreateStockMovementRequest {
+                product_id: lot.product_id,
+                movement_type: "expired".to_string(),
+                quantity: lot.remaining_quantity,
+                unit_price: None,
+                lot_number: Some(lot.lot_number.clone()),
+                expiry_date: Some(lot.expiry_date),
+                traceability_number: lot.traceability_number.clone(),
+                origin: lot.origin.clone(),
+                slaughterhouse: lot.slaughterhouse.clone(),
+                notes: Some("유통기한 경과 자동 폐기".to_string()),
+                created_by: None,
+                allow_negative: true,
+            };
+            inventory::record_movement(&mut tx, &movement, "adjustment", None, Some(lot.id)).await?;
+            result.quantity_written_off += lot.remaining_quantity;
+        }
+    }
+
+    // Simple (unlotted) stock tracked through product_inventory.expiry_date
+    let inventories = sqlx::query(
+        r#"
+        SELECT pi.product_id, pi.expiry_date,
+            pi.current_stock - COALESCE((
+                SELECT SUM(l.remaining_quantity) FROM stock_lots l
+                WHERE l.product_id = pi.product_id AND l.status IN ('active', 'expired')
+            ), 0.0) as unlotted_stock
+        FROM product_inventory pi
+        WHERE pi.expiry_date IS NOT NULL AND pi.expiry_date < ?
+        "#
+    )
+    .bind(today)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    result.inventories_expired = inventories.len() as i64;
+
+    if write_off {
+        for row in inventories {
+            let product_id: i64 = row.get("product_id");
+            let unlotted_stock: f64 = row.get("unlotted_stock");
+
+            if unlotted_stock > QUANTITY_EPSILON {
+                let movement = CreateStockMovementRequest {
+                    product_id,
+                    movement_type: "expired".to_string(),
+                    quantity: unlotted_stock,
+                    unit_price: None,
+                    lot_number: None,
+                    expiry_date: row.get("expiry_date"),
+                    traceability_number: None,
+                    origin: None,
+                    slaughterhouse: None,
+                    notes: Some("유통기한 경과 자동 폐기".to_string()),
+                    created_by: None,
+                    allow_negative: true,
+                };
+                inventory::record_movement(&mut tx, &movement, "adjustment", None, None).await?;
+                result.quantity_written_off += unlotted_stock;
+            }
+
+            sqlx::query("UPDATE product_inventory SET expiry_date = NULL WHERE product_id = ?")
+                .bind(product_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(result)
+}
+
+/// Active lots expiring within `days` days (including overdue ones the daily
+/// check has not processed yet), grouped by the storage location of their
+/// product. Groups are ordered frozen, cold, room; lots by expiry date.
+pub async fn get_expiry_alerts(pool: &DbPool, days: i64) -> AppResult<Vec<ExpiryAlertGroup>> {
+    if days < 0 {
+        return Err(AppError::validation("Days cannot be negative".to_string()));
+    }
+
+    let today = Local::now().date_naive();
+    let until = today + chrono::Duration::days(days);
+
+    let rows = sqlx::query(
+        r#"
+        SELECT l.*, p.name as product_name, COALESCE(pi.location, 'cold') as location
+        FROM stock_lots l
+        JOIN products p ON l.product_id = p.id
+        LEFT JOIN product_inventory pi ON pi.product_id = l.product_id
+        WHERE l.status = 'active' AND l.remaining_quantity > 0 AND l.expiry_date <= ?
+        ORDER BY l.expiry_date, l.id
+        "#
+    )
+    .bind(until)
+    .fetch_all(pool)
+    .await?;
+
+    let mut groups: Vec<ExpiryAlertGroup> = ["frozen", "cold", "room"]
+        .iter()
+        .map(|location| ExpiryAlertGroup { location: location.to_string(), lots: Vec::new() })
+        .collect();
+
+    for row in rows {
+        let lot = StockLot {
+            id: row.get("id"),
+            product_id: row.get("product_id"),
+            lot_number: row.get("lot_number"),
+            initial_quantity: row.get("initial_quantity"),
+            remaining_quantity: row.get("remaining_quantity"),
+            expiry_date: row.get("expiry_date"),
+            traceability_number: row.get("traceability_number"),
+            origin: row.get("origin"),
+            slaughterhouse: row.get("slaughterhouse"),
+            supplier_id: row.get("supplier_id"),
+            status: row.get("status"),
+            created_at: row.get("created_at"),
+            transaction_item_id: row.get("transaction_item_id"),
+        };
+        let location: String = row.get("location");
+        let days_left = (lot.expiry_date - today).num_days();
+
+        if let Some(group) = groups.iter_mut().find(|g| g.location == location) {
+            group.lots.push(ExpiringLot {
+                lot,
+                product_name: row.get("product_name"),
+                days_left,
+            });
+        }
+    }
+
+    groups.retain(|g| !g.lots.is_empty());
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use crate::services::{company, lots};
+    use serde_json::json;
+
+    fn days_from_today(days: i64) -> NaiveDate {
+        Local::now().date_naive() + chrono::Duration::days(days)
+    }
+
+    #[tokio::test]
+    async fn writes_off_expired_lots_once_when_enabled() {
+        let pool = test_pool().await;
+        let company = fixtures::company(&pool).await;
+        company::update_company(&pool, company.id, serde_json::from_value(json!({
+            "write_off_expired_stock": true,
+        })).unwrap()).await.unwrap();
+        let farm = fixtures::supplier(&pool).await;
+        let beef = fixtures::stocked_beef(&pool).await;
+        fixtures::confirmed_purchase(&pool, farm.id, beef.id, 3.0, days_from_today(-1)).await;
+        fixtures::confirmed_purchase(&pool, farm.id, beef.id, 2.0, days_from_today(5)).await;
+
+        let result = run_expiry_check(&pool).await.unwrap();
+
+        assert_eq!(result.lots_expired, 1);
+        assert_eq!(result.quantity_written_off, 3.0);
+        let stock = inventory::get_inventory_by_product(&pool, beef.id).await.unwrap().unwrap();
+        assert_eq!(stock.inventory.current_stock, 2.0);
+        let all = lots::get_all_lots(&pool, Some(beef.id)).await.unwrap();
+        let expired: Vec<_> = all.iter().filter(|l| l.status == "expired").collect();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].remaining_quantity, 0.0);
+
+        let again = run_expiry_check(&pool).await.unwrap();
+        assert_eq!(again.lots_expired, 0);
+        assert_eq!(again.quantity_written_off, 0.0);
+    }
+
+    #[tokio::test]
+    async fn alerts_group_lots_by_storage_location() {
+        let pool = test_pool().await;
+        let farm = fixtures::supplier(&pool).await;
+        let beef = fixtures::stocked_beef(&pool).await;
+        let frozen = fixtures::product_with(&pool, json!({
+            "name": "한우 사골",
+            "use_inventory_management": true,
+        })).await;
+        fixtures::confirmed_purchase(&pool, farm.id, beef.id, 1.0, days_from_today(3)).await;
+        fixtures::confirmed_purchase(&pool, farm.id, beef.id, 1.0, days_from_today(30)).await;
+        fixtures::confirmed_purchase(&pool, farm.id, frozen.id, 1.0, days_from_today(1)).await;
+        inventory::update_inventory(&pool, serde_json::from_value(json!({
+            "product_id": frozen.id,
+            "location": "frozen",
+        })).unwrap()).await.unwrap();
+
+        let groups = get_expiry_alerts(&pool, 7).await.unwrap();
+
+        let summary: Vec<(&str, Vec<(&str, i64)>)> = groups
+            .iter()
+            .map(|g| (
+                g.location.as_str(),
+                g.lots.iter().map(|l| (l.product_name.as_str(), l.days_left)).collect(),
+            ))
+            .collect();
+        assert_eq!(summary, vec![
+            ("frozen", vec![("한우 사골", 1)]),
+            ("cold", vec![("한우 등심", 3)]),
+        ]);
+
+        let result = get_expiry_alerts(&pool, -1).await;
+        assert!(matches!(result, Err(AppError::Validation { .. })));
+    }
+}
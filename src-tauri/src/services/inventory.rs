@@ -0,0 +1,690 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::{
+    CreateStockMovementRequest, InventoryWithProduct, ProductInventory, StockMovement,
+    UpdateInventoryRequest,
+};
+use chrono::{NaiveDate, Utc};
+use sqlx::{Row, SqliteConnection};
+use super::{lots, traceability};
+
+/// Stock below zero by less than this is treated as zero
+const STOCK_EPSILON: f64 = 0.0005;
+
+/// Change in current_stock caused by a movement.
+///
+/// 'in', 'out' and 'expired' movements carry a positive quantity; 'adjust'
+/// carries the signed correction itself.
+pub fn stock_change(movement_type: &str, quantity: f64) -> f64 {
+    match movement_type {
+        "in" | "adjust" => quantity,
+        "out" | "expired" => -quantity,
+        _ => 0.0,
+    }
+}
+
+const INVENTORY_SELECT: &str = r#"
+    SELECT pi.*, p.name as product_name, p.unit as product_unit
+    FROM product_inventory pi
+    JOIN products p ON pi.product_id = p.id
+"#;
+
+fn inventory_from_row(row: &sqlx::sqlite::SqliteRow) -> InventoryWithProduct {
+    InventoryWithProduct {
+        inventory: ProductInventory {
+            id: row.get("id"),
+            product_id: row.get("product_id"),
+            current_stock: row.get("current_stock"),
+            safety_stock: row.get("safety_stock"),
+            location: row.get("location"),
+            expiry_date: row.get("expiry_date"),
+            last_updated: row.get("last_updated"),
+        },
+        product_name: row.get("product_name"),
+        product_unit: row.get("product_unit"),
+    }
+}
+
+/// Stock levels of every product that has an inventory row
+pub async fn get_inventory(pool: &DbPool) -> AppResult<Vec<InventoryWithProduct>> {
+    let rows = sqlx::query(&format!("{} ORDER BY p.name", INVENTORY_SELECT))
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(inventory_from_row).collect())
+}
+
+pub async fn get_inventory_by_product(pool: &DbPool, product_id: i64) -> AppResult<Option<InventoryWithProduct>> {
+    let row = sqlx::query(&format!("{} WHERE pi.product_id = ?", INVENTORY_SELECT))
+        .bind(product_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.as_ref().map(inventory_from_row))
+}
+
+/// Updates the safety stock, location and expiry date of a product's inventory row,
+/// creating the row if needed. current_stock only changes through stock movements.
+pub async fn update_inventory(pool: &DbPool, request: UpdateInventoryRequest) -> AppResult<InventoryWithProduct> {
+    if let Some(safety_stock) = request.safety_stock {
+        if safety_stock < 0.0 {
+            return Err(AppError::validation("Safety stock cannot be negative".to_string()));
+        }
+    }
+
+    if let Some(location) = &request.location {
+        if !matches!(location.as_str(), "frozen" | "cold" | "room") {
+            return Err(AppError::validation(
+                "Location must be 'frozen', 'cold' or 'room'".to_string()
+            ));
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+
+    ensure_inventory_row(&mut tx, request.product_id).await?;
+
+    // Build dynamic update query
+    let mut query_parts = Vec::new();
+
+    if request.safety_stock.is_some() {
+        query_parts.push("safety_stock = ?");
+    }
+    if request.location.is_some() {
+        query_parts.push("location = ?");
+    }
+    if request.expiry_date.is_some() {
+        query_parts.push("expiry_date = ?");
+    }
+
+    query_parts.push("last_updated = ?");
+
+    let query_str = format!(
+        "UPDATE product_inventory SET {} WHERE product_id = ?",
+        query_parts.join(", ")
+    );
+
+    let mut query = sqlx::query(&query_str);
+
+    if let Some(safety_stock) = request.safety_stock {
+        query = query.bind(safety_stock);
+    }
+    if let Some(location) = &request.location {
+        query = query.bind(location);
+    }
+    if let Some(expiry_date) = request.expiry_date {
+        query = query.bind(expiry_date);
+    }
+
+    query.bind(Utc::now()).bind(request.product_id).execute(&mut *tx).await?;
+
+    tx.commit().await?;
+
+    get_inventory_by_product(pool, request.product_id).await?
+        .ok_or_else(|| AppError::NotFound("Updated inventory not found".to_string()))
+}
+
+/// Records a manual stock movement ('manual' reference, 'adjustment' for adjust).
+///
+/// Stock taken out ('out', 'expired', negative 'adjust') leaves the lot named
+/// by `lot_number`, or stock outside lots when none is named (see
+/// `lots::take_for_movement`); stock put in stays outside lots.
+pub async fn create_stock_movement(pool: &DbPool, mut request: CreateStockMovementRequest) -> AppResult<StockMovement> {
+    let reference_type = if request.movement_type == "adjust" { "adjustment" } else { "manual" };
+
+    let mut tx = pool.begin().await?;
+
+    // Take the write lock first so the lots read below cannot change underneath
+    sqlx::query("UPDATE stock_lots SET status = status WHERE 0")
+        .execute(&mut *tx)
+        .await?;
+
+    let change = stock_change(&request.movement_type, request.quantity);
+    let mut lot_id = None;
+    if change < 0.0 {
+        let lot = lots::take_for_movement(
+            &mut tx, request.product_id, -change, request.lot_number.as_deref()
+        ).await?;
+        if let Some(lot) = lot {
+            request.lot_number = Some(lot.lot_number.clone());
+            request.expiry_date = request.expiry_date.or(Some(lot.expiry_date));
+            lot_id = Some(lot.id);
+        }
+    }
+
+    let movement = record_movement(&mut tx, &request, reference_type, None, lot_id).await?;
+    tx.commit().await?;
+
+    Ok(movement)
+}
+
+/// Movements of one product (or all products), newest first
+pub async fn get_stock_movements(pool: &DbPool, product_id: Option<i64>) -> AppResult<Vec<StockMovement>> {
+    let movements = sqlx::query_as::<_, StockMovement>(
+        r#"
+        SELECT * FROM stock_movements
+        WHERE ? IS NULL OR product_id = ?
+        ORDER BY created_at DESC, id DESC
+        "#
+    )
+    .bind(product_id)
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(movements)
+}
+
+/// Inserts a stock movement and applies it to `product_inventory.current_stock`.
+///
+/// Both writes happen on the caller's DB transaction. Fails with a business
+/// error if the movement would take stock below zero and the request does not
+/// set `allow_negative`; the caller's transaction must then be rolled back.
+/// Lots are left to the caller: stock leaving must also leave its lot.
+pub async fn record_movement(
+    conn: &mut SqliteConnection,
+    request: &CreateStockMovementRequest,
+    reference_type: &str,
+    transaction_id: Option<i64>,
+    reference_id: Option<i64>,
+) -> AppResult<StockMovement> {
+    match request.movement_type.as_str() {
+        "in" | "out" | "expired" => {
+            if request.quantity <= 0.0 {
+                return Err(AppError::validation("Movement quantity must be positive".to_string()));
+            }
+        }
+        "adjust" => {
+            if request.quantity == 0.0 {
+                return Err(AppError::validation("Adjustment quantity cannot be zero".to_string()));
+            }
+        }
+        _ => {
+            return Err(AppError::validation(
+                "Movement type must be 'in', 'out', 'adjust' or 'expired'".to_string()
+            ))
+        }
+    }
+
+    if let Some(unit_price) = request.unit_price {
+        if unit_price.is_negative() {
+            return Err(AppError::validation("Unit price cannot be negative".to_string()));
+        }
+    }
+
+    let traceability = traceability::check_traceability_number(
+        conn, request.traceability_number.clone()
+    ).await?;
+
+    let now = Utc::now();
+
+    ensure_inventory_row(conn, request.product_id).await?;
+
+    let current_stock: (f64,) = sqlx::query_as(
+        r#"
+        UPDATE product_inventory SET current_stock = current_stock + ?, last_updated = ?
+        WHERE product_id = ?
+        RETURNING current_stock
+        "#
+    )
+    .bind(stock_change(&request.movement_type, request.quantity))
+    .bind(now)
+    .bind(request.product_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if current_stock.0 < -STOCK_EPSILON && !request.allow_negative {
+        return Err(AppError::Business(format!(
+            "Insufficient stock for product {}: the movement would leave {}",
+            request.product_id, current_stock.0
+        )));
+    }
+
+    let movement = sqlx::query_as::<_, StockMovement>(
+        r#"
+        INSERT INTO stock_movements (
+            product_id, movement_type, quantity, unit_price, lot_number, expiry_date,
+            traceability_number, traceability_warning, origin, slaughterhouse, transaction_id,
+            reference_type, reference_id, notes, created_at, created_by
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING *
+        "#
+    )
+    .bind(request.product_id)
+    .bind(&request.movement_type)
+    .bind(request.quantity)
+    .bind(request.unit_price)
+    .bind(&request.lot_number)
+    .bind(request.expiry_date)
+    .bind(&traceability.number)
+    .bind(&traceability.warning)
+    .bind(&request.origin)
+    .bind(&request.slaughterhouse)
+    .bind(transaction_id)
+    .bind(reference_type)
+    .bind(reference_id)
+    .bind(&request.notes)
+    .bind(now)
+    .bind(&request.created_by)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(movement)
+}
+
+/// Writes the stock movements of a sale or purchase being confirmed.
+///
+/// Every item whose product uses inventory management is handled with
+/// `reference_id` pointing at the item:
+/// - a purchase item creates a lot (it must carry an expiry date) and an
+///   'in' movement ('purchase' reference) for it; products that do not
+///   track expiry get the 'in' movement without a lot;
+/// - a sale item draws from the product's lots (see `lots::consume_lots`)
+///   and gets one 'out' movement ('sales' reference) per lot, plus one
+///   without a lot for any quantity the lots could not cover.
+///
+/// A sale that would take stock below zero fails. Call it on the same DB
+/// transaction as the confirmation.
+pub async fn record_transaction_movements(conn: &mut SqliteConnection, transaction_id: i64) -> AppResult<()> {
+    let rows = sqlx::query(
+        r#"
+        SELECT ti.id, ti.product_id, ti.quantity, ti.unit_price, ti.expiry_date,
+            COALESCE(ti.traceability_number, p.traceability_number) as traceability_number,
+            COALESCE(ti.origin, p.origin) as origin,
+            COALESCE(ti.slaughterhouse, p.slaughterhouse) as slaughterhouse,
+            p.name as product_name, p.track_expiry, t.transaction_type, t.transaction_date, t.customer_id
+        FROM transaction_items ti
+        JOIN transactions t ON ti.transaction_id = t.id
+        JOIN products p ON ti.product_id = p.id
+        WHERE ti.transaction_id = ? AND p.use_inventory_management = true
+            AND t.transaction_type IN ('sale', 'purchase')
+        ORDER BY ti.id
+        "#
+    )
+    .bind(transaction_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for row in rows {
+        let item_id: i64 = row.get("id");
+        let product_id: i64 = row.get("product_id");
+        let quantity: f64 = row.get("quantity");
+        let traceability_number: Option<String> = row.get("traceability_number");
+        let origin: Option<String> = row.get("origin");
+        let slaughterhouse: Option<String> = row.get("slaughterhouse");
+        let transaction_type: String = row.get("transaction_type");
+
+        let mut movement = CreateStockMovementRequest {
+            product_id,
+            movement_type: "in".to_string(),
+            quantity,
+            unit_price: row.get("unit_price"),
+            lot_number: None,
+            expiry_date: None,
+            traceability_number: traceability_number.clone(),
+            origin: origin.clone(),
+            slaughterhouse: slaughterhouse.clone(),
+            notes: None,
+            created_by: None,
+            allow_negative: false,
+        };
+
+        if transaction_type == "purchase" && !row.get::<bool, _>("track_expiry") {
+            movement.expiry_date = row.get("expiry_date");
+            record_movement(conn, &movement, "purchase", Some(transaction_id), Some(item_id)).await?;
+            continue;
+        }
+
+        if transaction_type == "purchase" {
+            let expiry_date: Option<NaiveDate> = row.get("expiry_date");
+            let expiry_date = expiry_date.ok_or_else(|| AppError::validation(format!(
+                "Expiry date is required to receive '{}' into stock",
+                row.get::<String, _>("product_name")
+            )))?;
+
+            let lot = lots::create_lot(conn, &lots::NewLot {
+                product_id,
+                transaction_item_id: item_id,
+                supplier_id: row.get("customer_id"),
+                received_at: row.get("transaction_date"),
+                quantity,
+                expiry_date,
+                traceability_number: traceability_number.as_deref(),
+                origin: origin.as_deref(),
+                slaughterhouse: slaughterhouse.as_deref(),
+            }).await?;
+
+            movement.lot_number = Some(lot.lot_number);
+            movement.expiry_date = Some(lot.expiry_date);
+            record_movement(conn, &movement, "purchase", Some(transaction_id), Some(item_id)).await?;
+            continue;
+        }
+
+        movement.movement_type = "out".to_string();
+
+        let consumed = lots::consume_lots(
+            conn, item_id, product_id, quantity, traceability_number.as_deref()
+        ).await?;
+
+        let mut unlotted = quantity;
+        for (lot, taken) in consumed {
+            unlotted -= taken;
+
+            let lot_movement = CreateStockMovementRequest {
+                quantity: taken,
+                lot_number: Some(lot.lot_number),
+                expiry_date: Some(lot.expiry_date),
+                traceability_number: lot.traceability_number.or_else(|| traceability_number.clone()),
+                origin: lot.origin.or_else(|| origin.clone()),
+                slaughterhouse: lot.slaughterhouse.or_else(|| slaughterhouse.clone()),
+                ..movement.clone()
+            };
+            record_movement(conn, &lot_movement, "sales", Some(transaction_id), Some(item_id)).await?;
+        }
+
+        // Stock received before lots were tracked, or by manual movements
+        if unlotted > STOCK_EPSILON {
+            movement.quantity = unlotted;
+            record_movement(conn, &movement, "sales", Some(transaction_id), Some(item_id)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the stock movements of a return (negative quantities, see
+/// `returns::create_return`), with `reference_id` pointing at the return item.
+///
+/// A sale return puts the goods back on hand with 'in' movements ('sales'
+/// reference), into the lots the original item drew from where possible. A
+/// purchase return sends goods back to the supplier with 'out' movements
+/// ('purchase' reference) from the lot the original item created, and fails
+/// if that stock is no longer there.
+pub async fn record_return_movements(conn: &mut SqliteConnection, transaction_id: i64) -> AppResult<()> {
+    let rows = sqlx::query(
+        r#"
+        SELECT ti.id, ti.product_id, ti.quantity, ti.unit_price, ti.returned_item_id,
+            COALESCE(ti.traceability_number, p.traceability_number) as traceability_number,
+            COALESCE(ti.origin, p.origin) as origin,
+            COALESCE(ti.slaughterhouse, p.slaughterhouse) as slaughterhouse,
+            t.transaction_type
+        FROM transaction_items ti
+        JOIN transactions t ON ti.transaction_id = t.id
+        JOIN products p ON ti.product_id = p.id
+        WHERE ti.transaction_id = ? AND p.use_inventory_management = true
+            AND ti.returned_item_id IS NOT NULL
+        ORDER BY ti.id
+        "#
+    )
+    .bind(transaction_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for row in rows {
+        let item_id: i64 = row.get("id");
+        let returned_item_id: i64 = row.get("returned_item_id");
+        let quantity = -row.get::<f64, _>("quantity");
+        let transaction_type: String = row.get("transaction_type");
+
+        let movement = CreateStockMovementRequest {
+            product_id: row.get("product_id"),
+            movement_type: if transaction_type == "sale" { "in" } else { "out" }.to_string(),
+            quantity,
+            unit_price: row.get("unit_price"),
+            lot_number: None,
+            expiry_date: None,
+            traceability_number: row.get("traceability_number"),
+            origin: row.get("origin"),
+            slaughterhouse: row.get("slaughterhouse"),
+            notes: Some("반품".to_string()),
+            created_by: None,
+            allow_negative: false,
+        };
+
+        if transaction_type == "purchase" {
+            let lot = lots::take_from_purchase_lot(conn, returned_item_id, quantity).await?;
+            let movement = CreateStockMovementRequest {
+                lot_number: lot.as_ref().map(|l| l.lot_number.clone()),
+                expiry_date: lot.as_ref().map(|l| l.expiry_date),
+                ..movement
+            };
+            record_movement(conn, &movement, "purchase", Some(transaction_id), Some(item_id)).await?;
+            continue;
+        }
+
+        let restocked = lots::return_to_lots(conn, returned_item_id, quantity).await?;
+
+        let mut unlotted = quantity;
+        for (lot, back) in restocked {
+            unlotted -= back;
+
+            let lot_movement = CreateStockMovementRequest {
+                quantity: back,
+                lot_number: Some(lot.lot_number),
+                expiry_date: Some(lot.expiry_date),
+                traceability_number: lot.traceability_number.or_else(|| movement.traceability_number.clone()),
+                origin: lot.origin.or_else(|| movement.origin.clone()),
+                slaughterhouse: lot.slaughterhouse.or_else(|| movement.slaughterhouse.clone()),
+                ..movement.clone()
+            };
+            record_movement(conn, &lot_movement, "sales", Some(transaction_id), Some(item_id)).await?;
+        }
+
+        if unlotted > STOCK_EPSILON {
+            let movement = CreateStockMovementRequest { quantity: unlotted, ..movement };
+            record_movement(conn, &movement, "sales", Some(transaction_id), Some(item_id)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes compensating 'cancellation' movements for the stock movements a
+/// confirmed sale or purchase made, with `reference_id` pointing at the
+/// movement being reversed.
+///
+/// Reversals are always applied, even when a cancelled purchase's stock has
+/// already been sold and the level goes below zero, so the movement history
+/// stays a faithful record. The transaction's lots are restored as well.
+pub async fn reverse_transaction_movements(conn: &mut SqliteConnection, transaction_id: i64) -> AppResult<()> {
+    let movements = sqlx::query_as::<_, StockMovement>(
+        r#"
+        SELECT * FROM stock_movements
+        WHERE transaction_id = ? AND reference_type IN ('sales', 'purchase')
+        ORDER BY id
+        "#
+    )
+    .bind(transaction_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for movement in movements {
+        let request = CreateStockMovementRequest {
+            product_id: movement.product_id,
+            movement_type: if movement.movement_type == "out" { "in" } else { "out" }.to_string(),
+            quantity: movement.quantity,
+            unit_price: movement.unit_price,
+            lot_number: movement.lot_number,
+            expiry_date: movement.expiry_date,
+            traceability_number: movement.traceability_number,
+            origin: movement.origin,
+            slaughterhouse: movement.slaughterhouse,
+            notes: None,
+            created_by: None,
+            allow_negative: true,
+        };
+
+        record_movement(conn, &request, "cancellation", Some(transaction_id), Some(movement.id)).await?;
+    }
+
+    lots::restore_transaction_lots(conn, transaction_id).await?;
+
+    Ok(())
+}
+
+/// Creates the product's inventory row with the table defaults if it has none
+async fn ensure_inventory_row(conn: &mut SqliteConnection, product_id: i64) -> AppResult<()> {
+    let product_exists = sqlx::query("SELECT id FROM products WHERE id = ?")
+        .bind(product_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    if product_exists.is_none() {
+        return Err(AppError::NotFound("Product not found".to_string()));
+    }
+
+    sqlx::query(
+        "INSERT INTO product_inventory (product_id, last_updated) VALUES (?, ?) ON CONFLICT (product_id) DO NOTHING"
+    )
+    .bind(product_id)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use serde_json::{json, Value};
+
+    async fn move_stock(pool: &DbPool, movement: Value) -> AppResult<StockMovement> {
+        create_stock_movement(pool, serde_json::from_value(movement).unwrap()).await
+    }
+
+    async fn stock_of(pool: &DbPool, product_id: i64) -> f64 {
+        get_inventory_by_product(pool, product_id).await.unwrap().unwrap().inventory.current_stock
+    }
+
+    #[tokio::test]
+    async fn manual_outbound_movements_leave_the_named_lot() {
+        let pool = test_pool().await;
+        let farm = fixtures::supplier(&pool).await;
+        let beef = fixtures::stocked_beef(&pool).await;
+        let expiry_date = chrono::Local::now().date_naive() + chrono::Duration::days(10);
+        fixtures::confirmed_purchase(&pool, farm.id, beef.id, 5.0, expiry_date).await;
+        let lot = lots::get_active_lots(&pool, beef.id).await.unwrap().remove(0);
+
+        let movement = move_stock(&pool, json!({
+            "product_id": beef.id,
+            "movement_type": "out",
+            "quantity": 2.0,
+            "lot_number": lot.lot_number,
+        })).await.unwrap();
+
+        assert_eq!(movement.expiry_date, Some(expiry_date));
+        assert_eq!(stock_of(&pool, beef.id).await, 3.0);
+        let lots = lots::get_active_lots(&pool, beef.id).await.unwrap();
+        assert_eq!(lots[0].remaining_quantity, 3.0);
+
+        let result = move_stock(&pool, json!({
+            "product_id": beef.id,
+            "movement_type": "out",
+            "quantity": 4.0,
+            "lot_number": lot.lot_number,
+        })).await;
+        assert!(matches!(result, Err(AppError::Validation { field: Some(ref f), .. }) if f == "quantity"));
+        assert_eq!(stock_of(&pool, beef.id).await, 3.0);
+    }
+
+    #[tokio::test]
+    async fn manual_outbound_movements_without_a_lot_only_take_unlotted_stock() {
+        let pool = test_pool().await;
+        let farm = fixtures::supplier(&pool).await;
+        let beef = fixtures::stocked_beef(&pool).await;
+        let expiry_date = chrono::Local::now().date_naive() + chrono::Duration::days(10);
+        fixtures::confirmed_purchase(&pool, farm.id, beef.id, 5.0, expiry_date).await;
+        move_stock(&pool, json!({ "product_id": beef.id, "movement_type": "in", "quantity": 1.0 }))
+            .await
+            .unwrap();
+
+        move_stock(&pool, json!({ "product_id": beef.id, "movement_type": "out", "quantity": 1.0 }))
+            .await
+            .unwrap();
+        let result = move_stock(&pool, json!({ "product_id": beef.id, "movement_type": "expired", "quantity": 1.0 }))
+            .await;
+
+        assert!(matches!(result, Err(AppError::Validation { field: Some(ref f), .. }) if f == "lot_number"));
+        assert_eq!(stock_of(&pool, beef.id).await, 5.0);
+        let lots = lots::get_active_lots(&pool, beef.id).await.unwrap();
+        assert_eq!(lots[0].remaining_quantity, 5.0);
+    }
+
+    #[tokio::test]
+    async fn manual_outbound_movements_of_products_without_lots() {
+        let pool = test_pool().await;
+        let beef = fixtures::stocked_beef(&pool).await;
+        move_stock(&pool, json!({ "product_id": beef.id, "movement_type": "in", "quantity": 2.0 }))
+            .await
+            .unwrap();
+
+        move_stock(&pool, json!({ "product_id": beef.id, "movement_type": "out", "quantity": 1.5 }))
+            .await
+            .unwrap();
+
+        assert_eq!(stock_of(&pool, beef.id).await, 0.5);
+    }
+
+    /// (movement_type, quantity, reference_type, transaction_id) of a product's movements, oldest first
+    async fn movements_of(pool: &DbPool, product_id: i64) -> Vec<(String, f64, Option<String>, Option<i64>)> {
+        let mut movements = get_stock_movements(pool, Some(product_id)).await.unwrap();
+        movements.sort_by_key(|m| m.id);
+        movements.into_iter().map(|m| (m.movement_type, m.quantity, m.reference_type, m.transaction_id)).collect()
+    }
+
+    #[tokio::test]
+    async fn confirming_and_cancelling_move_stock() {
+        let pool = test_pool().await;
+        let farm = fixtures::supplier(&pool).await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::stocked_beef(&pool).await;
+        let unstocked = fixtures::beef(&pool).await;
+        let expiry_date = chrono::Local::now().date_naive() + chrono::Duration::days(10);
+        let purchase = fixtures::confirmed_purchase(&pool, farm.id, beef.id, 5.0, expiry_date).await;
+
+        let sale = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 2.0).await;
+        assert_eq!(stock_of(&pool, beef.id).await, 3.0);
+        crate::services::transaction::cancel_transaction(&pool, sale.transaction.id).await.unwrap();
+
+        assert_eq!(stock_of(&pool, beef.id).await, 5.0);
+        let p = Some(purchase.transaction.id);
+        let s = Some(sale.transaction.id);
+        assert_eq!(movements_of(&pool, beef.id).await, vec![
+            ("in".to_string(), 5.0, Some("purchase".to_string()), p),
+            ("out".to_string(), 2.0, Some("sales".to_string()), s),
+            ("in".to_string(), 2.0, Some("cancellation".to_string()), s),
+        ]);
+        assert_eq!(lots::get_active_lots(&pool, beef.id).await.unwrap()[0].remaining_quantity, 5.0);
+
+        // Products outside inventory management move no stock
+        fixtures::confirmed_sale(&pool, buyer.id, unstocked.id, 1.0).await;
+        assert!(movements_of(&pool, unstocked.id).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn confirming_a_sale_beyond_stock_leaves_it_a_draft() {
+        let pool = test_pool().await;
+        let farm = fixtures::supplier(&pool).await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::stocked_beef(&pool).await;
+        let expiry_date = chrono::Local::now().date_naive() + chrono::Duration::days(10);
+        fixtures::confirmed_purchase(&pool, farm.id, beef.id, 1.0, expiry_date).await;
+        let sale = fixtures::draft(&pool, "sale", buyer.id, json!([
+            { "product_id": beef.id, "quantity": 2.0, "tax_rate": 0.1 },
+        ])).await;
+
+        let result = crate::services::transaction::confirm_transaction(&pool, sale.transaction.id).await;
+
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|t| t.transaction.status));
+        let sale = crate::services::transaction::get_transaction_by_id(&pool, sale.transaction.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sale.transaction.status, "draft");
+        assert_eq!(stock_of(&pool, beef.id).await, 1.0);
+        assert_eq!(movements_of(&pool, beef.id).await.len(), 1);
+    }
+}
@@ -0,0 +1,581 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::StockLot;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use sqlx::{Row, SqliteConnection};
+
+/// Quantities below this are treated as zero when draining lots
+const QUANTITY_EPSILON: f64 = 0.0005;
+
+/// A lot about to be created from a confirmed purchase item
+pub struct NewLot<'a> {
+    pub product_id: i64,
+    pub transaction_item_id: i64,
+    pub supplier_id: i64,
+    pub received_at: DateTime<Utc>,
+    pub quantity: f64,
+    pub expiry_date: NaiveDate,
+    pub traceability_number: Option<&'a str>,
+    pub origin: Option<&'a str>,
+    pub slaughterhouse: Option<&'a str>,
+}
+
+/// Which lots a sale item drew from, for display next to the item
+#[derive(Debug, serde::Serialize)]
+pub struct ItemLotConsumption {
+    pub transaction_item_id: i64,
+    pub product_id: i64,
+    pub lot_id: i64,
+    pub lot_number: String,
+    pub expiry_date: NaiveDate,
+    pub traceability_number: Option<String>,
+    pub quantity: f64,
+}
+
+/// Next `LOT-YYYYMMDD-NNN` number for the local day of `received_at`
+async fn next_lot_number(conn: &mut SqliteConnection, received_at: DateTime<Utc>) -> AppResult<String> {
+    let prefix = format!("LOT-{}-", received_at.with_timezone(&Local).format("%Y%m%d"));
+
+    let numbers: Vec<(String,)> = sqlx::query_as("SELECT lot_number FROM stock_lots WHERE lot_number LIKE ?")
+        .bind(format!("{}%", prefix))
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let last = numbers
+        .iter()
+        .filter_map(|(n,)| n.strip_prefix(&prefix).and_then(|seq| seq.parse::<i64>().ok()))
+        .max()
+        .unwrap_or(0);
+
+    Ok(format!("{}{:03}", prefix, last + 1))
+}
+
+/// Creates an active lot holding the whole quantity of a purchase item
+pub async fn create_lot(conn: &mut SqliteConnection, lot: &NewLot<'_>) -> AppResult<StockLot> {
+    let lot_number = next_lot_number(conn, lot.received_at).await?;
+
+    let created = sqlx::query_as::<_, StockLot>(
+        r#"
+        INSERT INTO stock_lots (
+            product_id, lot_number, initial_quantity, remaining_quantity, expiry_date,
+            traceability_number, origin, slaughterhouse, supplier_id, status, created_at,
+            transaction_item_id
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 'active', ?, ?)
+        RETURNING *
+        "#
+    )
+    .bind(lot.product_id)
+    .bind(&lot_number)
+    .bind(lot.quantity)
+    .bind(lot.quantity)
+    .bind(lot.expiry_date)
+    .bind(lot.traceability_number)
+    .bind(lot.origin)
+    .bind(lot.slaughterhouse)
+    .bind(lot.supplier_id)
+    .bind(Utc::now())
+    .bind(lot.transaction_item_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(created)
+}
+
+/// Draws `quantity` of a product from its active lots for one sale item.
+///
+/// Lots carrying the item's traceability number are used first, then the
+/// product's `lot_consumption` order decides: 'fefo' takes the earliest
+/// expiry date first, 'fifo' the oldest lot. Each draw is recorded in
+/// stock_lot_consumptions and a lot that reaches zero becomes 'finished'.
+/// Lots past their expiry date are never drawn from, even before the daily
+/// expiry check marks them expired. Returns the lots drawn from with the
+/// quantity taken from each; the sum is less than `quantity` when the lots
+/// run out. Fails when the rest could only come from lots past their date.
+pub async fn consume_lots(
+    conn: &mut SqliteConnection,
+    transaction_item_id: i64,
+    product_id: i64,
+    quantity: f64,
+    traceability_number: Option<&str>,
+) -> AppResult<Vec<(StockLot, f64)>> {
+    let lot_consumption: (String,) = sqlx::query_as("SELECT lot_consumption FROM products WHERE id = ?")
+        .bind(product_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let order = if lot_consumption.0 == "fifo" { "created_at, id" } else { "expiry_date, id" };
+    let today = Local::now().date_naive();
+
+    let lots = sqlx::query_as::<_, StockLot>(&format!(
+        r#"
+        SELECT * FROM stock_lots
+        WHERE product_id = ? AND status = 'active' AND remaining_quantity > 0
+            AND expiry_date >= ?
+        ORDER BY CASE WHEN ? IS NOT NULL AND traceability_number = ? THEN 0 ELSE 1 END, {}
+        "#,
+        order
+    ))
+    .bind(product_id)
+    .bind(today)
+    .bind(traceability_number)
+    .bind(traceability_number)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let now = Utc::now();
+    let mut remaining = quantity;
+    let mut consumed = Vec::new();
+
+    for mut lot in lots {
+        if remaining <= QUANTITY_EPSILON {
+            break;
+        }
+
+        let take = remaining.min(lot.remaining_quantity);
+        lot.remaining_quantity -= take;
+        remaining -= take;
+
+        if lot.remaining_quantity <= QUANTITY_EPSILON {
+            lot.remaining_quantity = 0.0;
+            lot.status = "finished".to_string();
+        }
+
+        sqlx::query("UPDATE stock_lots SET remaining_quantity = ?, status = ? WHERE id = ?")
+            .bind(lot.remaining_quantity)
+            .bind(&lot.status)
+            .bind(lot.id)
+            .execute(&mut *conn)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO stock_lot_consumptions (transaction_item_id, lot_id, quantity, created_at) VALUES (?, ?, ?, ?)"
+        )
+        .bind(transaction_item_id)
+        .bind(lot.id)
+        .bind(take)
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+
+        consumed.push((lot, take));
+    }
+
+    // Stock outside lots (received before lots were tracked, by manual
+    // movements or without expiry tracking) may cover the rest, but stock in
+    // lots past their date must not be sold as if it were unlotted. Expired
+    // lots keep their quantity on hand unless the expiry check wrote it off.
+    if remaining > QUANTITY_EPSILON {
+        let (unlotted, past_date): (f64, f64) = sqlx::query_as(
+            r#"
+            SELECT
+                COALESCE((SELECT current_stock FROM product_inventory WHERE product_id = ?), 0.0)
+                    - COALESCE((SELECT SUM(remaining_quantity) FROM stock_lots
+                        WHERE product_id = ? AND status IN ('active', 'expired')), 0.0),
+                COALESCE((SELECT SUM(remaining_quantity) FROM stock_lots
+                    WHERE product_id = ?
+                        AND (status = 'expired' OR (status = 'active' AND expiry_date < ?))), 0.0)
+            "#
+        )
+        .bind(product_id)
+        .bind(product_id)
+        .bind(product_id)
+        .bind(today)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        // current_stock still includes what was just taken from the lots
+        let unlotted = unlotted - (quantity - remaining);
+        if past_date > QUANTITY_EPSILON && remaining > unlotted + QUANTITY_EPSILON {
+            return Err(AppError::Business(format!(
+                "Product {} needs {:.2} more, but the remaining lots are past their expiry date",
+                product_id, remaining
+            )));
+        }
+    }
+
+    Ok(consumed)
+}
+
+/// Undoes the lot effects of a transaction being cancelled.
+///
+/// Quantities a sale drew are returned to their lots (a 'finished' lot
+/// becomes 'active' again; an expired one stays expired) and lots a purchase
+/// created are emptied and marked 'cancelled'. The consumption rows are
+/// kept as history.
+pub async fn restore_transaction_lots(conn: &mut SqliteConnection, transaction_id: i64) -> AppResult<()> {
+    let consumptions = sqlx::query(
+        r#"
+        SELECT c.lot_id, c.quantity
+        FROM stock_lot_consumptions c
+        JOIN transaction_items ti ON c.transaction_item_id = ti.id
+        WHERE ti.transaction_id = ?
+        ORDER BY c.id
+        "#
+    )
+    .bind(transaction_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for row in consumptions {
+        sqlx::query(
+            r#"
+            UPDATE stock_lots
+            SET remaining_quantity = remaining_quantity + ?,
+                status = CASE WHEN status = 'finished' THEN 'active' ELSE status END
+            WHERE id = ?
+            "#
+        )
+        .bind(row.get::<f64, _>("quantity"))
+        .bind(row.get::<i64, _>("lot_id"))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE stock_lots SET remaining_quantity = 0, status = 'cancelled'
+        WHERE transaction_item_id IN (SELECT id FROM transaction_items WHERE transaction_id = ?)
+        "#
+    )
+    .bind(transaction_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Puts quantity returned from a sale item back into the lots it was drawn
+/// from, newest draw first, and shrinks the consumption rows to match so
+/// they keep showing what the customer still holds. Returns the lots
+/// restocked with the quantity each got back; the sum is less than
+/// `quantity` when part of the item was sold without a lot.
+pub async fn return_to_lots(
+    conn: &mut SqliteConnection,
+    transaction_item_id: i64,
+    quantity: f64,
+) -> AppResult<Vec<(StockLot, f64)>> {
+    let consumptions: Vec<(i64, i64, f64)> = sqlx::query_as(
+        "SELECT id, lot_id, quantity FROM stock_lot_consumptions WHERE transaction_item_id = ? ORDER BY id DESC"
+    )
+    .bind(transaction_item_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut remaining = quantity;
+    let mut restocked = Vec::new();
+
+    for (consumption_id, lot_id, consumed) in consumptions {
+        if remaining <= QUANTITY_EPSILON {
+            break;
+        }
+
+        let back = remaining.min(consumed);
+        remaining -= back;
+
+        if consumed - back <= QUANTITY_EPSILON {
+            sqlx::query("DELETE FROM stock_lot_consumptions WHERE id = ?")
+                .bind(consumption_id)
+                .execute(&mut *conn)
+                .await?;
+        } else {
+            sqlx::query("UPDATE stock_lot_consumptions SET quantity = quantity - ? WHERE id = ?")
+                .bind(back)
+                .bind(consumption_id)
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        let lot = sqlx::query_as::<_, StockLot>(
+            r#"
+            UPDATE stock_lots
+            SET remaining_quantity = remaining_quantity + ?,
+                status = CASE WHEN status = 'finished' THEN 'active' ELSE status END
+            WHERE id = ?
+            RETURNING *
+            "#
+        )
+        .bind(back)
+        .bind(lot_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        restocked.push((lot, back));
+    }
+
+    Ok(restocked)
+}
+
+/// Takes quantity returned to the supplier out of the lot a purchase item
+/// created. Fails if the lot no longer holds that much; `None` when the
+/// item created no lot.
+pub async fn take_from_purchase_lot(
+    conn: &mut SqliteConnection,
+    transaction_item_id: i64,
+    quantity: f64,
+) -> AppResult<Option<StockLot>> {
+    let lot = sqlx::query_as::<_, StockLot>(
+        "SELECT * FROM stock_lots WHERE transaction_item_id = ? AND status != 'cancelled'"
+    )
+    .bind(transaction_item_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(mut lot) = lot else {
+        return Ok(None);
+    };
+
+    if quantity > lot.remaining_quantity + QUANTITY_EPSILON {
+        return Err(AppError::Business(format!(
+            "Lot {} has only {} left to return",
+            lot.lot_number, lot.remaining_quantity
+        )));
+    }
+
+    lot.remaining_quantity = (lot.remaining_quantity - quantity).max(0.0);
+    if lot.remaining_quantity <= QUANTITY_EPSILON && lot.status == "active" {
+        lot.remaining_quantity = 0.0;
+        lot.status = "finished".to_string();
+    }
+
+    sqlx::query("UPDATE stock_lots SET remaining_quantity = ?, status = ? WHERE id = ?")
+        .bind(lot.remaining_quantity)
+        .bind(&lot.status)
+        .bind(lot.id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(Some(lot))
+}
+
+/// Takes stock leaving by a manual 'out', 'expired' or negative 'adjust'
+/// movement out of the lots, so they never hold more than is on hand.
+///
+/// With a lot number the quantity comes out of that lot, active or expired;
+/// a lot reaching zero is 'finished' (an expired one stays expired). Without
+/// one it must come from stock outside lots. Returns the lot taken from.
+pub async fn take_for_movement(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    quantity: f64,
+    lot_number: Option<&str>,
+) -> AppResult<Option<StockLot>> {
+    let Some(lot_number) = lot_number.map(str::trim).filter(|n| !n.is_empty()) else {
+        let (on_hand, in_lots): (f64, f64) = sqlx::query_as(
+            r#"
+            SELECT
+                COALESCE((SELECT current_stock FROM product_inventory WHERE product_id = ?), 0.0),
+                COALESCE((SELECT SUM(remaining_quantity) FROM stock_lots
+                    WHERE product_id = ? AND status IN ('active', 'expired')), 0.0)
+            "#
+        )
+        .bind(product_id)
+        .bind(product_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let unlotted = on_hand - in_lots;
+        if in_lots > QUANTITY_EPSILON && quantity > unlotted + QUANTITY_EPSILON {
+            return Err(AppError::invalid_field("lot_number", format!(
+                "Only {:.2} of product {} is outside lots; choose the lot the stock leaves from",
+                unlotted.max(0.0), product_id
+            )));
+        }
+        return Ok(None);
+    };
+
+    let lot = sqlx::query_as::<_, StockLot>(
+        "SELECT * FROM stock_lots WHERE product_id = ? AND lot_number = ? AND status IN ('active', 'expired')"
+    )
+    .bind(product_id)
+    .bind(lot_number)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(mut lot) = lot else {
+        return Err(AppError::invalid_field("lot_number", format!(
+            "Product {} has no lot {} holding stock", product_id, lot_number
+        )));
+    };
+
+    if quantity > lot.remaining_quantity + QUANTITY_EPSILON {
+        return Err(AppError::invalid_field("quantity", format!(
+            "Lot {} has only {:.2} left", lot.lot_number, lot.remaining_quantity
+        )));
+    }
+
+    lot.remaining_quantity = (lot.remaining_quantity - quantity).max(0.0);
+    if lot.remaining_quantity <= QUANTITY_EPSILON {
+        lot.remaining_quantity = 0.0;
+        if lot.status == "active" {
+            lot.status = "finished".to_string();
+        }
+    }
+
+    sqlx::query("UPDATE stock_lots SET remaining_quantity = ?, status = ? WHERE id = ?")
+        .bind(lot.remaining_quantity)
+        .bind(&lot.status)
+        .bind(lot.id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(Some(lot))
+}
+
+/// Lots of a product that can still be sold, in the order sales will draw from them
+pub async fn get_active_lots(pool: &DbPool, product_id: i64) -> AppResult<Vec<StockLot>> {
+    let lot_consumption: Option<(String,)> = sqlx::query_as("SELECT lot_consumption FROM products WHERE id = ?")
+        .bind(product_id)
+        .fetch_optional(pool)
+        .await?;
+
+    let lot_consumption = lot_consumption
+        .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+    let order = if lot_consumption.0 == "fifo" { "created_at, id" } else { "expiry_date, id" };
+
+    let lots = sqlx::query_as::<_, StockLot>(&format!(
+        r#"
+        SELECT * FROM stock_lots
+        WHERE product_id = ? AND status = 'active' AND remaining_quantity > 0
+            AND expiry_date >= ?
+        ORDER BY {}
+        "#,
+        order
+    ))
+    .bind(product_id)
+    .bind(Local::now().date_naive())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(lots)
+}
+
+/// All lots of one product (or all products) in any status, newest first
+pub async fn get_all_lots(pool: &DbPool, product_id: Option<i64>) -> AppResult<Vec<StockLot>> {
+    let lots = sqlx::query_as::<_, StockLot>(
+        r#"
+        SELECT * FROM stock_lots
+        WHERE ? IS NULL OR product_id = ?
+        ORDER BY created_at DESC, id DESC
+        "#
+    )
+    .bind(product_id)
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(lots)
+}
+
+/// Lots each item of a sale drew from
+pub async fn get_transaction_lot_consumptions(
+    pool: &DbPool,
+    transaction_id: i64,
+) -> AppResult<Vec<ItemLotConsumption>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT c.transaction_item_id, ti.product_id, c.lot_id, l.lot_number, l.expiry_date,
+            l.traceability_number, c.quantity
+        FROM stock_lot_consumptions c
+        JOIN transaction_items ti ON c.transaction_item_id = ti.id
+        JOIN stock_lots l ON c.lot_id = l.id
+        WHERE ti.transaction_id = ?
+        ORDER BY c.transaction_item_id, c.id
+        "#
+    )
+    .bind(transaction_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ItemLotConsumption {
+            transaction_item_id: row.get("transaction_item_id"),
+            product_id: row.get("product_id"),
+            lot_id: row.get("lot_id"),
+            lot_number: row.get("lot_number"),
+            expiry_date: row.get("expiry_date"),
+            traceability_number: row.get("traceability_number"),
+            quantity: row.get("quantity"),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use crate::services::{expiry, transaction};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn draws_from_lots_by_expiry_date() {
+        let pool = test_pool().await;
+        let farm = fixtures::supplier(&pool).await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::stocked_beef(&pool).await;
+        let today = Local::now().date_naive();
+        fixtures::confirmed_purchase(&pool, farm.id, beef.id, 2.0, today + chrono::Duration::days(20)).await;
+        fixtures::confirmed_purchase(&pool, farm.id, beef.id, 2.0, today + chrono::Duration::days(10)).await;
+
+        let sale = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 3.0).await;
+
+        let drawn: Vec<(NaiveDate, f64)> = get_transaction_lot_consumptions(&pool, sale.transaction.id)
+            .await
+            .unwrap()
+            .iter()
+            .map(|c| (c.expiry_date, c.quantity))
+            .collect();
+        assert_eq!(drawn, vec![
+            (today + chrono::Duration::days(10), 2.0),
+            (today + chrono::Duration::days(20), 1.0),
+        ]);
+        let lots = get_active_lots(&pool, beef.id).await.unwrap();
+        assert_eq!(lots.len(), 1);
+        assert_eq!(lots[0].remaining_quantity, 1.0);
+    }
+
+    #[tokio::test]
+    async fn expired_lots_are_not_sold_as_unlotted_stock() {
+        let pool = test_pool().await;
+        let farm = fixtures::supplier(&pool).await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::stocked_beef(&pool).await;
+        let yesterday = Local::now().date_naive() - chrono::Duration::days(1);
+        fixtures::confirmed_purchase(&pool, farm.id, beef.id, 5.0, yesterday).await;
+
+        // Write-off is off, so the expired lot keeps its 5 kg on hand
+        let checked = expiry::run_expiry_check(&pool).await.unwrap();
+        assert_eq!(checked.lots_expired, 1);
+        assert_eq!(checked.quantity_written_off, 0.0);
+
+        let sale = fixtures::draft(&pool, "sale", buyer.id, json!([
+            { "product_id": beef.id, "quantity": 1.0, "tax_rate": 0.1 },
+        ])).await;
+        let result = transaction::confirm_transaction(&pool, sale.transaction.id).await;
+
+        assert!(
+            matches!(&result, Err(AppError::Business(message)) if message.contains("past their expiry date")),
+            "{:?}",
+            result.map(|t| t.transaction.status)
+        );
+    }
+
+    #[tokio::test]
+    async fn sells_stock_of_products_without_lots() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::stocked_beef(&pool).await;
+        crate::services::inventory::create_stock_movement(&pool, serde_json::from_value(json!({
+            "product_id": beef.id,
+            "movement_type": "in",
+            "quantity": 2.0,
+        })).unwrap()).await.unwrap();
+
+        let sale = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 1.0).await;
+
+        assert_eq!(sale.transaction.status, "confirmed");
+        assert!(get_transaction_lot_consumptions(&pool, sale.transaction.id).await.unwrap().is_empty());
+    }
+}
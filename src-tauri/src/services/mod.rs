@@ -1,9 +1,43 @@
+pub mod aging;
+pub mod balance;
 pub mod company;
 pub mod customer;
+pub mod documents;
+pub mod expiry;
+pub mod inventory;
+pub mod lots;
+pub mod numbering;
+pub mod orders;
+pub mod payment;
+pub mod pdf;
+pub mod pricing;
 pub mod product;
+pub mod quotation;
+pub mod returns;
+pub mod tax_invoice;
+pub mod tax_invoice_xml;
+pub mod trace;
+pub mod traceability;
 pub mod transaction;
 
+pub use aging::*;
+pub use balance::*;
 pub use company::*;
 pub use customer::*;
+pub use documents::*;
+pub use expiry::*;
+pub use inventory::*;
+pub use lots::*;
+pub use numbering::*;
+pub use orders::*;
+pub use payment::*;
+pub use pdf::*;
+pub use pricing::*;
 pub use product::*;
+pub use quotation::*;
+pub use returns::*;
+pub use tax_invoice::*;
+pub use tax_invoice_xml::*;
+pub use trace::*;
+pub use traceability::*;
 pub use transaction::*;
@@ -0,0 +1,438 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::{TransactionNumberSetting, UpdateTransactionNumberSettingRequest};
+use chrono::{DateTime, Local, Utc};
+use sqlx::{Row, SqliteConnection};
+use std::collections::BTreeMap;
+
+const TRANSACTION_TYPES: [&str; 4] = ["sale", "purchase", "payment_in", "payment_out"];
+const DEFAULT_SEQUENCE_DIGITS: i64 = 4;
+
+/// (transaction_type, date) -> (highest number held by a non-draft, drafts in order)
+type DraftGroups = BTreeMap<(String, String), (i64, Vec<(i64, String)>)>;
+
+#[derive(Debug, serde::Serialize)]
+pub struct RenumberedTransaction {
+    pub id: i64,
+    pub old_number: String,
+    pub new_number: String,
+}
+
+fn default_prefix(transaction_type: &str) -> &'static str {
+    match transaction_type {
+        "sale" => "S",
+        "purchase" => "P",
+        "payment_in" => "R",
+        _ => "D",
+    }
+}
+
+fn validate_transaction_type(transaction_type: &str) -> AppResult<()> {
+    if !TRANSACTION_TYPES.contains(&transaction_type) {
+        return Err(AppError::validation(
+            "Transaction type must be 'sale', 'purchase', 'payment_in' or 'payment_out'".to_string()
+        ));
+    }
+    Ok(())
+}
+
+/// The date part of a transaction number, in the user's local calendar day
+fn sequence_date(transaction_date: DateTime<Utc>) -> String {
+    transaction_date.with_timezone(&Local).format("%Y%m%d").to_string()
+}
+
+fn format_number(setting: &TransactionNumberSetting, date: &str, number: i64) -> String {
+    format!(
+        "{}-{}-{:0width$}",
+        setting.prefix,
+        date,
+        number,
+        width = setting.sequence_digits as usize
+    )
+}
+
+/// Date and sequence parts of a number produced by `format_number`, if it has the setting's prefix
+fn parse_number(setting: &TransactionNumberSetting, transaction_number: &str) -> Option<(String, i64)> {
+    let (date, seq) = transaction_number
+        .strip_prefix(&format!("{}-", setting.prefix))?
+        .split_once('-')?;
+    if date.len() != 8 || !date.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((date.to_string(), seq.parse().ok()?))
+}
+
+async fn current_company_id(conn: &mut SqliteConnection) -> AppResult<i64> {
+    // Same company get_company returns; 0 when no company is registered yet
+    let company_id: Option<(i64,)> = sqlx::query_as(
+        "SELECT id FROM companies ORDER BY created_at DESC LIMIT 1"
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(company_id.map(|c| c.0).unwrap_or(0))
+}
+
+async fn get_setting(
+    conn: &mut SqliteConnection,
+    company_id: i64,
+    transaction_type: &str,
+) -> AppResult<TransactionNumberSetting> {
+    let setting = sqlx::query_as::<_, TransactionNumberSetting>(
+        "SELECT * FROM transaction_number_settings WHERE company_id = ? AND transaction_type = ?"
+    )
+    .bind(company_id)
+    .bind(transaction_type)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(setting.unwrap_or_else(|| TransactionNumberSetting {
+        company_id,
+        transaction_type: transaction_type.to_string(),
+        prefix: default_prefix(transaction_type).to_string(),
+        sequence_digits: DEFAULT_SEQUENCE_DIGITS,
+    }))
+}
+
+/// Allocates the next transaction number on the caller's DB transaction.
+///
+/// The sequence row is incremented with a single upsert, which takes SQLite's
+/// write lock, so concurrent callers are serialized and a rollback of the
+/// caller's transaction also returns the number. Call it before any other
+/// statement in the transaction so the lock is not upgraded from a read.
+pub async fn allocate_transaction_number(
+    conn: &mut SqliteConnection,
+    transaction_type: &str,
+    transaction_date: DateTime<Utc>,
+) -> AppResult<String> {
+    validate_transaction_type(transaction_type)?;
+    let date = sequence_date(transaction_date);
+
+    let row = sqlx::query(
+        r#"
+        INSERT INTO transaction_number_sequences (company_id, transaction_type, sequence_date, last_number)
+        VALUES (
+            COALESCE((SELECT id FROM companies ORDER BY created_at DESC LIMIT 1), 0),
+            ?, ?, 1
+        )
+        ON CONFLICT (company_id, transaction_type, sequence_date)
+        DO UPDATE SET last_number = last_number + 1
+        RETURNING company_id, last_number
+        "#
+    )
+    .bind(transaction_type)
+    .bind(&date)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let company_id: i64 = row.get("company_id");
+    let number: i64 = row.get("last_number");
+
+    let setting = get_setting(conn, company_id, transaction_type).await?;
+    Ok(format_number(&setting, &date, number))
+}
+
+/// Returns the number the next transaction of this type and date would get, without allocating it
+pub async fn preview_transaction_number(
+    pool: &DbPool,
+    transaction_type: &str,
+    transaction_date: DateTime<Utc>,
+) -> AppResult<String> {
+    validate_transaction_type(transaction_type)?;
+    let date = sequence_date(transaction_date);
+
+    let mut conn = pool.acquire().await?;
+    let company_id = current_company_id(&mut conn).await?;
+
+    let last_number: Option<(i64,)> = sqlx::query_as(
+        r#"
+        SELECT last_number FROM transaction_number_sequences
+        WHERE company_id = ? AND transaction_type = ? AND sequence_date = ?
+        "#
+    )
+    .bind(company_id)
+    .bind(transaction_type)
+    .bind(&date)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let setting = get_setting(&mut conn, company_id, transaction_type).await?;
+    Ok(format_number(&setting, &date, last_number.map(|n| n.0).unwrap_or(0) + 1))
+}
+
+/// Reassigns the numbers of draft transactions so each type/date sequence is contiguous.
+///
+/// Drafts are numbered after the highest confirmed or cancelled number of their
+/// group, in `(transaction_date, id)` order. A number belongs to the group of
+/// the date written in it, so a confirmed transaction that was re-dated keeps
+/// its number out of reach. Gaps left by deleted drafts are closed and drafts
+/// whose date was changed get a number for their new date. The group's
+/// sequence only moves forward, so numbers already handed out are never
+/// allocated again.
+pub async fn renumber_draft_transactions(pool: &DbPool) -> AppResult<Vec<RenumberedTransaction>> {
+    let mut tx = pool.begin().await?;
+
+    // Take the write lock first; see allocate_transaction_number
+    sqlx::query("UPDATE transaction_number_sequences SET last_number = last_number WHERE 0")
+        .execute(&mut *tx)
+        .await?;
+
+    let company_id = current_company_id(&mut tx).await?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT id, transaction_type, transaction_date, transaction_number, status
+        FROM transactions
+        ORDER BY transaction_date, id
+        "#
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut groups: DraftGroups = BTreeMap::new();
+    let mut settings: BTreeMap<String, TransactionNumberSetting> = BTreeMap::new();
+
+    for row in rows {
+        let transaction_type: String = row.get("transaction_type");
+        let transaction_date: DateTime<Utc> = row.get("transaction_date");
+        let transaction_number: String = row.get("transaction_number");
+        let status: String = row.get("status");
+
+        if !settings.contains_key(&transaction_type) {
+            let setting = get_setting(&mut tx, company_id, &transaction_type).await?;
+            settings.insert(transaction_type.clone(), setting);
+        }
+        let setting = &settings[&transaction_type];
+
+        if status == "draft" {
+            let date = sequence_date(transaction_date);
+            let group = groups.entry((transaction_type, date)).or_insert((0, Vec::new()));
+            group.1.push((row.get("id"), transaction_number));
+        } else if let Some((date, number)) = parse_number(setting, &transaction_number) {
+            let group = groups.entry((transaction_type, date)).or_insert((0, Vec::new()));
+            group.0 = group.0.max(number);
+        }
+    }
+
+    let mut renumbered = Vec::new();
+    for ((transaction_type, date), (base, drafts)) in &groups {
+        let setting = &settings[transaction_type];
+        for (offset, (id, old_number)) in drafts.iter().enumerate() {
+            let new_number = format_number(setting, date, base + offset as i64 + 1);
+            if &new_number != old_number {
+                renumbered.push(RenumberedTransaction {
+                    id: *id,
+                    old_number: old_number.clone(),
+                    new_number,
+                });
+            }
+        }
+
+        if !drafts.is_empty() {
+            sqlx::query(
+                r#"
+                INSERT INTO transaction_number_sequences (company_id, transaction_type, sequence_date, last_number)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT (company_id, transaction_type, sequence_date)
+                DO UPDATE SET last_number = MAX(last_number, excluded.last_number)
+                "#
+            )
+            .bind(company_id)
+            .bind(transaction_type)
+            .bind(date)
+            .bind(base + drafts.len() as i64)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    // Move changed drafts out of the way first so swapped numbers don't hit the UNIQUE constraint
+    for r in &renumbered {
+        sqlx::query("UPDATE transactions SET transaction_number = ? WHERE id = ?")
+            .bind(format!("RENUMBER-{}", r.id))
+            .bind(r.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let now = Utc::now();
+    for r in &renumbered {
+        sqlx::query("UPDATE transactions SET transaction_number = ?, updated_at = ? WHERE id = ?")
+            .bind(&r.new_number)
+            .bind(now)
+            .bind(r.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(renumbered)
+}
+
+pub async fn get_transaction_number_settings(pool: &DbPool) -> AppResult<Vec<TransactionNumberSetting>> {
+    let mut conn = pool.acquire().await?;
+    let company_id = current_company_id(&mut conn).await?;
+
+    let mut settings = Vec::new();
+    for transaction_type in TRANSACTION_TYPES {
+        settings.push(get_setting(&mut conn, company_id, transaction_type).await?);
+    }
+
+    Ok(settings)
+}
+
+pub async fn update_transaction_number_setting(
+    pool: &DbPool,
+    request: UpdateTransactionNumberSettingRequest,
+) -> AppResult<TransactionNumberSetting> {
+    validate_transaction_type(&request.transaction_type)?;
+
+    if request.prefix.is_empty() || !request.prefix.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(AppError::validation(
+            "Prefix must be non-empty and contain only letters and digits".to_string()
+        ));
+    }
+
+    let sequence_digits = request.sequence_digits.unwrap_or(DEFAULT_SEQUENCE_DIGITS);
+    if !(1..=8).contains(&sequence_digits) {
+        return Err(AppError::validation(
+            "Sequence digits must be between 1 and 8".to_string()
+        ));
+    }
+
+    let mut conn = pool.acquire().await?;
+    let company_id = current_company_id(&mut conn).await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO transaction_number_settings (company_id, transaction_type, prefix, sequence_digits)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT (company_id, transaction_type)
+        DO UPDATE SET prefix = excluded.prefix, sequence_digits = excluded.sequence_digits
+        "#
+    )
+    .bind(company_id)
+    .bind(&request.transaction_type)
+    .bind(&request.prefix)
+    .bind(sequence_digits)
+    .execute(&mut *conn)
+    .await?;
+
+    get_setting(&mut conn, company_id, &request.transaction_type).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{test_pool, TestDatabase};
+    use crate::fixtures;
+    use crate::services::transaction;
+    use serde_json::json;
+
+    fn date(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    fn sale_number(transaction_date: &str, number: i64) -> String {
+        format!("S-{}-{:04}", sequence_date(date(transaction_date)), number)
+    }
+
+    async fn draft_sale(pool: &DbPool, customer_id: i64, product_id: i64) -> i64 {
+        fixtures::draft(pool, "sale", customer_id, json!([
+            { "product_id": product_id, "quantity": 1.0, "tax_rate": 0.1 },
+        ])).await.transaction.id
+    }
+
+    async fn number_of(pool: &DbPool, id: i64) -> String {
+        transaction::get_transaction_by_id(pool, id).await.unwrap().unwrap().transaction.transaction_number
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_saves_get_contiguous_numbers() {
+        let database = TestDatabase::new("numbering").await;
+        let pool = &database.pool;
+        let buyer = fixtures::buyer(pool).await;
+        let beef = fixtures::beef(pool).await;
+
+        let saves: Vec<_> = (0..16)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move { draft_sale(&pool, buyer.id, beef.id).await })
+            })
+            .collect();
+        let mut numbers = Vec::new();
+        for save in saves {
+            let id = save.await.unwrap();
+            numbers.push(number_of(pool, id).await);
+        }
+        numbers.sort();
+
+        let expected: Vec<String> = (1..=16).map(|n| sale_number(fixtures::TRANSACTION_DATE, n)).collect();
+        assert_eq!(numbers, expected);
+        assert_eq!(
+            preview_transaction_number(pool, "sale", date(fixtures::TRANSACTION_DATE)).await.unwrap(),
+            sale_number(fixtures::TRANSACTION_DATE, 17)
+        );
+    }
+
+    #[tokio::test]
+    async fn renumbering_closes_gaps_left_by_deleted_drafts() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let first = draft_sale(&pool, buyer.id, beef.id).await;
+        let second = draft_sale(&pool, buyer.id, beef.id).await;
+        let third = draft_sale(&pool, buyer.id, beef.id).await;
+        transaction::confirm_transaction(&pool, first).await.unwrap();
+        transaction::delete_transaction(&pool, second).await.unwrap();
+
+        let renumbered = renumber_draft_transactions(&pool).await.unwrap();
+
+        assert_eq!(renumbered.len(), 1);
+        assert_eq!(renumbered[0].id, third);
+        assert_eq!(renumbered[0].old_number, sale_number(fixtures::TRANSACTION_DATE, 3));
+        assert_eq!(number_of(&pool, third).await, sale_number(fixtures::TRANSACTION_DATE, 2));
+        assert_eq!(number_of(&pool, first).await, sale_number(fixtures::TRANSACTION_DATE, 1));
+        // The sequence never goes back, so 0003 is not handed out a second time
+        assert_eq!(
+            preview_transaction_number(&pool, "sale", date(fixtures::TRANSACTION_DATE)).await.unwrap(),
+            sale_number(fixtures::TRANSACTION_DATE, 4)
+        );
+    }
+
+    #[tokio::test]
+    async fn renumbering_skips_numbers_kept_by_redated_transactions() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let first = draft_sale(&pool, buyer.id, beef.id).await;
+        let moved = draft_sale(&pool, buyer.id, beef.id).await;
+        let draft = draft_sale(&pool, buyer.id, beef.id).await;
+        transaction::confirm_transaction(&pool, first).await.unwrap();
+        transaction::confirm_transaction(&pool, moved).await.unwrap();
+        transaction::update_transaction(&pool, moved, serde_json::from_value(json!({
+            "transaction_date": "2026-03-05T01:00:00Z",
+        })).unwrap()).await.unwrap();
+
+        let renumbered = renumber_draft_transactions(&pool).await.unwrap();
+
+        assert!(renumbered.is_empty(), "{:?}", renumbered);
+        assert_eq!(number_of(&pool, moved).await, sale_number(fixtures::TRANSACTION_DATE, 2));
+        assert_eq!(number_of(&pool, draft).await, sale_number(fixtures::TRANSACTION_DATE, 3));
+        assert_eq!(
+            preview_transaction_number(&pool, "sale", date(fixtures::TRANSACTION_DATE)).await.unwrap(),
+            sale_number(fixtures::TRANSACTION_DATE, 4)
+        );
+        let next = draft_sale(&pool, buyer.id, beef.id).await;
+        assert_eq!(number_of(&pool, next).await, sale_number(fixtures::TRANSACTION_DATE, 4));
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_transaction_types() {
+        let pool = test_pool().await;
+        assert!(matches!(
+            preview_transaction_number(&pool, "refund", date(fixtures::TRANSACTION_DATE)).await,
+            Err(AppError::Validation { .. })
+        ));
+    }
+}
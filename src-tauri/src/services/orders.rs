@@ -0,0 +1,638 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::{
+    CreateOrderRequest, CreateTransactionItemRequest, CreateTransactionRequest, Customer, FulfilOrderRequest,
+    Order, OrderItem, OrderItemWithProduct, OrderWithItems, Product, TransactionWithItems,
+};
+use chrono::{DateTime, Local, Utc};
+use sqlx::SqliteConnection;
+use std::collections::{BTreeSet, HashMap};
+use super::{pricing, product, transaction};
+
+const ORDER_STATUSES: [&str; 4] = ["open", "partially_fulfilled", "fulfilled", "closed"];
+
+/// Quantities closer than this are treated as equal
+const QUANTITY_EPSILON: f64 = 0.0005;
+
+/// Sales orders (수주) and purchase orders (발주) live in separate tables of
+/// the same shape; this picks the tables and columns for one of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OrderKind {
+    Sales,
+    Purchase,
+}
+
+impl OrderKind {
+    /// Parses the transaction type the order turns into: 'sale' or 'purchase'
+    pub fn parse(order_type: &str) -> AppResult<Self> {
+        match order_type {
+            "sale" => Ok(OrderKind::Sales),
+            "purchase" => Ok(OrderKind::Purchase),
+            _ => Err(AppError::validation("Order type must be 'sale' or 'purchase'".to_string())),
+        }
+    }
+
+    pub fn transaction_type(self) -> &'static str {
+        match self {
+            OrderKind::Sales => "sale",
+            OrderKind::Purchase => "purchase",
+        }
+    }
+
+    fn orders_table(self) -> &'static str {
+        match self {
+            OrderKind::Sales => "sales_orders",
+            OrderKind::Purchase => "purchase_orders",
+        }
+    }
+
+    fn items_table(self) -> &'static str {
+        match self {
+            OrderKind::Sales => "sales_order_items",
+            OrderKind::Purchase => "purchase_order_items",
+        }
+    }
+
+    /// Column of `transaction_items` that links a delivery to an order line
+    fn link_column(self) -> &'static str {
+        match self {
+            OrderKind::Sales => "sales_order_item_id",
+            OrderKind::Purchase => "purchase_order_item_id",
+        }
+    }
+
+    fn number_prefix(self) -> &'static str {
+        match self {
+            OrderKind::Sales => "SO",
+            OrderKind::Purchase => "PO",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            OrderKind::Sales => "수주",
+            OrderKind::Purchase => "발주",
+        }
+    }
+}
+
+/// Next `SO-YYYYMMDD-NNNN` / `PO-YYYYMMDD-NNNN` number for the local day of `order_date`
+async fn next_order_number(conn: &mut SqliteConnection, kind: OrderKind, order_date: DateTime<Utc>) -> AppResult<String> {
+    let prefix = format!("{}-{}-", kind.number_prefix(), order_date.with_timezone(&Local).format("%Y%m%d"));
+
+    let numbers: Vec<(String,)> = sqlx::query_as(&format!(
+        "SELECT order_number FROM {} WHERE order_number LIKE ?",
+        kind.orders_table()
+    ))
+    .bind(format!("{}%", prefix))
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let last = numbers
+        .iter()
+        .filter_map(|(n,)| n.strip_prefix(&prefix).and_then(|seq| seq.parse::<i64>().ok()))
+        .max()
+        .unwrap_or(0);
+
+    Ok(format!("{}{:04}", prefix, last + 1))
+}
+
+pub async fn get_orders(
+    pool: &DbPool,
+    order_type: String,
+    status: Option<String>,
+    customer_id: Option<i64>,
+) -> AppResult<Vec<OrderWithItems>> {
+    let kind = OrderKind::parse(&order_type)?;
+
+    if let Some(status) = status.as_deref() {
+        if !ORDER_STATUSES.contains(&status) {
+            return Err(AppError::validation(
+                "Status must be 'open', 'partially_fulfilled', 'fulfilled' or 'closed'".to_string()
+            ));
+        }
+    }
+
+    let orders = sqlx::query_as::<_, Order>(&format!(
+        r#"
+        SELECT * FROM {}
+        WHERE (? IS NULL OR status = ?) AND (? IS NULL OR customer_id = ?)
+        ORDER BY order_date DESC, id DESC
+        "#,
+        kind.orders_table()
+    ))
+    .bind(&status)
+    .bind(&status)
+    .bind(customer_id)
+    .bind(customer_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut result = Vec::with_capacity(orders.len());
+    for order in orders {
+        result.push(with_items(pool, kind, order).await?);
+    }
+
+    Ok(result)
+}
+
+pub async fn get_order_by_id(pool: &DbPool, order_type: String, id: i64) -> AppResult<Option<OrderWithItems>> {
+    let kind = OrderKind::parse(&order_type)?;
+    find_order(pool, kind, id).await
+}
+
+async fn find_order(pool: &DbPool, kind: OrderKind, id: i64) -> AppResult<Option<OrderWithItems>> {
+    let order = sqlx::query_as::<_, Order>(&format!("SELECT * FROM {} WHERE id = ?", kind.orders_table()))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    match order {
+        Some(order) => Ok(Some(with_items(pool, kind, order).await?)),
+        None => Ok(None),
+    }
+}
+
+async fn require_order(pool: &DbPool, kind: OrderKind, id: i64) -> AppResult<OrderWithItems> {
+    find_order(pool, kind, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Order not found".to_string()))
+}
+
+async fn with_items(pool: &DbPool, kind: OrderKind, order: Order) -> AppResult<OrderWithItems> {
+    let customer = sqlx::query_as::<_, Customer>("SELECT * FROM customers WHERE id = ?")
+        .bind(order.customer_id)
+        .fetch_one(pool)
+        .await?;
+
+    let rows = sqlx::query_as::<_, OrderItem>(&format!(
+        "SELECT * FROM {} WHERE order_id = ? ORDER BY id",
+        kind.items_table()
+    ))
+    .bind(order.id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut items = Vec::with_capacity(rows.len());
+    for item in rows {
+        let product = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = ?")
+            .bind(item.product_id)
+            .fetch_one(pool)
+            .await?;
+        let open_quantity = (item.quantity - item.delivered_quantity).max(0.0);
+        items.push(OrderItemWithProduct { item, open_quantity, product });
+    }
+
+    Ok(OrderWithItems {
+        order,
+        order_type: kind.transaction_type().to_string(),
+        items,
+        customer,
+    })
+}
+
+/// Creates an open sales or purchase order. Items are validated like
+/// transaction items and a missing unit price comes from the price book.
+pub async fn create_order(pool: &DbPool, request: CreateOrderRequest) -> AppResult<OrderWithItems> {
+    let now = Utc::now();
+    let kind = OrderKind::parse(&request.order_type)?;
+
+    if request.items.is_empty() {
+        return Err(AppError::validation("Order must have at least one item".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Take the write lock first so two orders cannot pick the same number
+    sqlx::query(&format!("UPDATE {} SET status = status WHERE 0", kind.orders_table()))
+        .execute(&mut *tx)
+        .await?;
+
+    let customer_exists: Option<(i64,)> = sqlx::query_as("SELECT id FROM customers WHERE id = ?")
+        .bind(request.customer_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    if customer_exists.is_none() {
+        return Err(AppError::NotFound("Customer not found".to_string()));
+    }
+
+    let price_mode = request.price_mode.clone().unwrap_or_else(|| "exclusive".to_string());
+    transaction::validate_price_mode(&price_mode)?;
+
+    let mut unit_prices = Vec::with_capacity(request.items.len());
+    for item in &request.items {
+        if item.quantity <= 0.0 {
+            return Err(AppError::validation("Item quantity must be positive".to_string()));
+        }
+
+        if item.unit_price.is_some_and(|price| price.is_negative()) {
+            return Err(AppError::validation("Item unit price cannot be negative".to_string()));
+        }
+
+        if item.tax_rate < 0.0 || item.tax_rate > 1.0 {
+            return Err(AppError::validation("Tax rate must be between 0.0 and 1.0".to_string()));
+        }
+
+        let default_price = pricing::default_unit_price(&mut tx, request.customer_id, item.product_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Product not found or inactive".to_string()))?;
+
+        // The price book holds VAT-exclusive prices; exempt and zero-rated products carry no VAT
+        let default_price = if price_mode == "inclusive" {
+            let (tax_category,): (String,) = sqlx::query_as("SELECT tax_category FROM products WHERE id = ?")
+                .bind(item.product_id)
+                .fetch_one(&mut *tx)
+                .await?;
+            product::validate_tax_category(&tax_category)?;
+            let tax_rate = if tax_category == "taxable" { item.tax_rate } else { 0.0 };
+            default_price.including_vat(tax_rate)
+        } else {
+            default_price
+        };
+        unit_prices.push(item.unit_price.unwrap_or(default_price));
+    }
+
+    let order_number = next_order_number(&mut tx, kind, request.order_date).await?;
+
+    let order_id = sqlx::query(&format!(
+        r#"
+        INSERT INTO {} (
+            order_number, customer_id, order_date, expected_date, status, price_mode, notes, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, 'open', ?, ?, ?, ?)
+        "#,
+        kind.orders_table()
+    ))
+    .bind(&order_number)
+    .bind(request.customer_id)
+    .bind(request.order_date)
+    .bind(request.expected_date)
+    .bind(&price_mode)
+    .bind(&request.notes)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    for (item, unit_price) in request.items.iter().zip(unit_prices) {
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO {} (
+                order_id, product_id, quantity, delivered_quantity, unit_price, tax_rate, notes, created_at
+            ) VALUES (?, ?, ?, 0, ?, ?, ?, ?)
+            "#,
+            kind.items_table()
+        ))
+        .bind(order_id)
+        .bind(item.product_id)
+        .bind(item.quantity)
+        .bind(unit_price)
+        .bind(item.tax_rate)
+        .bind(&item.notes)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    require_order(pool, kind, order_id).await
+}
+
+/// Creates a draft sale (sales order) or purchase (purchase order) for part
+/// of an order's open quantities, at the order's prices.
+///
+/// Each requested line must belong to the order and its quantity may not
+/// exceed what is still open. The transaction goes through the same
+/// validation as `create_transaction`; each `transaction_items` row is linked
+/// to its order line and the order's delivered quantities and status are
+/// updated on the same DB transaction.
+pub async fn fulfil_order(pool: &DbPool, request: FulfilOrderRequest) -> AppResult<TransactionWithItems> {
+    let kind = OrderKind::parse(&request.order_type)?;
+
+    if request.items.is_empty() {
+        return Err(AppError::validation("Select at least one order line to deliver".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(&format!("UPDATE {} SET status = status WHERE 0", kind.orders_table()))
+        .execute(&mut *tx)
+        .await?;
+
+    let order = sqlx::query_as::<_, Order>(&format!("SELECT * FROM {} WHERE id = ?", kind.orders_table()))
+        .bind(request.order_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Order not found".to_string()))?;
+
+    if !matches!(order.status.as_str(), "open" | "partially_fulfilled") {
+        return Err(AppError::Business(format!(
+            "Cannot deliver against order {} in status '{}'",
+            order.order_number, order.status
+        )));
+    }
+
+    let lines: HashMap<i64, OrderItem> = sqlx::query_as::<_, OrderItem>(&format!(
+        "SELECT * FROM {} WHERE order_id = ?",
+        kind.items_table()
+    ))
+    .bind(order.id)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|line| (line.id, line))
+    .collect();
+
+    // Quantity requested per line, so a line listed twice is checked as a whole
+    let mut requested: HashMap<i64, f64> = HashMap::new();
+    for item in &request.items {
+        if !lines.contains_key(&item.order_item_id) {
+            return Err(AppError::validation(format!(
+                "Order line {} does not belong to order {}",
+                item.order_item_id, order.order_number
+            )));
+        }
+        if item.quantity <= 0.0 {
+            return Err(AppError::validation("Item quantity must be positive".to_string()));
+        }
+        *requested.entry(item.order_item_id).or_insert(0.0) += item.quantity;
+    }
+
+    for (line_id, quantity) in &requested {
+        let line = &lines[line_id];
+        let open = line.quantity - line.delivered_quantity;
+        if *quantity > open + QUANTITY_EPSILON {
+            return Err(AppError::validation(format!(
+                "Order line {} has {:.2} open but {:.2} was requested",
+                line_id,
+                open.max(0.0),
+                quantity
+            )));
+        }
+    }
+
+    let transaction_request = CreateTransactionRequest {
+        customer_id: order.customer_id,
+        transaction_type: kind.transaction_type().to_string(),
+        transaction_date: request.transaction_date,
+        due_date: request.due_date,
+        price_mode: Some(order.price_mode.clone()),
+        items: request
+            .items
+            .iter()
+            .map(|item| {
+                let line = &lines[&item.order_item_id];
+                CreateTransactionItemRequest {
+                    product_id: line.product_id,
+                    quantity: item.quantity,
+                    unit_price: Some(line.unit_price),
+                    tax_rate: line.tax_rate,
+                    tax_category: None,
+                    traceability_number: item.traceability_number.clone(),
+                    origin: item.origin.clone(),
+                    slaughterhouse: item.slaughterhouse.clone(),
+                    expiry_date: item.expiry_date,
+                    notes: line.notes.clone(),
+                }
+            })
+            .collect(),
+        notes: Some(match &request.notes {
+            Some(notes) if !notes.trim().is_empty() => notes.clone(),
+            _ => format!("{} {}", kind.label(), order.order_number),
+        }),
+    };
+
+    let transaction_id = transaction::insert_transaction(&mut tx, transaction_request).await?;
+
+    // Items were inserted in request order, so ids line up with the request
+    let item_ids: Vec<(i64,)> = sqlx::query_as(
+        "SELECT id FROM transaction_items WHERE transaction_id = ? ORDER BY id"
+    )
+    .bind(transaction_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for ((item_id,), item) in item_ids.iter().zip(&request.items) {
+        sqlx::query(&format!("UPDATE transaction_items SET {} = ? WHERE id = ?", kind.link_column()))
+            .bind(item.order_item_id)
+            .bind(item_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    recompute_order(&mut tx, kind, order.id).await?;
+
+    tx.commit().await?;
+
+    transaction::get_transaction_by_id(pool, transaction_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Created transaction not found".to_string()))
+}
+
+/// Closes an order so nothing more is delivered against it; the remaining
+/// open quantities are dropped. Transactions already made are kept.
+pub async fn close_order(pool: &DbPool, order_type: String, id: i64) -> AppResult<OrderWithItems> {
+    let kind = OrderKind::parse(&order_type)?;
+
+    let order = require_order(pool, kind, id).await?.order;
+    if order.status == "closed" {
+        return Err(AppError::Business(format!("Order {} is already closed", order.order_number)));
+    }
+
+    sqlx::query(&format!("UPDATE {} SET status = 'closed', updated_at = ? WHERE id = ?", kind.orders_table()))
+        .bind(Utc::now())
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    require_order(pool, kind, id).await
+}
+
+/// Orders with lines delivered by the given transaction's items
+pub async fn linked_orders(conn: &mut SqliteConnection, transaction_id: i64) -> AppResult<BTreeSet<(OrderKind, i64)>> {
+    let mut orders = BTreeSet::new();
+
+    for kind in [OrderKind::Sales, OrderKind::Purchase] {
+        let ids: Vec<(i64,)> = sqlx::query_as(&format!(
+            r#"
+            SELECT DISTINCT oi.order_id FROM transaction_items ti
+            JOIN {} oi ON oi.id = ti.{}
+            WHERE ti.transaction_id = ?
+            "#,
+            kind.items_table(),
+            kind.link_column()
+        ))
+        .bind(transaction_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        orders.extend(ids.into_iter().map(|(id,)| (kind, id)));
+    }
+
+    Ok(orders)
+}
+
+/// Recomputes the delivered quantities and status of the given orders.
+/// Call it on the same DB transaction as any write that creates, cancels,
+/// deletes or changes the items of a transaction linked to an order (collect
+/// the orders with `linked_orders` before deleting).
+pub async fn recompute_orders(conn: &mut SqliteConnection, orders: &BTreeSet<(OrderKind, i64)>) -> AppResult<()> {
+    for (kind, id) in orders {
+        recompute_order(conn, *kind, *id).await?;
+    }
+    Ok(())
+}
+
+/// Delivered quantity of each line is the sum of its linked items on
+/// transactions that are not cancelled (drafts count, so the same open
+/// quantity cannot be put on two drafts). A closed order stays closed;
+/// otherwise the status follows the delivered quantities.
+/// Fails if any line of the given orders has been delivered beyond its
+/// ordered quantity, e.g. after a linked draft item was increased.
+pub async fn ensure_not_overdelivered(conn: &mut SqliteConnection, orders: &BTreeSet<(OrderKind, i64)>) -> AppResult<()> {
+    for (kind, order_id) in orders {
+        let over: Option<(i64, f64, f64)> = sqlx::query_as(&format!(
+            "SELECT id, quantity, delivered_quantity FROM {} WHERE order_id = ? AND delivered_quantity > quantity + ? LIMIT 1",
+            kind.items_table()
+        ))
+        .bind(order_id)
+        .bind(QUANTITY_EPSILON)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        if let Some((line_id, ordered, delivered)) = over {
+            return Err(AppError::validation(format!(
+                "Order line {} would be delivered {:.2} of {:.2} ordered",
+                line_id, delivered, ordered
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+async fn recompute_order(conn: &mut SqliteConnection, kind: OrderKind, order_id: i64) -> AppResult<()> {
+    sqlx::query(&format!(
+        r#"
+        UPDATE {items} SET delivered_quantity = COALESCE((
+            SELECT SUM(ti.quantity) FROM transaction_items ti
+            JOIN transactions t ON t.id = ti.transaction_id
+            WHERE ti.{link} = {items}.id AND t.status != 'cancelled'
+        ), 0)
+        WHERE order_id = ?
+        "#,
+        items = kind.items_table(),
+        link = kind.link_column()
+    ))
+    .bind(order_id)
+    .execute(&mut *conn)
+    .await?;
+
+    let lines: Vec<(f64, f64)> = sqlx::query_as(&format!(
+        "SELECT quantity, delivered_quantity FROM {} WHERE order_id = ?",
+        kind.items_table()
+    ))
+    .bind(order_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let status = if lines.iter().all(|(ordered, delivered)| *delivered >= ordered - QUANTITY_EPSILON) {
+        "fulfilled"
+    } else if lines.iter().any(|(_, delivered)| *delivered > QUANTITY_EPSILON) {
+        "partially_fulfilled"
+    } else {
+        "open"
+    };
+
+    sqlx::query(&format!(
+        "UPDATE {} SET status = ?, updated_at = ? WHERE id = ? AND status != 'closed' AND status != ?",
+        kind.orders_table()
+    ))
+    .bind(status)
+    .bind(Utc::now())
+    .bind(order_id)
+    .bind(status)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use serde_json::json;
+
+    /// An open purchase order for 5 kg at 9,000 won
+    async fn purchase_order(pool: &DbPool) -> OrderWithItems {
+        let farm = fixtures::supplier(pool).await;
+        let beef = fixtures::beef(pool).await;
+        create_order(pool, serde_json::from_value(json!({
+            "order_type": "purchase",
+            "customer_id": farm.id,
+            "order_date": fixtures::TRANSACTION_DATE,
+            "items": [{ "product_id": beef.id, "quantity": 5.0, "tax_rate": 0.1, "unit_price": 9000 }],
+        })).unwrap()).await.unwrap()
+    }
+
+    async fn deliver(pool: &DbPool, order: &OrderWithItems, quantity: f64) -> AppResult<TransactionWithItems> {
+        fulfil_order(pool, serde_json::from_value(json!({
+            "order_type": order.order_type,
+            "order_id": order.order.id,
+            "transaction_date": fixtures::TRANSACTION_DATE,
+            "items": [{ "order_item_id": order.items[0].item.id, "quantity": quantity }],
+        })).unwrap()).await
+    }
+
+    async fn reload(pool: &DbPool, order: &OrderWithItems) -> OrderWithItems {
+        get_order_by_id(pool, order.order_type.clone(), order.order.id).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn deliveries_track_the_open_quantity() {
+        let pool = test_pool().await;
+        let order = purchase_order(&pool).await;
+        assert_eq!(order.order.status, "open");
+
+        let delivery = deliver(&pool, &order, 2.0).await.unwrap();
+
+        assert_eq!(delivery.transaction.transaction_type, "purchase");
+        assert_eq!(delivery.transaction.status, "draft");
+        assert_eq!(delivery.items[0].item.unit_price.as_won(), 9000);
+        assert_eq!(delivery.items[0].item.purchase_order_item_id, Some(order.items[0].item.id));
+        let partial = reload(&pool, &order).await;
+        assert_eq!(partial.order.status, "partially_fulfilled");
+        assert_eq!(partial.items[0].open_quantity, 3.0);
+
+        // Deleting the draft gives the quantity back
+        transaction::delete_transaction(&pool, delivery.transaction.id).await.unwrap();
+        assert_eq!(reload(&pool, &order).await.order.status, "open");
+
+        deliver(&pool, &order, 5.0).await.unwrap();
+        let fulfilled = reload(&pool, &order).await;
+        assert_eq!(fulfilled.order.status, "fulfilled");
+        assert_eq!(fulfilled.items[0].open_quantity, 0.0);
+    }
+
+    #[tokio::test]
+    async fn deliveries_stay_within_open_orders_and_quantities() {
+        let pool = test_pool().await;
+        let order = purchase_order(&pool).await;
+
+        let result = deliver(&pool, &order, 6.0).await;
+        assert!(matches!(result, Err(AppError::Validation { .. })), "{:?}", result.map(|t| t.transaction.id));
+        let result = get_order_by_id(&pool, "quote".to_string(), order.order.id).await;
+        assert!(matches!(result, Err(AppError::Validation { .. })));
+
+        deliver(&pool, &order, 1.0).await.unwrap();
+        let closed = close_order(&pool, order.order_type.clone(), order.order.id).await.unwrap();
+        assert_eq!(closed.order.status, "closed");
+
+        let result = deliver(&pool, &order, 1.0).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|t| t.transaction.id));
+    }
+}
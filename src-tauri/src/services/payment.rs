@@ -0,0 +1,424 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::{
+    Customer, PaymentAllocation, PaymentWithAllocations, RecordPaymentRequest, Transaction,
+};
+use crate::money::Money;
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqliteConnection};
+use super::{balance, numbering};
+
+/// A sale or purchase with the part not yet covered by payment allocations
+#[derive(Debug, serde::Serialize)]
+pub struct OpenTransaction {
+    pub id: i64,
+    pub transaction_number: String,
+    pub transaction_type: String,
+    pub transaction_date: DateTime<Utc>,
+    pub total_amount: Money,
+    pub allocated_amount: Money,
+    pub open_amount: Money,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct LedgerEntry {
+    pub transaction_id: i64,
+    pub transaction_number: String,
+    pub transaction_type: String,
+    pub transaction_date: DateTime<Utc>,
+    pub charge: Money,  // sale / purchase amount
+    pub payment: Money, // payment_in / payment_out amount
+    pub balance: Money,
+}
+
+/// Records money received from a customer (payment_in)
+pub async fn record_receipt(pool: &DbPool, request: RecordPaymentRequest) -> AppResult<PaymentWithAllocations> {
+    record_payment(pool, "payment_in", request).await
+}
+
+/// Records money paid to a supplier (payment_out)
+pub async fn record_disbursement(pool: &DbPool, request: RecordPaymentRequest) -> AppResult<PaymentWithAllocations> {
+    record_payment(pool, "payment_out", request).await
+}
+
+async fn record_payment(
+    pool: &DbPool,
+    payment_type: &str,
+    request: RecordPaymentRequest,
+) -> AppResult<PaymentWithAllocations> {
+    let now = Utc::now();
+
+    if !request.amount.is_positive() {
+        return Err(AppError::invalid_field("amount", "Payment amount must be positive".to_string()));
+    }
+
+    let customer_type: Option<(String,)> = sqlx::query_as("SELECT customer_type FROM customers WHERE id = ?")
+        .bind(request.customer_id)
+        .fetch_optional(pool)
+        .await?;
+
+    let Some((customer_type,)) = customer_type else {
+        return Err(AppError::NotFound("Customer not found".to_string()));
+    };
+
+    // Receipts come from customers, disbursements go to suppliers
+    let expected_type = if payment_type == "payment_in" { "customer" } else { "supplier" };
+    if customer_type != expected_type {
+        return Err(AppError::invalid_field("customer_id", format!(
+            "A {} must be recorded against a {}, not a {}", payment_type, expected_type, customer_type
+        )));
+    }
+
+    let allocated_total: Money = request.allocations.iter().map(|a| a.amount).sum();
+    if allocated_total > request.amount {
+        return Err(AppError::invalid_field("allocations",
+            "Allocated amounts exceed the payment amount".to_string()
+        ));
+    }
+
+    let target_type = if payment_type == "payment_in" { "sale" } else { "purchase" };
+
+    let mut tx = pool.begin().await?;
+
+    let transaction_number = numbering::allocate_transaction_number(
+        &mut tx, payment_type, request.payment_date
+    ).await?;
+
+    // Payments carry no items and take effect immediately
+    let payment_id = sqlx::query(
+        r#"
+        INSERT INTO transactions (
+            transaction_number, customer_id, transaction_type, transaction_date,
+            subtotal, tax_amount, total_amount, status, notes, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 0, ?, 'confirmed', ?, ?, ?)
+        "#
+    )
+    .bind(&transaction_number)
+    .bind(request.customer_id)
+    .bind(payment_type)
+    .bind(request.payment_date)
+    .bind(request.amount)
+    .bind(request.amount)
+    .bind(&request.notes)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    for (index, allocation) in request.allocations.iter().enumerate() {
+        if !allocation.amount.is_positive() {
+            return Err(AppError::invalid_field(
+                format!("allocations[{index}].amount"), "Allocation amount must be positive"
+            ));
+        }
+
+        let target = sqlx::query(
+            "SELECT customer_id, transaction_type, status FROM transactions WHERE id = ?"
+        )
+        .bind(allocation.transaction_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!(
+            "Transaction {} not found", allocation.transaction_id
+        )))?;
+
+        let customer_id: i64 = target.get("customer_id");
+        let transaction_type: String = target.get("transaction_type");
+        let status: String = target.get("status");
+
+        if customer_id != request.customer_id || transaction_type != target_type {
+            return Err(AppError::invalid_field(format!("allocations[{index}].transaction_id"), format!(
+                "Transaction {} is not a {} of this customer", allocation.transaction_id, target_type
+            )));
+        }
+
+        if status != "confirmed" {
+            return Err(AppError::Business(format!(
+                "Transaction {} is not confirmed", allocation.transaction_id
+            )));
+        }
+
+        let open_amount = open_amount(&mut tx, allocation.transaction_id).await?;
+        if allocation.amount > open_amount {
+            return Err(AppError::Business(format!(
+                "Allocation of {} exceeds the open amount {} of transaction {}",
+                allocation.amount, open_amount, allocation.transaction_id
+            )));
+        }
+
+        sqlx::query(
+            "INSERT INTO payment_allocations (payment_id, transaction_id, amount, created_at) VALUES (?, ?, ?, ?)"
+        )
+        .bind(payment_id)
+        .bind(allocation.transaction_id)
+        .bind(allocation.amount)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    balance::recompute_customer_balance(&mut tx, request.customer_id).await?;
+
+    tx.commit().await?;
+
+    let payment = get_payment_by_id(pool, payment_id).await?
+        .ok_or_else(|| AppError::NotFound("Created payment not found".to_string()))?;
+
+    Ok(payment)
+}
+
+/// Amount of a sale/purchase not yet covered by allocations of non-cancelled payments
+async fn open_amount(conn: &mut SqliteConnection, transaction_id: i64) -> AppResult<Money> {
+    let row = sqlx::query(
+        r#"
+        SELECT t.total_amount - COALESCE((
+            SELECT SUM(pa.amount) FROM payment_allocations pa
+            JOIN transactions p ON pa.payment_id = p.id
+            WHERE pa.transaction_id = t.id AND p.status != 'cancelled'
+        ), 0) as open_amount
+        FROM transactions t
+        WHERE t.id = ?
+        "#
+    )
+    .bind(transaction_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(row.get("open_amount"))
+}
+
+pub async fn get_payment_by_id(pool: &DbPool, id: i64) -> AppResult<Option<PaymentWithAllocations>> {
+    let transaction = sqlx::query_as::<_, Transaction>(
+        "SELECT * FROM transactions WHERE id = ? AND transaction_type IN ('payment_in', 'payment_out')"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    let transaction = match transaction {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+
+    let customer = sqlx::query_as::<_, Customer>(
+        "SELECT * FROM customers WHERE id = ?"
+    )
+    .bind(transaction.customer_id)
+    .fetch_one(pool)
+    .await?;
+
+    let allocations = sqlx::query_as::<_, PaymentAllocation>(
+        "SELECT * FROM payment_allocations WHERE payment_id = ? ORDER BY id"
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Some(PaymentWithAllocations {
+        transaction,
+        allocations,
+        customer,
+    }))
+}
+
+/// Confirmed sales (or purchases) of a customer that still have an open amount, oldest first
+pub async fn get_open_transactions(
+    pool: &DbPool,
+    customer_id: i64,
+    transaction_type: &str,
+) -> AppResult<Vec<OpenTransaction>> {
+    if !matches!(transaction_type, "sale" | "purchase") {
+        return Err(AppError::validation(
+            "Transaction type must be 'sale' or 'purchase'".to_string()
+        ));
+    }
+
+    let rows = sqlx::query(
+        r#"
+        SELECT t.id, t.transaction_number, t.transaction_type, t.transaction_date, t.total_amount,
+            COALESCE((
+                SELECT SUM(pa.amount) FROM payment_allocations pa
+                JOIN transactions p ON pa.payment_id = p.id
+                WHERE pa.transaction_id = t.id AND p.status != 'cancelled'
+            ), 0) as allocated_amount
+        FROM transactions t
+        WHERE t.customer_id = ? AND t.transaction_type = ? AND t.status = 'confirmed'
+        ORDER BY t.transaction_date, t.id
+        "#
+    )
+    .bind(customer_id)
+    .bind(transaction_type)
+    .fetch_all(pool)
+    .await?;
+
+    let mut open_transactions = Vec::new();
+    for row in rows {
+        let total_amount: Money = row.get("total_amount");
+        let allocated_amount: Money = row.get("allocated_amount");
+        if !(total_amount - allocated_amount).is_positive() {
+            continue;
+        }
+
+        open_transactions.push(OpenTransaction {
+            id: row.get("id"),
+            transaction_number: row.get("transaction_number"),
+            transaction_type: row.get("transaction_type"),
+            transaction_date: row.get("transaction_date"),
+            total_amount,
+            allocated_amount,
+            open_amount: total_amount - allocated_amount,
+        });
+    }
+
+    Ok(open_transactions)
+}
+
+/// Receivable (sales and receipts) or payable (purchases and disbursements) ledger of a customer
+pub async fn get_customer_ledger(
+    pool: &DbPool,
+    customer_id: i64,
+    ledger_type: &str,
+) -> AppResult<Vec<LedgerEntry>> {
+    let (charge_type, payment_type) = match ledger_type {
+        "receivable" => ("sale", "payment_in"),
+        "payable" => ("purchase", "payment_out"),
+        _ => {
+            return Err(AppError::validation(
+                "Ledger type must be 'receivable' or 'payable'".to_string()
+            ))
+        }
+    };
+
+    let transactions = sqlx::query_as::<_, Transaction>(
+        r#"
+        SELECT * FROM transactions
+        WHERE customer_id = ? AND transaction_type IN (?, ?) AND status = 'confirmed'
+        ORDER BY transaction_date, id
+        "#
+    )
+    .bind(customer_id)
+    .bind(charge_type)
+    .bind(payment_type)
+    .fetch_all(pool)
+    .await?;
+
+    let mut balance = Money::ZERO;
+    let mut entries = Vec::new();
+    for t in transactions {
+        let (charge, payment) = if t.transaction_type == charge_type {
+            (t.total_amount, Money::ZERO)
+        } else {
+            (Money::ZERO, t.total_amount)
+        };
+        balance += charge - payment;
+
+        entries.push(LedgerEntry {
+            transaction_id: t.id,
+            transaction_number: t.transaction_number,
+            transaction_type: t.transaction_type,
+            transaction_date: t.transaction_date,
+            charge,
+            payment,
+            balance,
+        });
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use serde_json::{json, Value};
+
+    fn payment(customer_id: i64, amount: i64, allocations: Value) -> RecordPaymentRequest {
+        serde_json::from_value(json!({
+            "customer_id": customer_id,
+            "amount": amount,
+            "payment_date": fixtures::TRANSACTION_DATE,
+            "allocations": allocations,
+        })).unwrap()
+    }
+
+    fn field_of<T: std::fmt::Debug>(result: AppResult<T>) -> String {
+        match result {
+            Err(AppError::Validation { field: Some(field), .. }) => field,
+            other => panic!("expected a field error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn receipts_settle_the_sales_they_are_allocated_to() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let first = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 2.0).await;
+        let second = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 1.0).await;
+
+        let receipt = record_receipt(&pool, payment(buyer.id, 30000, json!([
+            { "transaction_id": first.transaction.id, "amount": 22000 },
+            { "transaction_id": second.transaction.id, "amount": 5000 },
+        ]))).await.unwrap();
+
+        assert_eq!(receipt.transaction.transaction_type, "payment_in");
+        assert_eq!(receipt.allocations.len(), 2);
+        let open = get_open_transactions(&pool, buyer.id, "sale").await.unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].id, second.transaction.id);
+        assert_eq!(open[0].open_amount.as_won(), 6000);
+    }
+
+    #[tokio::test]
+    async fn over_allocation_is_rejected_without_recording_the_payment() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let sale = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 1.0).await;
+
+        let result = record_receipt(&pool, payment(buyer.id, 10000, json!([
+            { "transaction_id": sale.transaction.id, "amount": 11000 },
+        ]))).await;
+        assert_eq!(field_of(result), "allocations");
+
+        let result = record_receipt(&pool, payment(buyer.id, 20000, json!([
+            { "transaction_id": sale.transaction.id, "amount": 12000 },
+        ]))).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|p| p.allocations));
+
+        let result = record_receipt(&pool, payment(buyer.id, 20000, json!([
+            { "transaction_id": sale.transaction.id, "amount": 5000 },
+            { "transaction_id": sale.transaction.id, "amount": 0 },
+        ]))).await;
+        assert_eq!(field_of(result), "allocations[1].amount");
+
+        let open = get_open_transactions(&pool, buyer.id, "sale").await.unwrap();
+        assert_eq!(open[0].open_amount.as_won(), 11000);
+        let ledger = get_customer_ledger(&pool, buyer.id, "receivable").await.unwrap();
+        assert_eq!(ledger.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn payments_must_match_the_customer_type() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let farm = fixtures::supplier(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let purchase = fixtures::draft(&pool, "purchase", farm.id, json!([
+            { "product_id": beef.id, "quantity": 1.0, "tax_rate": 0.1 },
+        ])).await;
+        crate::services::transaction::confirm_transaction(&pool, purchase.transaction.id).await.unwrap();
+
+        let result = record_receipt(&pool, payment(farm.id, 11000, json!([]))).await;
+        assert_eq!(field_of(result), "customer_id");
+        let result = record_disbursement(&pool, payment(buyer.id, 11000, json!([]))).await;
+        assert_eq!(field_of(result), "customer_id");
+
+        let result = record_disbursement(&pool, payment(farm.id, 11000, json!([
+            { "transaction_id": purchase.transaction.id, "amount": 11000 },
+        ]))).await;
+        assert_eq!(result.unwrap().allocations.len(), 1);
+    }
+}
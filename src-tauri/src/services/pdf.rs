@@ -0,0 +1,714 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+use std::rc::Rc;
+use ttf_parser::{Face, GlyphId, Tag};
+
+pub const PAGE_WIDTH_MM: f64 = 210.0;
+pub const PAGE_HEIGHT_MM: f64 = 297.0;
+
+const PT_PER_MM: f64 = 72.0 / 25.4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color(pub u8, pub u8, pub u8);
+
+impl Color {
+    pub const BLACK: Color = Color(0, 0, 0);
+    pub const WHITE: Color = Color(255, 255, 255);
+    pub const GRAY: Color = Color(102, 102, 102);
+    pub const LIGHT_GRAY: Color = Color(221, 221, 221);
+
+    fn operands(self) -> String {
+        format!(
+            "{:.3} {:.3} {:.3}",
+            self.0 as f64 / 255.0,
+            self.1 as f64 / 255.0,
+            self.2 as f64 / 255.0
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TextStyle {
+    pub size: f64, // points
+    pub color: Color,
+    pub bold: bool,
+}
+
+/// Hex digits of the UTF-16BE code units of `text`. Characters outside the
+/// BMP become '?', since UniKS-UCS2-H (and the font) only cover UCS-2.
+fn utf16_hex(text: &str) -> String {
+    text.chars()
+        .map(|c| if (c as u32) > 0xFFFF { '?' } else { c })
+        .map(|c| format!("{:04X}", c as u32))
+        .collect()
+}
+
+/// The font all text of a document is set in
+pub enum PdfFont {
+    /// The standard Korean CID font HYGoThic-Medium (Adobe-Korea1, encoding
+    /// UniKS-UCS2-H). It is not embedded, so viewers substitute their own
+    /// Korean gothic font and layout assumes half-width ASCII and full-width
+    /// everything else.
+    Reference,
+    /// A TrueType font; the glyphs a document uses are embedded
+    Embedded(TrueTypeFont),
+}
+
+/// Font dictionaries (objects 3 to 5) and the objects they refer to
+type FontObjects = (Vec<Vec<u8>>, Vec<Vec<u8>>);
+
+/// A TrueType (glyf outline) font file, with the glyphs used so far
+pub struct TrueTypeFont {
+    data: Vec<u8>,
+    index: u32,
+    name: String,
+    units_per_em: f64,
+    /// Glyph id and advance width (font units) per character looked up
+    glyphs: RefCell<HashMap<char, (u16, u16)>>,
+    /// Glyphs drawn, with the character each stands for (for ToUnicode)
+    used: RefCell<BTreeMap<u16, char>>,
+}
+
+impl PdfFont {
+    /// Reads a TrueType font file (.ttf, or the first font of a .ttc).
+    /// OpenType fonts with CFF outlines (.otf) are not supported.
+    pub fn load(path: &Path) -> Result<PdfFont, String> {
+        let font = TrueTypeFont::parse(std::fs::read(path).map_err(|e| e.to_string())?)?;
+        if font.face().glyph_index('가').is_none() {
+            return Err("the font has no Korean glyphs".to_string());
+        }
+        Ok(PdfFont::Embedded(font))
+    }
+
+    /// Width of a character in ems
+    fn char_width(&self, c: char) -> f64 {
+        match self {
+            PdfFont::Reference => if c.is_ascii() { 0.5 } else { 1.0 },
+            PdfFont::Embedded(font) => font.glyph(c).1 as f64 / font.units_per_em,
+        }
+    }
+
+    /// Width of `text` in millimetres at `size` points
+    pub fn text_width(&self, text: &str, size: f64) -> f64 {
+        text.chars().map(|c| self.char_width(c)).sum::<f64>() * size / PT_PER_MM
+    }
+
+    /// Cuts `text` so it fits `width` millimetres, marking the cut with ".."
+    pub fn fit_text(&self, text: &str, size: f64, width: f64) -> String {
+        if self.text_width(text, size) <= width {
+            return text.to_string();
+        }
+
+        let dots = self.text_width("..", size);
+        let mut fitted = String::new();
+        let mut fitted_width = 0.0;
+        for c in text.chars() {
+            let char_width = self.char_width(c) * size / PT_PER_MM;
+            if fitted_width + char_width + dots > width {
+                break;
+            }
+            fitted.push(c);
+            fitted_width += char_width;
+        }
+        fitted.push_str("..");
+        fitted
+    }
+
+    /// Hex string operand drawing `text`: UCS-2 codes for the reference
+    /// font, glyph ids for an embedded one (which records them as used)
+    fn encode(&self, text: &str) -> String {
+        match self {
+            PdfFont::Reference => utf16_hex(text),
+            PdfFont::Embedded(font) => text
+                .chars()
+                .map(|c| {
+                    let (glyph, _) = font.glyph(c);
+                    font.used.borrow_mut().entry(glyph).or_insert(c);
+                    format!("{:04X}", glyph)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TrueTypeFont {
+    fn parse(data: Vec<u8>) -> Result<TrueTypeFont, String> {
+        let index = 0;
+        let face = Face::parse(&data, index).map_err(|e| format!("not a font file ({})", e))?;
+        let raw = face.raw_face();
+        if raw.table(Tag::from_bytes(b"glyf")).is_none() || raw.table(Tag::from_bytes(b"loca")).is_none() {
+            return Err("only TrueType fonts (glyf outlines) can be embedded".to_string());
+        }
+
+        let name = face
+            .names()
+            .into_iter()
+            .filter(|n| n.name_id == ttf_parser::name_id::POST_SCRIPT_NAME)
+            .find_map(|n| n.to_string())
+            .map(|n| n.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-').collect::<String>())
+            .filter(|n| !n.is_empty())
+            .unwrap_or_else(|| "EmbeddedFont".to_string());
+        let units_per_em = face.units_per_em() as f64;
+
+        Ok(TrueTypeFont {
+            data,
+            index,
+            name,
+            units_per_em,
+            glyphs: RefCell::new(HashMap::new()),
+            used: RefCell::new(BTreeMap::new()),
+        })
+    }
+
+    fn face(&self) -> Face<'_> {
+        Face::parse(&self.data, self.index).expect("font was parsed when loaded")
+    }
+
+    /// Glyph id and advance width of `c`; characters the font lacks get
+    /// .notdef (glyph 0)
+    fn glyph(&self, c: char) -> (u16, u16) {
+        if let Some(glyph) = self.glyphs.borrow().get(&c) {
+            return *glyph;
+        }
+        let face = self.face();
+        let id = face.glyph_index(c).unwrap_or(GlyphId(0));
+        let glyph = (id.0, face.glyph_hor_advance(id).unwrap_or(0));
+        self.glyphs.borrow_mut().insert(c, glyph);
+        glyph
+    }
+
+    /// Font units to PDF glyph space (1/1000 em)
+    fn scale(&self, value: f64) -> i64 {
+        (value * 1000.0 / self.units_per_em).round() as i64
+    }
+
+    /// Type0 font, CID font and font descriptor (objects 3 to 5), then the
+    /// ToUnicode CMap (object 7) and the font file (object 8)
+    fn objects(&self) -> Result<FontObjects, String> {
+        let face = self.face();
+        let used = self.used.borrow();
+        let base_font = format!("SIMERP+{}", self.name);
+
+        let widths: Vec<String> = used
+            .keys()
+            .map(|glyph| {
+                let advance = face.glyph_hor_advance(GlyphId(*glyph)).unwrap_or(0);
+                format!("{} [{}]", glyph, self.scale(advance as f64))
+            })
+            .collect();
+
+        let bbox = face.global_bounding_box();
+        let ascent = self.scale(face.ascender() as f64);
+        let descent = self.scale(face.descender() as f64);
+        let cap_height = face.capital_height().map(|h| self.scale(h as f64)).unwrap_or(ascent);
+
+        let fonts = vec![
+            format!(
+                "<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H \
+                 /DescendantFonts [4 0 R] /ToUnicode 7 0 R >>",
+                base_font
+            )
+            .into_bytes(),
+            format!(
+                "<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{} \
+                 /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+                 /FontDescriptor 5 0 R /DW 1000 /W [{}] /CIDToGIDMap /Identity >>",
+                base_font,
+                widths.join(" ")
+            )
+            .into_bytes(),
+            format!(
+                "<< /Type /FontDescriptor /FontName /{} /Flags 4 /FontBBox [{} {} {} {}] \
+                 /ItalicAngle 0 /Ascent {} /Descent {} /CapHeight {} /StemV 80 /FontFile2 8 0 R >>",
+                base_font,
+                self.scale(bbox.x_min as f64),
+                self.scale(bbox.y_min as f64),
+                self.scale(bbox.x_max as f64),
+                self.scale(bbox.y_max as f64),
+                ascent,
+                descent,
+                cap_height
+            )
+            .into_bytes(),
+        ];
+
+        let font_file = subset_true_type(&face, &used.keys().copied().collect())?;
+        let extra = vec![
+            flate_stream(&to_unicode_cmap(&used), ""),
+            flate_stream(&font_file, &format!(" /Length1 {}", font_file.len())),
+        ];
+        Ok((fonts, extra))
+    }
+}
+
+/// CMap mapping the glyph ids drawn back to their characters, so text can
+/// be searched and copied
+fn to_unicode_cmap(used: &BTreeMap<u16, char>) -> Vec<u8> {
+    let mut cmap = String::from(
+        "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+         1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+    );
+    let entries: Vec<(&u16, &char)> = used.iter().collect();
+    // A bfchar block holds at most 100 entries
+    for block in entries.chunks(100) {
+        cmap.push_str(&format!("{} beginbfchar\n", block.len()));
+        for (glyph, c) in block {
+            let mut units = [0u16; 2];
+            let hex: String = c.encode_utf16(&mut units).iter().map(|u| format!("{:04X}", u)).collect();
+            cmap.push_str(&format!("<{:04X}> <{}>\n", glyph, hex));
+        }
+        cmap.push_str("endbfchar\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+    cmap.into_bytes()
+}
+
+/// A stream object compressed with FlateDecode; `entries` go into its dictionary
+fn flate_stream(data: &[u8], entries: &str) -> Vec<u8> {
+    let compressed = miniz_oxide::deflate::compress_to_vec_zlib(data, 6);
+    let mut object = format!("<< /Length {} /Filter /FlateDecode{} >>\nstream\n", compressed.len(), entries).into_bytes();
+    object.extend_from_slice(&compressed);
+    object.extend_from_slice(b"\nendstream");
+    object
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// The font with only `glyphs`, their composite components and .notdef left
+/// in its glyf table. Glyph ids stay the same, so CIDs map to glyphs one to
+/// one (/CIDToGIDMap /Identity); the other glyphs become empty. Only the
+/// tables a PDF viewer needs to draw glyphs are kept.
+fn subset_true_type(face: &Face, glyphs: &BTreeSet<u16>) -> Result<Vec<u8>, String> {
+    let raw = face.raw_face();
+    let table = |tag: &[u8; 4]| raw.table(Tag::from_bytes(tag));
+    let malformed = || "the font's glyph tables are malformed".to_string();
+
+    let head = table(b"head").ok_or_else(malformed)?;
+    let loca = table(b"loca").ok_or_else(malformed)?;
+    let glyf = table(b"glyf").ok_or_else(malformed)?;
+    let long_offsets = read_u16(head, 50).ok_or_else(malformed)? == 1;
+    let glyph_count = face.number_of_glyphs() as usize;
+
+    let glyph_range = |glyph: u16| -> Option<std::ops::Range<usize>> {
+        let index = glyph as usize;
+        let (start, end) = if long_offsets {
+            (read_u32(loca, index * 4)? as usize, read_u32(loca, index * 4 + 4)? as usize)
+        } else {
+            (read_u16(loca, index * 2)? as usize * 2, read_u16(loca, index * 2 + 2)? as usize * 2)
+        };
+        (start <= end && end <= glyf.len()).then_some(start..end)
+    };
+
+    // Composite glyphs are drawn from other glyphs, which must come along
+    let mut keep: BTreeSet<u16> = glyphs.iter().copied().chain([0]).collect();
+    let mut pending: Vec<u16> = keep.iter().copied().collect();
+    while let Some(glyph) = pending.pop() {
+        let data = &glyf[glyph_range(glyph).ok_or_else(malformed)?];
+        if data.len() < 10 || (read_u16(data, 0).ok_or_else(malformed)? as i16) >= 0 {
+            continue;
+        }
+        let mut offset = 10;
+        loop {
+            let flags = read_u16(data, offset).ok_or_else(malformed)?;
+            let component = read_u16(data, offset + 2).ok_or_else(malformed)?;
+            if keep.insert(component) {
+                pending.push(component);
+            }
+            offset += 4 + if flags & 0x0001 != 0 { 4 } else { 2 };
+            offset += if flags & 0x0008 != 0 {
+                2
+            } else if flags & 0x0040 != 0 {
+                4
+            } else if flags & 0x0080 != 0 {
+                8
+            } else {
+                0
+            };
+            if flags & 0x0020 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut new_glyf = Vec::new();
+    let mut new_loca = Vec::with_capacity((glyph_count + 1) * 4);
+    for glyph in 0..glyph_count {
+        new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+        if keep.contains(&(glyph as u16)) {
+            new_glyf.extend_from_slice(&glyf[glyph_range(glyph as u16).ok_or_else(malformed)?]);
+            new_glyf.resize(new_glyf.len().next_multiple_of(4), 0);
+        }
+    }
+    new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+
+    // Long loca offsets from now on; the checksum adjustment is set at the end
+    let mut new_head = head.to_vec();
+    new_head.get_mut(50..52).ok_or_else(malformed)?.copy_from_slice(&1u16.to_be_bytes());
+    new_head.get_mut(8..12).ok_or_else(malformed)?.fill(0);
+
+    let mut tables: Vec<(&[u8; 4], Vec<u8>)> = vec![
+        (b"glyf", new_glyf),
+        (b"head", new_head),
+        (b"loca", new_loca),
+    ];
+    for tag in [b"cvt ", b"fpgm", b"hhea", b"hmtx", b"maxp", b"prep"] {
+        if let Some(data) = table(tag) {
+            tables.push((tag, data.to_vec()));
+        }
+    }
+    tables.sort_by_key(|(tag, _)| **tag);
+
+    let font = write_sfnt(&tables);
+    let adjustment = 0xB1B0_AFBAu32.wrapping_sub(table_checksum(&font));
+    let mut font = font;
+    let head_offset = font_table_offset(&font, b"head").ok_or_else(malformed)?;
+    font[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    Ok(font)
+}
+
+fn table_checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/// A TrueType file of `tables`, sorted by tag
+fn write_sfnt(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let count = tables.len() as u16;
+    let entry_selector = 15 - count.leading_zeros() as u16;
+    let search_range = (1u16 << entry_selector) * 16;
+
+    let mut font = Vec::new();
+    font.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+    for value in [count, search_range, entry_selector, count * 16 - search_range] {
+        font.extend_from_slice(&value.to_be_bytes());
+    }
+
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, data) in tables {
+        font.extend_from_slice(*tag);
+        font.extend_from_slice(&table_checksum(data).to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        offset += data.len().next_multiple_of(4);
+    }
+    for (_, data) in tables {
+        font.extend_from_slice(data);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+    font
+}
+
+fn font_table_offset(font: &[u8], tag: &[u8; 4]) -> Option<usize> {
+    let count = read_u16(font, 4)? as usize;
+    (0..count)
+        .map(|index| 12 + 16 * index)
+        .find(|record| font.get(*record..*record + 4) == Some(&tag[..]))
+        .and_then(|record| read_u32(font, record + 8))
+        .map(|offset| offset as usize)
+}
+
+/// Drawing operations of one page
+pub struct Page {
+    content: String,
+    font: Rc<PdfFont>,
+}
+
+impl Page {
+    /// See `PdfFont::fit_text`
+    pub fn fit_text(&self, text: &str, size: f64, width: f64) -> String {
+        self.font.fit_text(text, size, width)
+    }
+
+    fn x(mm: f64) -> f64 {
+        mm * PT_PER_MM
+    }
+
+    fn y(mm: f64) -> f64 {
+        (PAGE_HEIGHT_MM - mm) * PT_PER_MM
+    }
+
+    /// Rectangle with its top-left corner at (x, y), filled and/or outlined
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, fill: Option<Color>, stroke: Option<Color>) {
+        let path = format!(
+            "{:.2} {:.2} {:.2} {:.2} re",
+            Self::x(x),
+            Self::y(y + height),
+            width * PT_PER_MM,
+            height * PT_PER_MM
+        );
+
+        match (fill, stroke) {
+            (Some(fill), Some(stroke)) => self.content.push_str(&format!(
+                "{} rg {} RG 0.5 w {} B\n", fill.operands(), stroke.operands(), path
+            )),
+            (Some(fill), None) => self.content.push_str(&format!("{} rg {} f\n", fill.operands(), path)),
+            (None, Some(stroke)) => self.content.push_str(&format!("{} RG 0.5 w {} S\n", stroke.operands(), path)),
+            (None, None) => {}
+        }
+    }
+
+    /// Straight line between two (x, y) points; `width` is in points
+    pub fn line(&mut self, from: (f64, f64), to: (f64, f64), color: Color, width: f64, dashed: bool) {
+        self.content.push_str(&format!(
+            "{} RG {:.2} w {} {:.2} {:.2} m {:.2} {:.2} l S [] 0 d\n",
+            color.operands(),
+            width,
+            if dashed { "[3 3] 0 d" } else { "[] 0 d" },
+            Self::x(from.0),
+            Self::y(from.1),
+            Self::x(to.0),
+            Self::y(to.1)
+        ));
+    }
+
+    /// Text whose baseline is at `y`; `x` is the left edge, centre or right
+    /// edge depending on `align`. Bold is drawn by also stroking the glyphs.
+    pub fn text(&mut self, x: f64, y: f64, text: &str, align: Align, style: TextStyle) {
+        if text.is_empty() {
+            return;
+        }
+
+        let left = match align {
+            Align::Left => x,
+            Align::Center => x - self.font.text_width(text, style.size) / 2.0,
+            Align::Right => x - self.font.text_width(text, style.size),
+        };
+
+        self.content.push_str(&format!(
+            "BT /F1 {:.1} Tf {} rg {} RG {} {:.2} {:.2} Td <{}> Tj ET\n",
+            style.size,
+            style.color.operands(),
+            style.color.operands(),
+            if style.bold { "0.25 w 2 Tr" } else { "0 Tr" },
+            Self::x(left),
+            Self::y(y),
+            self.font.encode(text)
+        ));
+    }
+}
+
+/// A minimal PDF document of A4 portrait pages, with all text in one font
+/// (see `PdfFont`). Coordinates are millimetres from the page's top-left
+/// corner.
+pub struct PdfDocument {
+    title: String,
+    font: Rc<PdfFont>,
+    pages: Vec<Page>,
+}
+
+impl PdfDocument {
+    pub fn new(title: &str, font: PdfFont) -> Self {
+        PdfDocument {
+            title: title.to_string(),
+            font: Rc::new(font),
+            pages: Vec::new(),
+        }
+    }
+
+    pub fn add_page(&mut self) -> &mut Page {
+        self.pages.push(Page { content: String::new(), font: Rc::clone(&self.font) });
+        self.pages.last_mut().expect("page was just added")
+    }
+
+    /// Font objects 3 to 5, and the objects after the info dictionary they refer to
+    fn font_objects(&self) -> Result<FontObjects, String> {
+        match self.font.as_ref() {
+            PdfFont::Embedded(font) => font.objects(),
+            PdfFont::Reference => Ok((
+                vec![
+                    "<< /Type /Font /Subtype /Type0 /BaseFont /HYGoThic-Medium /Encoding /UniKS-UCS2-H /DescendantFonts [4 0 R] >>".to_string(),
+                    "<< /Type /Font /Subtype /CIDFontType0 /BaseFont /HYGoThic-Medium \
+                     /CIDSystemInfo << /Registry (Adobe) /Ordering (Korea1) /Supplement 1 >> \
+                     /FontDescriptor 5 0 R /DW 1000 /W [1 95 500] >>".to_string(),
+                    "<< /Type /FontDescriptor /FontName /HYGoThic-Medium /Flags 4 \
+                     /FontBBox [-6 -145 1003 880] /ItalicAngle 0 /Ascent 880 /Descent -120 \
+                     /CapHeight 880 /StemV 93 >>".to_string(),
+                ]
+                .into_iter()
+                .map(String::into_bytes)
+                .collect(),
+                Vec::new(),
+            )),
+        }
+    }
+
+    /// Serialises the document. Object numbers: 1 catalog, 2 page tree,
+    /// 3 font, 4 CID font, 5 font descriptor, 6 info, then for an embedded
+    /// font 7 ToUnicode CMap and 8 font file, then a page and its content
+    /// stream per page. Fails only if an embedded font cannot be subset.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let (fonts, font_extra) = self.font_objects()?;
+
+        let page_count = self.pages.len().max(1);
+        let first_page = 7 + font_extra.len();
+        let page_ids: Vec<usize> = (0..page_count).map(|i| first_page + i * 2).collect();
+
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" "),
+                page_count
+            )
+            .into_bytes(),
+        ];
+        objects.extend(fonts);
+        objects.push(format!("<< /Title <FEFF{}> /Producer (Simple ERP) >>", utf16_hex(&self.title)).into_bytes());
+        objects.extend(font_extra);
+
+        for (index, page_id) in page_ids.iter().enumerate() {
+            let content = self.pages.get(index).map(|page| page.content.as_str()).unwrap_or("");
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH_MM * PT_PER_MM,
+                PAGE_HEIGHT_MM * PT_PER_MM,
+                page_id + 1
+            ).into_bytes());
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                content.len(),
+                content
+            ).into_bytes());
+        }
+
+        let mut out: Vec<u8> = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            out.extend_from_slice(object);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref = out.len();
+        out.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        out.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R /Info 6 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .as_bytes(),
+        );
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEJAVU: &str = "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf";
+
+    /// DejaVu Sans has no Hangul, so it is read past `PdfFont::load`; tests
+    /// needing it are skipped where it is not installed
+    fn dejavu() -> Option<TrueTypeFont> {
+        let data = std::fs::read(DEJAVU).ok()?;
+        Some(TrueTypeFont::parse(data).expect("DejaVu Sans parses"))
+    }
+
+    fn style() -> TextStyle {
+        TextStyle { size: 10.0, color: Color::BLACK, bold: false }
+    }
+
+    #[test]
+    fn reference_font_is_not_embedded() {
+        let mut document = PdfDocument::new("거래명세서", PdfFont::Reference);
+        document.add_page().text(10.0, 10.0, "가A", Align::Left, style());
+        let pdf = String::from_utf8_lossy(&document.to_bytes().unwrap()).into_owned();
+
+        assert!(pdf.contains("/BaseFont /HYGoThic-Medium /Encoding /UniKS-UCS2-H"));
+        assert!(pdf.contains("<AC000041> Tj"));
+        assert!(!pdf.contains("/FontFile2"));
+        assert!(pdf.contains("xref\n0 9\n"));
+    }
+
+    #[test]
+    fn reference_font_fits_text_by_half_and_full_widths() {
+        let font = PdfFont::Reference;
+        // 10pt: ASCII is 5pt wide, Hangul 10pt
+        let mm = |pt: f64| pt / PT_PER_MM;
+        assert_eq!(font.fit_text("AB가", 10.0, mm(20.0)), "AB가");
+        assert_eq!(font.fit_text("AB가나", 10.0, mm(25.0)), "AB..");
+    }
+
+    #[test]
+    fn load_rejects_fonts_without_hangul() {
+        if !Path::new(DEJAVU).exists() {
+            return;
+        }
+        let error = PdfFont::load(Path::new(DEJAVU)).err().unwrap();
+        assert_eq!(error, "the font has no Korean glyphs");
+        assert!(PdfFont::load(Path::new("/nonexistent/font.ttf")).is_err());
+    }
+
+    #[test]
+    fn embeds_only_the_glyphs_drawn() {
+        let Some(font) = dejavu() else { return };
+        let mut document = PdfDocument::new("Delivery note", PdfFont::Embedded(font));
+        document.add_page().text(10.0, 10.0, "Ab", Align::Left, style());
+
+        let PdfFont::Embedded(font) = document.font.as_ref() else { unreachable!() };
+        let face = font.face();
+        let a = face.glyph_index('A').unwrap();
+        let b = face.glyph_index('b').unwrap();
+        let z = face.glyph_index('Z').unwrap();
+        assert_eq!(font.used.borrow().values().collect::<String>(), "Ab");
+
+        let subset = subset_true_type(&face, &font.used.borrow().keys().copied().collect()).unwrap();
+        assert!(subset.len() < font.data.len() / 10);
+        let subset = Face::parse(&subset, 0).expect("the subset is a valid font");
+        assert_eq!(subset.number_of_glyphs(), face.number_of_glyphs());
+        assert_eq!(subset.glyph_bounding_box(a), face.glyph_bounding_box(a));
+        assert_eq!(subset.glyph_bounding_box(b), face.glyph_bounding_box(b));
+        assert!(face.glyph_bounding_box(z).is_some());
+        assert_eq!(subset.glyph_bounding_box(z), None);
+        assert_eq!(subset.glyph_hor_advance(a), face.glyph_hor_advance(a));
+
+        let pdf = String::from_utf8_lossy(&document.to_bytes().unwrap()).into_owned();
+        assert!(pdf.contains("/BaseFont /SIMERP+DejaVuSans /Encoding /Identity-H /DescendantFonts [4 0 R] /ToUnicode 7 0 R"));
+        assert!(pdf.contains(&format!("<{:04X}{:04X}> Tj", a.0, b.0)));
+        assert!(pdf.contains(&format!(
+            "/W [{} [{}] {} [{}]]",
+            a.0,
+            font.scale(face.glyph_hor_advance(a).unwrap() as f64),
+            b.0,
+            font.scale(face.glyph_hor_advance(b).unwrap() as f64)
+        )));
+        assert!(pdf.contains("/FontFile2 8 0 R"));
+        assert!(pdf.contains("/Contents 10 0 R"));
+        assert!(pdf.contains("xref\n0 11\n"));
+    }
+
+    #[test]
+    fn to_unicode_maps_glyphs_back_to_characters() {
+        let used: BTreeMap<u16, char> = (0..150u16).map(|g| (g + 1, char::from_u32(0xAC00 + g as u32).unwrap())).collect();
+        let cmap = String::from_utf8(to_unicode_cmap(&used)).unwrap();
+
+        assert!(cmap.contains("100 beginbfchar\n<0001> <AC00>\n"));
+        assert!(cmap.contains("50 beginbfchar\n<0065> <AC64>\n"));
+        assert!(cmap.contains("<0096> <AC95>\nendbfchar\n"));
+    }
+}
@@ -0,0 +1,286 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::{CustomerProductPrice, PriceHistory, SetCustomerProductPriceRequest};
+use crate::money::Money;
+use chrono::Utc;
+use sqlx::SqliteConnection;
+
+pub async fn get_customer_product_prices(pool: &DbPool, customer_id: i64) -> AppResult<Vec<CustomerProductPrice>> {
+    let prices = sqlx::query_as::<_, CustomerProductPrice>(
+        r#"
+        SELECT cpp.* FROM customer_product_prices cpp
+        JOIN products p ON cpp.product_id = p.id
+        WHERE cpp.customer_id = ? AND cpp.is_active = true
+        ORDER BY p.name
+        "#
+    )
+    .bind(customer_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(prices)
+}
+
+pub async fn get_customer_product_price(
+    pool: &DbPool,
+    customer_id: i64,
+    product_id: i64,
+) -> AppResult<Option<CustomerProductPrice>> {
+    let price = sqlx::query_as::<_, CustomerProductPrice>(
+        r#"
+        SELECT * FROM customer_product_prices
+        WHERE customer_id = ? AND product_id = ? AND is_active = true
+        "#
+    )
+    .bind(customer_id)
+    .bind(product_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(price)
+}
+
+/// Sets a customer's price for a product by hand, recording the change in price_history
+pub async fn set_customer_product_price(
+    pool: &DbPool,
+    request: SetCustomerProductPriceRequest,
+) -> AppResult<CustomerProductPrice> {
+    if request.price.is_negative() {
+        return Err(AppError::invalid_field("price", "Price cannot be negative".to_string()));
+    }
+
+    let customer_exists = sqlx::query("SELECT id FROM customers WHERE id = ?")
+        .bind(request.customer_id)
+        .fetch_optional(pool)
+        .await?;
+
+    if customer_exists.is_none() {
+        return Err(AppError::NotFound("Customer not found".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let old_price = default_unit_price(&mut tx, request.customer_id, request.product_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Product not found or inactive".to_string()))?;
+
+    let price = set_price(
+        &mut tx,
+        request.customer_id,
+        request.product_id,
+        old_price,
+        request.price,
+        None,
+        request.notes.as_deref(),
+    ).await?;
+
+    tx.commit().await?;
+    Ok(price)
+}
+
+/// Price changes of a customer, newest first, optionally for a single product
+pub async fn get_price_history(
+    pool: &DbPool,
+    customer_id: i64,
+    product_id: Option<i64>,
+) -> AppResult<Vec<PriceHistory>> {
+    let history = sqlx::query_as::<_, PriceHistory>(
+        r#"
+        SELECT * FROM price_history
+        WHERE customer_id = ? AND (? IS NULL OR product_id = ?)
+        ORDER BY changed_date DESC, id DESC
+        "#
+    )
+    .bind(customer_id)
+    .bind(product_id)
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(history)
+}
+
+/// Unit price a new item gets when the request leaves it out: the customer's
+/// price book entry, else the product's list price. `None` if the product
+/// does not exist or is inactive.
+pub async fn default_unit_price(
+    conn: &mut SqliteConnection,
+    customer_id: i64,
+    product_id: i64,
+) -> AppResult<Option<Money>> {
+    let price: Option<(Money,)> = sqlx::query_as(
+        r#"
+        SELECT COALESCE(cpp.current_price_per_kg, p.unit_price)
+        FROM products p
+        LEFT JOIN customer_product_prices cpp
+            ON cpp.product_id = p.id AND cpp.customer_id = ? AND cpp.is_active = true
+        WHERE p.id = ? AND p.is_active = true
+        "#
+    )
+    .bind(customer_id)
+    .bind(product_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(price.map(|p| p.0))
+}
+
+/// Updates the price book from the items of a transaction being confirmed.
+///
+/// Every product sold (or bought) at a price other than the customer's
+/// current default becomes the new price book entry, and a price_history
+/// row linked to the transaction is appended. When a product appears on
+/// several lines the last line's price wins. Prices are kept VAT-exclusive:
+/// a VAT-inclusive transaction's prices have their VAT taken out first.
+/// Call it on the same DB transaction as the confirmation.
+pub async fn record_transaction_prices(conn: &mut SqliteConnection, transaction_id: i64) -> AppResult<()> {
+    let items: Vec<(i64, i64, Money, f64, String)> = sqlx::query_as(
+        r#"
+        SELECT t.customer_id, ti.product_id, ti.unit_price, ti.tax_rate, t.price_mode
+        FROM transaction_items ti
+        JOIN transactions t ON ti.transaction_id = t.id
+        WHERE ti.transaction_id = ? AND t.transaction_type IN ('sale', 'purchase')
+        ORDER BY ti.id
+        "#
+    )
+    .bind(transaction_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut latest: Vec<(i64, i64, Money)> = Vec::new();
+    for (customer_id, product_id, unit_price, tax_rate, price_mode) in items {
+        let unit_price = if price_mode == "inclusive" { unit_price.excluding_vat(tax_rate) } else { unit_price };
+        match latest.iter_mut().find(|(_, p, _)| *p == product_id) {
+            Some(entry) => entry.2 = unit_price,
+            None => latest.push((customer_id, product_id, unit_price)),
+        }
+    }
+
+    for (customer_id, product_id, unit_price) in latest {
+        // Inactive products keep their lines but no longer get a price book entry
+        let Some(old_price) = default_unit_price(conn, customer_id, product_id).await? else {
+            continue;
+        };
+
+        if old_price != unit_price {
+            set_price(conn, customer_id, product_id, old_price, unit_price, Some(transaction_id), None).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn set_price(
+    conn: &mut SqliteConnection,
+    customer_id: i64,
+    product_id: i64,
+    old_price: Money,
+    new_price: Money,
+    transaction_id: Option<i64>,
+    notes: Option<&str>,
+) -> AppResult<CustomerProductPrice> {
+    let now = Utc::now();
+
+    let price = sqlx::query_as::<_, CustomerProductPrice>(
+        r#"
+        INSERT INTO customer_product_prices (customer_id, product_id, current_price_per_kg, last_updated, is_active)
+        VALUES (?, ?, ?, ?, true)
+        ON CONFLICT (customer_id, product_id)
+        DO UPDATE SET current_price_per_kg = excluded.current_price_per_kg,
+            last_updated = excluded.last_updated, is_active = true
+        RETURNING *
+        "#
+    )
+    .bind(customer_id)
+    .bind(product_id)
+    .bind(new_price)
+    .bind(now)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if old_price != new_price {
+        sqlx::query(
+            r#"
+            INSERT INTO price_history (
+                customer_id, product_id, old_price, new_price, changed_date, transaction_id, notes
+            ) VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(customer_id)
+        .bind(product_id)
+        .bind(old_price)
+        .bind(new_price)
+        .bind(now)
+        .bind(transaction_id)
+        .bind(notes)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use crate::services::transaction;
+    use serde_json::json;
+
+    fn price_request(customer_id: i64, product_id: i64, price: i64) -> SetCustomerProductPriceRequest {
+        serde_json::from_value(json!({
+            "customer_id": customer_id,
+            "product_id": product_id,
+            "price": price,
+            "notes": "3월 단가 협의",
+        })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn confirmed_prices_become_the_customers_default() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let sale = fixtures::draft(&pool, "sale", buyer.id, json!([
+            { "product_id": beef.id, "quantity": 1.0, "tax_rate": 0.1, "unit_price": 12000 },
+        ])).await;
+        assert!(get_customer_product_price(&pool, buyer.id, beef.id).await.unwrap().is_none());
+
+        transaction::confirm_transaction(&pool, sale.transaction.id).await.unwrap();
+
+        let price = get_customer_product_price(&pool, buyer.id, beef.id).await.unwrap().unwrap();
+        assert_eq!(price.current_price_per_kg.as_won(), 12000);
+        let history = get_price_history(&pool, buyer.id, Some(beef.id)).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].old_price.as_won(), 10000);
+        assert_eq!(history[0].transaction_id, Some(sale.transaction.id));
+
+        // The next line without a price takes the price book entry, and confirming it changes nothing
+        let next = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 1.0).await;
+        assert_eq!(next.items[0].item.unit_price.as_won(), 12000);
+        assert_eq!(get_price_history(&pool, buyer.id, None).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn manual_prices_are_checked_and_recorded() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+
+        let result = set_customer_product_price(&pool, price_request(buyer.id, beef.id, -1)).await;
+        assert!(matches!(result, Err(AppError::Validation { field: Some(ref f), .. }) if f == "price"));
+        let result = set_customer_product_price(&pool, price_request(buyer.id, beef.id + 1, 9000)).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        let result = set_customer_product_price(&pool, price_request(buyer.id + 100, beef.id, 9000)).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+
+        let price = set_customer_product_price(&pool, price_request(buyer.id, beef.id, 9000)).await.unwrap();
+
+        assert_eq!(price.current_price_per_kg.as_won(), 9000);
+        let history = get_price_history(&pool, buyer.id, Some(beef.id)).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].transaction_id, None);
+        assert_eq!(history[0].notes.as_deref(), Some("3월 단가 협의"));
+    }
+}
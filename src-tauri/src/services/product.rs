@@ -2,6 +2,26 @@ use crate::database::DbPool;
 use crate::errors::{AppError, AppResult};
 use crate::models::{Product, CreateProductRequest, UpdateProductRequest};
 use chrono::Utc;
+use super::traceability;
+
+fn validate_lot_consumption(lot_consumption: &str) -> AppResult<()> {
+    if !matches!(lot_consumption, "fefo" | "fifo") {
+        return Err(AppError::invalid_field("lot_consumption",
+            "Lot consumption must be 'fefo' or 'fifo'".to_string()
+        ));
+    }
+    Ok(())
+}
+
+/// Checks a tax category: 'taxable', 'zero_rated' (영세율) or 'exempt' (면세)
+pub(crate) fn validate_tax_category(tax_category: &str) -> AppResult<()> {
+    if !matches!(tax_category, "taxable" | "zero_rated" | "exempt") {
+        return Err(AppError::invalid_field("tax_category",
+            "Tax category must be 'taxable', 'zero_rated' or 'exempt'".to_string()
+        ));
+    }
+    Ok(())
+}
 
 pub async fn get_products(pool: &DbPool, active_only: bool) -> AppResult<Vec<Product>> {
     let query = if active_only {
@@ -29,23 +49,36 @@ pub async fn get_product_by_id(pool: &DbPool, id: i64) -> AppResult<Option<Produ
     Ok(product)
 }
 
-pub async fn create_product(pool: &DbPool, request: CreateProductRequest) -> AppResult<Product> {
+pub async fn create_product(pool: &DbPool, mut request: CreateProductRequest) -> AppResult<Product> {
     let now = Utc::now();
     
     // Validate unit price
-    if request.unit_price < 0.0 {
-        return Err(AppError::Validation(
+    if request.unit_price.is_negative() {
+        return Err(AppError::invalid_field("unit_price",
             "Unit price cannot be negative".to_string()
         ));
     }
     
     // Validate tax rate
     if request.tax_rate < 0.0 || request.tax_rate > 1.0 {
-        return Err(AppError::Validation(
+        return Err(AppError::invalid_field("tax_rate",
             "Tax rate must be between 0.0 and 1.0".to_string()
         ));
     }
     
+    if let Some(ref tax_category) = request.tax_category {
+        validate_tax_category(tax_category)?;
+    }
+    
+    if let Some(ref lot_consumption) = request.lot_consumption {
+        validate_lot_consumption(lot_consumption)?;
+    }
+    
+    let traceability = traceability::check_traceability_number(
+        &mut *pool.acquire().await?, request.traceability_number.take()
+    ).await?;
+    request.traceability_number = traceability.number;
+    
     // Check for duplicate product code if provided
     if let Some(ref code) = request.code {
         let existing = sqlx::query("SELECT id FROM products WHERE code = ?")
@@ -54,7 +87,7 @@ pub async fn create_product(pool: &DbPool, request: CreateProductRequest) -> App
             .await?;
         
         if existing.is_some() {
-            return Err(AppError::Validation(
+            return Err(AppError::invalid_field("code",
                 "Product code already exists".to_string()
             ));
         }
@@ -64,8 +97,10 @@ pub async fn create_product(pool: &DbPool, request: CreateProductRequest) -> App
         r#"
         INSERT INTO products (
             name, code, description, unit_price, unit, 
-            tax_rate, category, is_active, created_at, updated_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, true, ?, ?)
+            tax_rate, tax_category, category, traceability_number, traceability_warning, origin,
+            slaughterhouse, use_inventory_management, lot_consumption, track_expiry, is_active,
+            created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, true, ?, ?)
         "#
     )
     .bind(&request.name)
@@ -74,7 +109,15 @@ pub async fn create_product(pool: &DbPool, request: CreateProductRequest) -> App
     .bind(request.unit_price)
     .bind(&request.unit)
     .bind(request.tax_rate)
+    .bind(request.tax_category.as_deref().unwrap_or("taxable"))
     .bind(&request.category)
+    .bind(&request.traceability_number)
+    .bind(&traceability.warning)
+    .bind(&request.origin)
+    .bind(&request.slaughterhouse)
+    .bind(request.use_inventory_management.unwrap_or(false))
+    .bind(request.lot_consumption.as_deref().unwrap_or("fefo"))
+    .bind(request.track_expiry.unwrap_or(true))
     .bind(now)
     .bind(now)
     .execute(pool)
@@ -87,13 +130,13 @@ pub async fn create_product(pool: &DbPool, request: CreateProductRequest) -> App
     Ok(product)
 }
 
-pub async fn update_product(pool: &DbPool, id: i64, request: UpdateProductRequest) -> AppResult<Product> {
+pub async fn update_product(pool: &DbPool, id: i64, mut request: UpdateProductRequest) -> AppResult<Product> {
     let now = Utc::now();
     
     // Validate unit price if provided
     if let Some(price) = request.unit_price {
-        if price < 0.0 {
-            return Err(AppError::Validation(
+        if price.is_negative() {
+            return Err(AppError::invalid_field("unit_price",
                 "Unit price cannot be negative".to_string()
             ));
         }
@@ -102,12 +145,25 @@ pub async fn update_product(pool: &DbPool, id: i64, request: UpdateProductReques
     // Validate tax rate if provided
     if let Some(tax_rate) = request.tax_rate {
         if tax_rate < 0.0 || tax_rate > 1.0 {
-            return Err(AppError::Validation(
+            return Err(AppError::invalid_field("tax_rate",
                 "Tax rate must be between 0.0 and 1.0".to_string()
             ));
         }
     }
     
+    if let Some(ref tax_category) = request.tax_category {
+        validate_tax_category(tax_category)?;
+    }
+    
+    if let Some(ref lot_consumption) = request.lot_consumption {
+        validate_lot_consumption(lot_consumption)?;
+    }
+    
+    let traceability = traceability::check_traceability_number(
+        &mut *pool.acquire().await?, request.traceability_number.take()
+    ).await?;
+    request.traceability_number = traceability.number;
+    
     // Check for duplicate product code if provided and different from current
     if let Some(ref code) = request.code {
         let existing = sqlx::query("SELECT id FROM products WHERE code = ? AND id != ?")
@@ -117,7 +173,7 @@ pub async fn update_product(pool: &DbPool, id: i64, request: UpdateProductReques
             .await?;
         
         if existing.is_some() {
-            return Err(AppError::Validation(
+            return Err(AppError::invalid_field("code",
                 "Product code already exists".to_string()
             ));
         }
@@ -144,15 +200,37 @@ pub async fn update_product(pool: &DbPool, id: i64, request: UpdateProductReques
     if request.tax_rate.is_some() {
         query_parts.push("tax_rate = ?");
     }
+    if request.tax_category.is_some() {
+        query_parts.push("tax_category = ?");
+    }
     if request.category.is_some() {
         query_parts.push("category = ?");
     }
+    if request.traceability_number.is_some() {
+        query_parts.push("traceability_number = ?");
+        query_parts.push("traceability_warning = ?");
+    }
+    if request.origin.is_some() {
+        query_parts.push("origin = ?");
+    }
+    if request.slaughterhouse.is_some() {
+        query_parts.push("slaughterhouse = ?");
+    }
+    if request.use_inventory_management.is_some() {
+        query_parts.push("use_inventory_management = ?");
+    }
+    if request.lot_consumption.is_some() {
+        query_parts.push("lot_consumption = ?");
+    }
+    if request.track_expiry.is_some() {
+        query_parts.push("track_expiry = ?");
+    }
     if request.is_active.is_some() {
         query_parts.push("is_active = ?");
     }
     
     if query_parts.is_empty() {
-        return Err(AppError::Validation("No fields to update".to_string()));
+        return Err(AppError::validation("No fields to update".to_string()));
     }
     
     query_parts.push("updated_at = ?");
@@ -183,9 +261,30 @@ pub async fn update_product(pool: &DbPool, id: i64, request: UpdateProductReques
     if let Some(tax_rate) = request.tax_rate {
         query = query.bind(tax_rate);
     }
+    if let Some(tax_category) = &request.tax_category {
+        query = query.bind(tax_category);
+    }
     if let Some(category) = &request.category {
         query = query.bind(category);
     }
+    if let Some(traceability_number) = &request.traceability_number {
+        query = query.bind(traceability_number).bind(&traceability.warning);
+    }
+    if let Some(origin) = &request.origin {
+        query = query.bind(origin);
+    }
+    if let Some(slaughterhouse) = &request.slaughterhouse {
+        query = query.bind(slaughterhouse);
+    }
+    if let Some(use_inventory_management) = request.use_inventory_management {
+        query = query.bind(use_inventory_management);
+    }
+    if let Some(lot_consumption) = &request.lot_consumption {
+        query = query.bind(lot_consumption);
+    }
+    if let Some(track_expiry) = request.track_expiry {
+        query = query.bind(track_expiry);
+    }
     if let Some(is_active) = request.is_active {
         query = query.bind(is_active);
     }
@@ -266,6 +365,19 @@ pub async fn search_products(pool: &DbPool, query: &str, active_only: bool) -> A
     Ok(products)
 }
 
+/// Products still marked 'taxable' with a 0% rate. Products registered
+/// before tax categories existed used a 0% rate for both exempt (면세) and
+/// zero-rated (영세율) goods, so each needs its category set by hand.
+pub async fn get_products_for_tax_review(pool: &DbPool) -> AppResult<Vec<Product>> {
+    let products = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products WHERE tax_category = 'taxable' AND tax_rate = 0 ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(products)
+}
+
 pub async fn get_products_by_category(pool: &DbPool, category: &str, active_only: bool) -> AppResult<Vec<Product>> {
     let query = if active_only {
         sqlx::query_as::<_, Product>(
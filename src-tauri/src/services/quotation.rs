@@ -0,0 +1,503 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::{
+    CreateQuotationRequest, CreateTransactionItemRequest, CreateTransactionRequest, Customer, Product,
+    Quotation, QuotationItem, QuotationItemWithProduct, QuotationWithItems, TransactionWithItems,
+};
+use crate::money::Money;
+use chrono::{DateTime, Local, Utc};
+use sqlx::SqliteConnection;
+use super::{company, pricing, product, transaction};
+
+const QUOTATION_STATUSES: [&str; 5] = ["draft", "sent", "accepted", "expired", "rejected"];
+
+/// Next `Q-YYYYMMDD-NNNN` number for the local day of `quotation_date`
+async fn next_quotation_number(conn: &mut SqliteConnection, quotation_date: DateTime<Utc>) -> AppResult<String> {
+    let prefix = format!("Q-{}-", quotation_date.with_timezone(&Local).format("%Y%m%d"));
+
+    let numbers: Vec<(String,)> = sqlx::query_as("SELECT quotation_number FROM quotations WHERE quotation_number LIKE ?")
+        .bind(format!("{}%", prefix))
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let last = numbers
+        .iter()
+        .filter_map(|(n,)| n.strip_prefix(&prefix).and_then(|seq| seq.parse::<i64>().ok()))
+        .max()
+        .unwrap_or(0);
+
+    Ok(format!("{}{:04}", prefix, last + 1))
+}
+
+fn quotation_status_error(quotation: &Quotation, action: &str) -> AppError {
+    AppError::Business(format!(
+        "Cannot {} quotation {} in status '{}'",
+        action, quotation.quotation_number, quotation.status
+    ))
+}
+
+/// A draft or sent quote is past its validity date once the local day is after `valid_until`
+fn is_past_validity(quotation: &Quotation) -> bool {
+    quotation.valid_until < Local::now().date_naive()
+}
+
+pub async fn get_quotations(
+    pool: &DbPool,
+    status: Option<String>,
+    customer_id: Option<i64>,
+) -> AppResult<Vec<QuotationWithItems>> {
+    if let Some(status) = status.as_deref() {
+        if !QUOTATION_STATUSES.contains(&status) {
+            return Err(AppError::validation(
+                "Status must be 'draft', 'sent', 'accepted', 'expired' or 'rejected'".to_string()
+            ));
+        }
+    }
+
+    let quotations = sqlx::query_as::<_, Quotation>(
+        r#"
+        SELECT * FROM quotations
+        WHERE (? IS NULL OR status = ?) AND (? IS NULL OR customer_id = ?)
+        ORDER BY quotation_date DESC, id DESC
+        "#
+    )
+    .bind(&status)
+    .bind(&status)
+    .bind(customer_id)
+    .bind(customer_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut result = Vec::with_capacity(quotations.len());
+    for quotation in quotations {
+        result.push(with_items(pool, quotation).await?);
+    }
+
+    Ok(result)
+}
+
+pub async fn get_quotation_by_id(pool: &DbPool, id: i64) -> AppResult<Option<QuotationWithItems>> {
+    let quotation = sqlx::query_as::<_, Quotation>("SELECT * FROM quotations WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    match quotation {
+        Some(quotation) => Ok(Some(with_items(pool, quotation).await?)),
+        None => Ok(None),
+    }
+}
+
+async fn with_items(pool: &DbPool, quotation: Quotation) -> AppResult<QuotationWithItems> {
+    let customer = sqlx::query_as::<_, Customer>("SELECT * FROM customers WHERE id = ?")
+        .bind(quotation.customer_id)
+        .fetch_one(pool)
+        .await?;
+
+    let rows = sqlx::query_as::<_, QuotationItem>(
+        "SELECT * FROM quotation_items WHERE quotation_id = ? ORDER BY id"
+    )
+    .bind(quotation.id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut items = Vec::with_capacity(rows.len());
+    for item in rows {
+        let product = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = ?")
+            .bind(item.product_id)
+            .fetch_one(pool)
+            .await?;
+        items.push(QuotationItemWithProduct { item, product });
+    }
+
+    Ok(QuotationWithItems { quotation, items, customer })
+}
+
+async fn require_quotation(pool: &DbPool, id: i64) -> AppResult<QuotationWithItems> {
+    get_quotation_by_id(pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Quotation not found".to_string()))
+}
+
+/// Creates a draft quotation (견적서). Items are validated like transaction
+/// items and a missing unit price comes from the customer's price book, so a
+/// quote shows the price the sale would get.
+pub async fn create_quotation(pool: &DbPool, request: CreateQuotationRequest) -> AppResult<QuotationWithItems> {
+    let now = Utc::now();
+
+    if request.valid_until < request.quotation_date.with_timezone(&Local).date_naive() {
+        return Err(AppError::validation("Validity date cannot be before the quotation date".to_string()));
+    }
+
+    if request.items.is_empty() {
+        return Err(AppError::validation("Quotation must have at least one item".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Take the write lock first so two quotes cannot pick the same number
+    sqlx::query("UPDATE quotations SET status = status WHERE 0")
+        .execute(&mut *tx)
+        .await?;
+
+    let customer_exists: Option<(i64,)> = sqlx::query_as("SELECT id FROM customers WHERE id = ?")
+        .bind(request.customer_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    if customer_exists.is_none() {
+        return Err(AppError::NotFound("Customer not found".to_string()));
+    }
+
+    let price_mode = request.price_mode.clone().unwrap_or_else(|| "exclusive".to_string());
+    transaction::validate_price_mode(&price_mode)?;
+
+    // (unit price, tax rate, tax category) of each item, in request order
+    let mut lines = Vec::with_capacity(request.items.len());
+
+    for item in &request.items {
+        if item.quantity <= 0.0 {
+            return Err(AppError::validation("Item quantity must be positive".to_string()));
+        }
+
+        if item.unit_price.is_some_and(|price| price.is_negative()) {
+            return Err(AppError::validation("Item unit price cannot be negative".to_string()));
+        }
+
+        if item.tax_rate < 0.0 || item.tax_rate > 1.0 {
+            return Err(AppError::validation("Tax rate must be between 0.0 and 1.0".to_string()));
+        }
+
+        let default_price = pricing::default_unit_price(&mut tx, request.customer_id, item.product_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Product not found or inactive".to_string()))?;
+
+        let tax_category = match &item.tax_category {
+            Some(tax_category) => tax_category.clone(),
+            None => {
+                let (tax_category,): (String,) = sqlx::query_as("SELECT tax_category FROM products WHERE id = ?")
+                    .bind(item.product_id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                tax_category
+            }
+        };
+        product::validate_tax_category(&tax_category)?;
+        let tax_rate = if tax_category == "taxable" { item.tax_rate } else { 0.0 };
+
+        // The price book holds VAT-exclusive prices
+        let default_price = if price_mode == "inclusive" { default_price.including_vat(tax_rate) } else { default_price };
+
+        lines.push((item.unit_price.unwrap_or(default_price), tax_rate, tax_category));
+    }
+
+    let amounts = company::vat_policy(&mut tx).await?.split_lines(
+        &request
+            .items
+            .iter()
+            .zip(&lines)
+            .map(|(item, (unit_price, tax_rate, _))| (unit_price.times(item.quantity), *tax_rate))
+            .collect::<Vec<_>>(),
+        price_mode == "inclusive",
+    );
+
+    let mut subtotal_amount = Money::ZERO;
+    let mut tax_amount = Money::ZERO;
+    let mut exempt_amount = Money::ZERO;
+    for ((_, _, tax_category), (supply, tax)) in lines.iter().zip(&amounts) {
+        if tax_category == "exempt" {
+            exempt_amount += *supply;
+        } else {
+            subtotal_amount += *supply;
+            tax_amount += *tax;
+        }
+    }
+
+    let quotation_number = next_quotation_number(&mut tx, request.quotation_date).await?;
+
+    let quotation_id = sqlx::query(
+        r#"
+        INSERT INTO quotations (
+            quotation_number, customer_id, quotation_date, valid_until, price_mode,
+            subtotal, tax_amount, exempt_amount, total_amount, status, notes, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 'draft', ?, ?, ?)
+        "#
+    )
+    .bind(&quotation_number)
+    .bind(request.customer_id)
+    .bind(request.quotation_date)
+    .bind(request.valid_until)
+    .bind(&price_mode)
+    .bind(subtotal_amount)
+    .bind(tax_amount)
+    .bind(exempt_amount)
+    .bind(subtotal_amount + tax_amount + exempt_amount)
+    .bind(&request.notes)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    for ((item, (unit_price, tax_rate, tax_category)), (supply, tax)) in request.items.iter().zip(lines).zip(amounts) {
+        sqlx::query(
+            r#"
+            INSERT INTO quotation_items (
+                quotation_id, product_id, quantity, unit_price,
+                tax_rate, tax_category, subtotal, tax_amount, total_amount, notes, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(quotation_id)
+        .bind(item.product_id)
+        .bind(item.quantity)
+        .bind(unit_price)
+        .bind(tax_rate)
+        .bind(&tax_category)
+        .bind(supply)
+        .bind(tax)
+        .bind(supply + tax)
+        .bind(&item.notes)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    require_quotation(pool, quotation_id).await
+}
+
+/// Moves a quotation along draft → sent → accepted, or to rejected from
+/// draft or sent. 'expired' is only set by `expire_quotations`, and a quote
+/// past its validity date can no longer be sent or accepted.
+pub async fn update_quotation_status(pool: &DbPool, id: i64, status: String) -> AppResult<QuotationWithItems> {
+    if !QUOTATION_STATUSES.contains(&status.as_str()) {
+        return Err(AppError::validation(
+            "Status must be 'draft', 'sent', 'accepted', 'expired' or 'rejected'".to_string()
+        ));
+    }
+
+    let quotation = require_quotation(pool, id).await?.quotation;
+
+    let allowed = match (quotation.status.as_str(), status.as_str()) {
+        ("draft", "sent") | ("sent", "accepted") => {
+            if is_past_validity(&quotation) {
+                return Err(AppError::Business(format!(
+                    "Quotation {} expired on {}",
+                    quotation.quotation_number, quotation.valid_until
+                )));
+            }
+            true
+        }
+        ("draft", "rejected") | ("sent", "rejected") => true,
+        _ => false,
+    };
+
+    if !allowed {
+        return Err(quotation_status_error(&quotation, &format!("mark as '{}'", status)));
+    }
+
+    sqlx::query("UPDATE quotations SET status = ?, updated_at = ? WHERE id = ?")
+        .bind(&status)
+        .bind(Utc::now())
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    require_quotation(pool, id).await
+}
+
+/// Deletes a draft quotation and its items
+pub async fn delete_quotation(pool: &DbPool, id: i64) -> AppResult<()> {
+    let quotation = require_quotation(pool, id).await?.quotation;
+    if quotation.status != "draft" {
+        return Err(quotation_status_error(&quotation, "delete"));
+    }
+
+    sqlx::query("DELETE FROM quotations WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Turns a quotation into a draft sale with the quoted items and prices.
+///
+/// The sale goes through the same validation as `create_transaction` and is
+/// created on the same DB transaction that marks the quote 'accepted' and
+/// links it, so a quote converts at most once. Draft and sent quotes must
+/// still be within their validity date; an accepted quote keeps its prices.
+/// The sale is dated `transaction_date`, or now.
+pub async fn convert_quotation_to_transaction(
+    pool: &DbPool,
+    id: i64,
+    transaction_date: Option<DateTime<Utc>>,
+) -> AppResult<TransactionWithItems> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE quotations SET status = status WHERE 0")
+        .execute(&mut *tx)
+        .await?;
+
+    let quotation = sqlx::query_as::<_, Quotation>("SELECT * FROM quotations WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Quotation not found".to_string()))?;
+
+    if let Some(transaction_id) = quotation.transaction_id {
+        return Err(AppError::Business(format!(
+            "Quotation {} was already converted to transaction {}",
+            quotation.quotation_number, transaction_id
+        )));
+    }
+
+    match quotation.status.as_str() {
+        "draft" | "sent" if is_past_validity(&quotation) => {
+            return Err(AppError::Business(format!(
+                "Quotation {} expired on {}",
+                quotation.quotation_number, quotation.valid_until
+            )));
+        }
+        "draft" | "sent" | "accepted" => {}
+        _ => return Err(quotation_status_error(&quotation, "convert")),
+    }
+
+    let items = sqlx::query_as::<_, QuotationItem>(
+        "SELECT * FROM quotation_items WHERE quotation_id = ? ORDER BY id"
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let request = CreateTransactionRequest {
+        customer_id: quotation.customer_id,
+        transaction_type: "sale".to_string(),
+        transaction_date: transaction_date.unwrap_or_else(Utc::now),
+        due_date: None,
+        price_mode: Some(quotation.price_mode.clone()),
+        items: items
+            .into_iter()
+            .map(|item| CreateTransactionItemRequest {
+                product_id: item.product_id,
+                quantity: item.quantity,
+                unit_price: Some(item.unit_price),
+                tax_rate: item.tax_rate,
+                tax_category: Some(item.tax_category),
+                traceability_number: None,
+                origin: None,
+                slaughterhouse: None,
+                expiry_date: None,
+                notes: item.notes,
+            })
+            .collect(),
+        notes: Some(match &quotation.notes {
+            Some(notes) if !notes.trim().is_empty() => format!("견적서 {} - {}", quotation.quotation_number, notes),
+            _ => format!("견적서 {}", quotation.quotation_number),
+        }),
+    };
+
+    let transaction_id = transaction::insert_transaction(&mut tx, request).await?;
+
+    sqlx::query("UPDATE quotations SET status = 'accepted', transaction_id = ?, updated_at = ? WHERE id = ?")
+        .bind(transaction_id)
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    transaction::get_transaction_by_id(pool, transaction_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Created transaction not found".to_string()))
+}
+
+/// Marks draft and sent quotations whose validity date has passed as
+/// 'expired' and returns how many changed. main.rs runs it daily alongside
+/// the stock expiry check; safe to run repeatedly.
+pub async fn expire_quotations(pool: &DbPool) -> AppResult<i64> {
+    let today = Local::now().date_naive();
+
+    let result = sqlx::query(
+        r#"
+        UPDATE quotations SET status = 'expired', updated_at = ?
+        WHERE status IN ('draft', 'sent') AND valid_until < ?
+        "#
+    )
+    .bind(Utc::now())
+    .bind(today)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use serde_json::json;
+
+    /// A draft quote of 2 kg at 12,000 won, dated `days_ago` days back and valid `valid_for` days from today
+    async fn quote(pool: &DbPool, days_ago: i64, valid_for: i64) -> QuotationWithItems {
+        let buyer = fixtures::buyer(pool).await;
+        let beef = fixtures::beef(pool).await;
+        create_quotation(pool, quote_request(buyer.id, beef.id, days_ago, valid_for)).await.unwrap()
+    }
+
+    fn quote_request(customer_id: i64, product_id: i64, days_ago: i64, valid_for: i64) -> CreateQuotationRequest {
+        serde_json::from_value(json!({
+            "customer_id": customer_id,
+            "quotation_date": Utc::now() - chrono::Duration::days(days_ago),
+            "valid_until": Local::now().date_naive() + chrono::Duration::days(valid_for),
+            "items": [{ "product_id": product_id, "quantity": 2.0, "tax_rate": 0.1, "unit_price": 12000 }],
+            "notes": "설 선물세트",
+        })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn quotes_convert_once_into_a_draft_sale() {
+        let pool = test_pool().await;
+        let quote = quote(&pool, 0, 7).await;
+        assert_eq!(quote.quotation.status, "draft");
+        assert_eq!(quote.quotation.total_amount.as_won(), 26400);
+        update_quotation_status(&pool, quote.quotation.id, "sent".to_string()).await.unwrap();
+
+        let sale = convert_quotation_to_transaction(&pool, quote.quotation.id, None).await.unwrap();
+
+        assert_eq!(sale.transaction.status, "draft");
+        assert_eq!(sale.transaction.total_amount.as_won(), 26400);
+        assert_eq!(sale.items[0].item.unit_price.as_won(), 12000);
+        let notes = format!("견적서 {} - 설 선물세트", quote.quotation.quotation_number);
+        assert_eq!(sale.transaction.notes.as_deref(), Some(notes.as_str()));
+        let accepted = get_quotation_by_id(&pool, quote.quotation.id).await.unwrap().unwrap();
+        assert_eq!(accepted.quotation.status, "accepted");
+        assert_eq!(accepted.quotation.transaction_id, Some(sale.transaction.id));
+
+        let result = convert_quotation_to_transaction(&pool, quote.quotation.id, None).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|t| t.transaction.id));
+        let result = delete_quotation(&pool, quote.quotation.id).await;
+        assert!(matches!(result, Err(AppError::Business(_))));
+    }
+
+    #[tokio::test]
+    async fn expired_quotes_cannot_be_sent_or_converted() {
+        let pool = test_pool().await;
+        let quote = quote(&pool, 10, -1).await;
+
+        let result = update_quotation_status(&pool, quote.quotation.id, "sent".to_string()).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|q| q.quotation.status));
+        assert_eq!(expire_quotations(&pool).await.unwrap(), 1);
+        assert_eq!(expire_quotations(&pool).await.unwrap(), 0);
+        let result = convert_quotation_to_transaction(&pool, quote.quotation.id, None).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|t| t.transaction.id));
+
+        let request = quote_request(quote.quotation.customer_id, quote.items[0].item.product_id, 0, -1);
+        let result = create_quotation(&pool, request).await;
+        assert!(matches!(result, Err(AppError::Validation { .. })), "{:?}", result.map(|q| q.quotation.id));
+    }
+}
@@ -0,0 +1,378 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::{ReturnItemRequest, Transaction, TransactionItem, TransactionWithItems};
+use chrono::{DateTime, Utc};
+use sqlx::SqliteConnection;
+use std::collections::HashMap;
+use super::{balance, inventory, numbering, transaction};
+
+/// Quantities closer than this are treated as equal
+const QUANTITY_EPSILON: f64 = 0.0005;
+
+/// Returns part of a confirmed sale or purchase (반품).
+///
+/// Creates a confirmed transaction of the same type, linked through
+/// `return_of_transaction_id`, whose items carry the returned quantities as
+/// negative numbers at the original prices and tax rates, each linked to
+/// its original item. Because the totals are negative the customer's
+/// balance goes down by the returned amount. Stock comes back for a sale
+/// return and goes out for a purchase return (see
+/// `inventory::record_return_movements`).
+///
+/// An item cannot be returned beyond what was originally on it, counting
+/// earlier returns. Returns cannot be returned themselves.
+///
+/// Return items deliberately leave `sales_order_item_id` and
+/// `purchase_order_item_id` empty even when the original item delivered an
+/// order line. An order's delivered quantity sums the linked items, so a
+/// linked return would reopen the order as if the goods had never been
+/// delivered. Goods sent again to replace a return are a new transaction.
+pub async fn create_return(
+    pool: &DbPool,
+    transaction_id: i64,
+    items: Vec<ReturnItemRequest>,
+    return_date: Option<DateTime<Utc>>,
+    notes: Option<String>,
+) -> AppResult<TransactionWithItems> {
+    let now = Utc::now();
+
+    if items.is_empty() {
+        return Err(AppError::validation("Select at least one item to return".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Take the write lock first so two returns cannot both pass the quantity check
+    sqlx::query("UPDATE transactions SET status = status WHERE 0")
+        .execute(&mut *tx)
+        .await?;
+
+    let original = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = ?")
+        .bind(transaction_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+
+    if !matches!(original.transaction_type.as_str(), "sale" | "purchase") {
+        return Err(AppError::validation("Only sales and purchases can be returned".to_string()));
+    }
+
+    if original.status != "confirmed" {
+        return Err(AppError::Business("Only confirmed transactions can be returned".to_string()));
+    }
+
+    if original.return_of_transaction_id.is_some() {
+        return Err(AppError::Business("A return cannot be returned".to_string()));
+    }
+
+    let original_items: HashMap<i64, TransactionItem> = sqlx::query_as::<_, TransactionItem>(
+        "SELECT * FROM transaction_items WHERE transaction_id = ?"
+    )
+    .bind(transaction_id)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|item| (item.id, item))
+    .collect();
+
+    // Quantity requested per item, so an item listed twice is checked as a whole
+    let mut requested: HashMap<i64, f64> = HashMap::new();
+    for item in &items {
+        if !original_items.contains_key(&item.transaction_item_id) {
+            return Err(AppError::validation(format!(
+                "Item {} does not belong to transaction {}",
+                item.transaction_item_id, original.transaction_number
+            )));
+        }
+        if item.quantity <= 0.0 {
+            return Err(AppError::validation("Return quantity must be positive".to_string()));
+        }
+        *requested.entry(item.transaction_item_id).or_insert(0.0) += item.quantity;
+    }
+
+    for (item_id, quantity) in &requested {
+        let already_returned: (f64,) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(SUM(-ti.quantity), 0.0) FROM transaction_items ti
+            JOIN transactions t ON ti.transaction_id = t.id
+            WHERE ti.returned_item_id = ? AND t.status != 'cancelled'
+            "#
+        )
+        .bind(item_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let returnable = original_items[item_id].quantity - already_returned.0;
+        if *quantity > returnable + QUANTITY_EPSILON {
+            return Err(AppError::validation(format!(
+                "Item {} has {:.2} left to return but {:.2} was requested",
+                item_id,
+                returnable.max(0.0),
+                quantity
+            )));
+        }
+    }
+
+    let return_date = return_date.unwrap_or(now);
+    if return_date < original.transaction_date {
+        return Err(AppError::validation("Return date cannot be before the original transaction".to_string()));
+    }
+
+    let transaction_number = numbering::allocate_transaction_number(
+        &mut tx, &original.transaction_type, return_date
+    ).await?;
+
+    let notes = match notes {
+        Some(notes) if !notes.trim().is_empty() => notes,
+        _ => format!("{} 반품", original.transaction_number),
+    };
+
+    let return_id = sqlx::query(
+        r#"
+        INSERT INTO transactions (
+            transaction_number, customer_id, transaction_type, transaction_date,
+            price_mode, status, notes, return_of_transaction_id, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'confirmed', ?, ?, ?, ?)
+        "#
+    )
+    .bind(&transaction_number)
+    .bind(original.customer_id)
+    .bind(&original.transaction_type)
+    .bind(return_date)
+    .bind(&original.price_mode)
+    .bind(&notes)
+    .bind(original.id)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    // Amounts follow the original's price mode and the company's rounding
+    // policy, as on any sale or purchase
+    for item in &items {
+        let original_item = &original_items[&item.transaction_item_id];
+        sqlx::query(
+            r#"
+            INSERT INTO transaction_items (
+                transaction_id, product_id, quantity, unit_price,
+                tax_rate, tax_category, subtotal, tax_amount, total_amount,
+                traceability_number, traceability_warning, origin, slaughterhouse, notes,
+                returned_item_id, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, 0, 0, 0, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(return_id)
+        .bind(original_item.product_id)
+        .bind(-item.quantity)
+        .bind(original_item.unit_price)
+        .bind(original_item.tax_rate)
+        .bind(&original_item.tax_category)
+        .bind(&original_item.traceability_number)
+        .bind(&original_item.traceability_warning)
+        .bind(&original_item.origin)
+        .bind(&original_item.slaughterhouse)
+        .bind(&original_item.notes)
+        .bind(original_item.id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    transaction::recompute_totals(&mut tx, return_id).await?;
+    inventory::record_return_movements(&mut tx, return_id).await?;
+    balance::recompute_customer_balance(&mut tx, original.customer_id).await?;
+
+    tx.commit().await?;
+
+    transaction::get_transaction_by_id(pool, return_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Created return not found".to_string()))
+}
+
+/// Returns made against a transaction, oldest first
+pub async fn get_transaction_returns(pool: &DbPool, transaction_id: i64) -> AppResult<Vec<TransactionWithItems>> {
+    let ids: Vec<(i64,)> = sqlx::query_as(
+        "SELECT id FROM transactions WHERE return_of_transaction_id = ? ORDER BY transaction_date, id"
+    )
+    .bind(transaction_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut returns = Vec::with_capacity(ids.len());
+    for (id,) in ids {
+        if let Some(r) = transaction::get_transaction_by_id(pool, id).await? {
+            returns.push(r);
+        }
+    }
+
+    Ok(returns)
+}
+
+/// Fails if the transaction is a return or has returns made against it.
+/// Cancelling or deleting either side would undo stock and balance effects
+/// the other one already accounts for.
+pub async fn ensure_no_returns(conn: &mut SqliteConnection, transaction_id: i64, action: &str) -> AppResult<()> {
+    let linked: (Option<i64>, i64) = sqlx::query_as(
+        r#"
+        SELECT t.return_of_transaction_id,
+            (SELECT COUNT(*) FROM transactions r WHERE r.return_of_transaction_id = t.id)
+        FROM transactions t WHERE t.id = ?
+        "#
+    )
+    .bind(transaction_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .unwrap_or((None, 0));
+
+    if linked.0.is_some() {
+        return Err(AppError::Business(format!("Cannot {} a return", action)));
+    }
+    if linked.1 > 0 {
+        return Err(AppError::Business(format!("Cannot {} a transaction that has returns", action)));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use crate::models::{OrderItem, ReturnItemRequest};
+    use crate::services::orders;
+    use serde_json::json;
+
+    /// A confirmed sale of 3 kg of beef; returns the sale and its item id
+    async fn confirmed_sale(pool: &DbPool) -> (TransactionWithItems, i64) {
+        let buyer = fixtures::buyer(pool).await;
+        let beef = fixtures::beef(pool).await;
+        let sale = fixtures::confirmed_sale(pool, buyer.id, beef.id, 3.0).await;
+
+        let item_id = sale.items[0].item.id;
+        (sale, item_id)
+    }
+
+    async fn return_quantity(pool: &DbPool, sale: &TransactionWithItems, item_id: i64, quantity: f64) -> AppResult<TransactionWithItems> {
+        let items = vec![ReturnItemRequest { transaction_item_id: item_id, quantity }];
+        create_return(pool, sale.transaction.id, items, None, None).await
+    }
+
+    fn assert_validation(result: AppResult<TransactionWithItems>, expected: &str) {
+        match result {
+            Err(AppError::Validation { message, .. }) => assert_eq!(message, expected),
+            other => panic!("expected a validation error '{}', got {:?}", expected, other.map(|r| r.transaction.id)),
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_the_quantity_at_the_original_price() {
+        let pool = test_pool().await;
+        let (sale, item_id) = confirmed_sale(&pool).await;
+
+        let returned = return_quantity(&pool, &sale, item_id, 1.0).await.unwrap();
+
+        assert_eq!(returned.transaction.return_of_transaction_id, Some(sale.transaction.id));
+        assert_eq!(returned.transaction.status, "confirmed");
+        assert_eq!(returned.items.len(), 1);
+        let item = &returned.items[0].item;
+        assert_eq!(item.quantity, -1.0);
+        assert_eq!(item.unit_price, sale.items[0].item.unit_price);
+        assert_eq!(item.returned_item_id, Some(item_id));
+        assert_eq!(returned.transaction.total_amount.as_won(), -11000);
+    }
+
+    #[tokio::test]
+    async fn rejects_returning_more_than_was_sold() {
+        let pool = test_pool().await;
+        let (sale, item_id) = confirmed_sale(&pool).await;
+
+        assert_validation(
+            return_quantity(&pool, &sale, item_id, 3.5).await,
+            &format!("Item {} has 3.00 left to return but 3.50 was requested", item_id),
+        );
+
+        // The same item listed twice is checked as a whole
+        let items = vec![
+            ReturnItemRequest { transaction_item_id: item_id, quantity: 2.0 },
+            ReturnItemRequest { transaction_item_id: item_id, quantity: 2.0 },
+        ];
+        assert_validation(
+            create_return(&pool, sale.transaction.id, items, None, None).await,
+            &format!("Item {} has 3.00 left to return but 4.00 was requested", item_id),
+        );
+
+        assert!(get_transaction_returns(&pool, sale.transaction.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn counts_earlier_returns_against_the_quantity() {
+        let pool = test_pool().await;
+        let (sale, item_id) = confirmed_sale(&pool).await;
+
+        return_quantity(&pool, &sale, item_id, 2.0).await.unwrap();
+        assert_validation(
+            return_quantity(&pool, &sale, item_id, 2.0).await,
+            &format!("Item {} has 1.00 left to return but 2.00 was requested", item_id),
+        );
+
+        return_quantity(&pool, &sale, item_id, 1.0).await.unwrap();
+        assert_validation(
+            return_quantity(&pool, &sale, item_id, 0.5).await,
+            &format!("Item {} has 0.00 left to return but 0.50 was requested", item_id),
+        );
+
+        assert_eq!(get_transaction_returns(&pool, sale.transaction.id).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn rejects_returning_a_return() {
+        let pool = test_pool().await;
+        let (sale, item_id) = confirmed_sale(&pool).await;
+        let returned = return_quantity(&pool, &sale, item_id, 1.0).await.unwrap();
+
+        let result = return_quantity(&pool, &returned, returned.items[0].item.id, 1.0).await;
+
+        assert!(
+            matches!(&result, Err(AppError::Business(message)) if message == "A return cannot be returned"),
+            "{:?}",
+            result.map(|r| r.transaction.id)
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_do_not_reopen_the_order() {
+        let pool = test_pool().await;
+        let (sale, _) = confirmed_sale(&pool).await;
+        let beef_id = sale.items[0].item.product_id;
+
+        let order = orders::create_order(&pool, serde_json::from_value(json!({
+            "order_type": "sale",
+            "customer_id": sale.transaction.customer_id,
+            "order_date": "2026-03-02T01:00:00Z",
+            "items": [{ "product_id": beef_id, "quantity": 2.0, "tax_rate": 0.1 }],
+        })).unwrap()).await.unwrap();
+        let line_id = order.items[0].item.id;
+        let delivery = orders::fulfil_order(&pool, serde_json::from_value(json!({
+            "order_type": "sale",
+            "order_id": order.order.id,
+            "transaction_date": "2026-03-03T01:00:00Z",
+            "items": [{ "order_item_id": line_id, "quantity": 2.0 }],
+        })).unwrap()).await.unwrap();
+        transaction::confirm_transaction(&pool, delivery.transaction.id).await.unwrap();
+        assert_eq!(delivery.items[0].item.sales_order_item_id, Some(line_id));
+
+        let returned = return_quantity(&pool, &delivery, delivery.items[0].item.id, 1.0).await.unwrap();
+
+        assert_eq!(returned.items[0].item.sales_order_item_id, None);
+        let line = sqlx::query_as::<_, OrderItem>("SELECT * FROM sales_order_items WHERE id = ?")
+            .bind(line_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(line.delivered_quantity, 2.0);
+        let order = orders::get_order_by_id(&pool, "sale".to_string(), order.order.id).await.unwrap().unwrap();
+        assert_eq!(order.order.status, "fulfilled");
+    }
+}
@@ -0,0 +1,562 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::{AmendTaxInvoiceRequest, TaxInvoice};
+use crate::money::Money;
+use chrono::{DateTime, Local, Utc};
+use sqlx::{Row, SqliteConnection};
+use super::company;
+
+const INVOICE_STATUSES: [&str; 4] = ["issued", "sent", "received", "void"];
+const AMENDMENT_REASONS: [&str; 4] = ["return", "price_change", "cancellation", "duplicate"];
+
+/// 수정사유 code of an amendment reason, as used in the e-tax invoice XML
+pub fn amendment_status_code(reason: &str) -> Option<&'static str> {
+    match reason {
+        "price_change" => Some("02"), // 공급가액 변동
+        "return" => Some("03"),       // 환입
+        "cancellation" => Some("04"), // 계약의 해제
+        "duplicate" => Some("06"),    // 착오에 의한 이중발급
+        _ => None,
+    }
+}
+
+/// Next `TI-YYYYMMDD-NNNN` number for the local day of `issue_date`.
+/// Voided invoices keep their numbers, so a number is never handed out twice.
+async fn next_invoice_number(conn: &mut SqliteConnection, issue_date: DateTime<Utc>) -> AppResult<String> {
+    let prefix = format!("TI-{}-", issue_date.with_timezone(&Local).format("%Y%m%d"));
+
+    let numbers: Vec<(String,)> = sqlx::query_as("SELECT invoice_number FROM tax_invoices WHERE invoice_number LIKE ?")
+        .bind(format!("{}%", prefix))
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let last = numbers
+        .iter()
+        .filter_map(|(n,)| n.strip_prefix(&prefix).and_then(|seq| seq.parse::<i64>().ok()))
+        .max()
+        .unwrap_or(0);
+
+    Ok(format!("{}{:04}", prefix, last + 1))
+}
+
+/// Issues a tax invoice (세금계산서) for a confirmed sale.
+///
+/// The supplier (the registered company) and buyer (the customer) business
+/// numbers and names are copied onto the invoice so later edits to either do
+/// not change an issued document. The invoice is dated on the transaction
+/// date (작성일자) and carries the transaction's supply and tax amounts;
+/// exempt (면세) items are left off, as they go on a 계산서 instead. Taxable
+/// and zero-rated (영세율) items cannot share an invoice. A transaction can
+/// have only one invoice that is not void.
+pub async fn issue_tax_invoice(pool: &DbPool, transaction_id: i64) -> AppResult<TaxInvoice> {
+    let mut tx = pool.begin().await?;
+
+    // Take the write lock first so two issues cannot pick the same number
+    sqlx::query("UPDATE tax_invoices SET status = status WHERE 0")
+        .execute(&mut *tx)
+        .await?;
+
+    let transaction = sqlx::query(
+        r#"
+        SELECT t.transaction_type, t.status, t.transaction_date, t.subtotal, t.tax_amount,
+            t.return_of_transaction_id, c.name as buyer_name, c.business_number as buyer_business_number
+        FROM transactions t
+        JOIN customers c ON t.customer_id = c.id
+        WHERE t.id = ?
+        "#
+    )
+    .bind(transaction_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+
+    let transaction_type: String = transaction.get("transaction_type");
+    let status: String = transaction.get("status");
+    if transaction_type != "sale" || status != "confirmed" {
+        return Err(AppError::Business(
+            "Tax invoices can only be issued for confirmed sales".to_string()
+        ));
+    }
+
+    if transaction.get::<Option<i64>, _>("return_of_transaction_id").is_some() {
+        return Err(AppError::Business(
+            "Returns are invoiced by amending the original sale's invoice with reason 'return'".to_string()
+        ));
+    }
+
+    let categories: Vec<(String,)> = sqlx::query_as(
+        "SELECT DISTINCT tax_category FROM transaction_items WHERE transaction_id = ? AND tax_category != 'exempt'"
+    )
+    .bind(transaction_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if categories.is_empty() {
+        return Err(AppError::Business(
+            "All items are VAT-exempt; exempt sales are invoiced with a 계산서, not a tax invoice".to_string()
+        ));
+    }
+    if categories.len() > 1 {
+        return Err(AppError::Business(
+            "Taxable and zero-rated items need separate tax invoices; split them into separate sales".to_string()
+        ));
+    }
+
+    let existing: Option<(String,)> = sqlx::query_as(
+        "SELECT invoice_number FROM tax_invoices WHERE transaction_id = ? AND original_invoice_id IS NULL AND status != 'void'"
+    )
+    .bind(transaction_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if let Some((invoice_number,)) = existing {
+        return Err(AppError::Business(format!(
+            "Transaction already has tax invoice {}", invoice_number
+        )));
+    }
+
+    let buyer_business_number: Option<String> = transaction.get("buyer_business_number");
+    let buyer_business_number = buyer_business_number
+        .filter(|n| !n.trim().is_empty())
+        .ok_or_else(|| AppError::validation(
+            "Customer has no business registration number".to_string()
+        ))?;
+
+    let supplier: (String, String) = sqlx::query_as(
+        "SELECT business_number, name FROM companies ORDER BY created_at DESC LIMIT 1"
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::validation("Company information is not registered".to_string()))?;
+
+    let supply_amount: Money = transaction.get("subtotal");
+    let tax_amount: Money = transaction.get("tax_amount");
+    let issue_date: DateTime<Utc> = transaction.get("transaction_date");
+    let invoice_number = next_invoice_number(&mut tx, issue_date).await?;
+    let now = Utc::now();
+
+    let invoice = sqlx::query_as::<_, TaxInvoice>(
+        r#"
+        INSERT INTO tax_invoices (
+            transaction_id, invoice_number, issue_date,
+            supplier_business_number, supplier_name, buyer_business_number, buyer_name,
+            supply_amount, tax_amount, total_amount, status, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'issued', ?, ?)
+        RETURNING *
+        "#
+    )
+    .bind(transaction_id)
+    .bind(&invoice_number)
+    .bind(issue_date)
+    .bind(&supplier.0)
+    .bind(&supplier.1)
+    .bind(&buyer_business_number)
+    .bind(transaction.get::<String, _>("buyer_name"))
+    .bind(supply_amount)
+    .bind(tax_amount)
+    .bind(supply_amount + tax_amount)
+    .bind(now)
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(invoice)
+}
+
+/// Issues an amended tax invoice (수정세금계산서) linked to an original one.
+///
+/// The amendment carries the change only, and both rows are kept so the
+/// chain stays auditable:
+/// - 'return' (환입): `supply_amount` is the negative supply value returned
+/// - 'price_change' (공급가액 변동): `supply_amount` is the positive or negative difference
+/// - 'cancellation' (계약의 해제) and 'duplicate' (착오에 의한 이중발급): the
+///   remaining amount of the chain is reversed in full; no amounts are given
+///
+/// The chain's net supply value can never go below zero. The tax amount
+/// defaults to the original invoice's tax ratio, rounded by the company's
+/// VAT rounding mode (see `VatPolicy::prorate`), and the issue date to now.
+pub async fn amend_tax_invoice(pool: &DbPool, request: AmendTaxInvoiceRequest) -> AppResult<TaxInvoice> {
+    if !AMENDMENT_REASONS.contains(&request.reason.as_str()) {
+        return Err(AppError::validation(
+            "Amendment reason must be 'return', 'price_change', 'cancellation' or 'duplicate'".to_string()
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Take the write lock first; see issue_tax_invoice
+    sqlx::query("UPDATE tax_invoices SET status = status WHERE 0")
+        .execute(&mut *tx)
+        .await?;
+
+    let original = sqlx::query_as::<_, TaxInvoice>("SELECT * FROM tax_invoices WHERE id = ?")
+        .bind(request.original_invoice_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tax invoice not found".to_string()))?;
+
+    if original.original_invoice_id.is_some() {
+        return Err(AppError::Business(
+            "Amendments must reference the original tax invoice, not another amendment".to_string()
+        ));
+    }
+    if original.status == "void" {
+        return Err(AppError::Business("A void tax invoice cannot be amended".to_string()));
+    }
+
+    let net: (Money, Money) = sqlx::query_as(
+        r#"
+        SELECT COALESCE(SUM(supply_amount), 0), COALESCE(SUM(tax_amount), 0)
+        FROM tax_invoices
+        WHERE (id = ? OR original_invoice_id = ?) AND status != 'void'
+        "#
+    )
+    .bind(original.id)
+    .bind(original.id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let (supply_amount, tax_amount) = match request.reason.as_str() {
+        "cancellation" | "duplicate" => {
+            if request.supply_amount.is_some() || request.tax_amount.is_some() {
+                return Err(AppError::validation(
+                    "A cancellation or duplicate amendment reverses the remaining amount; do not give amounts".to_string()
+                ));
+            }
+            if net.0.is_zero() && net.1.is_zero() {
+                return Err(AppError::Business("Tax invoice has already been fully reversed".to_string()));
+            }
+            (-net.0, -net.1)
+        }
+        reason => {
+            let supply_amount = request.supply_amount.ok_or_else(|| AppError::validation(
+                "Supply amount is required for a return or price change amendment".to_string()
+            ))?;
+
+            if reason == "return" && !supply_amount.is_negative() {
+                return Err(AppError::validation(
+                    "A return amendment must have a negative supply amount".to_string()
+                ));
+            }
+            if supply_amount.is_zero() {
+                return Err(AppError::validation("Supply amount cannot be zero".to_string()));
+            }
+            if (net.0 + supply_amount).is_negative() {
+                return Err(AppError::Business(format!(
+                    "Amendment exceeds the remaining supply value of {}", net.0
+                )));
+            }
+
+            let tax_amount = match request.tax_amount {
+                Some(tax_amount) => tax_amount,
+                None if original.supply_amount.is_zero() => Money::ZERO,
+                None => company::vat_policy(&mut tx)
+                    .await?
+                    .prorate(supply_amount, original.tax_amount, original.supply_amount),
+            };
+            (supply_amount, tax_amount)
+        }
+    };
+
+    let now = Utc::now();
+    let issue_date = request.issue_date.unwrap_or(now);
+    let invoice_number = next_invoice_number(&mut tx, issue_date).await?;
+
+    let invoice = sqlx::query_as::<_, TaxInvoice>(
+        r#"
+        INSERT INTO tax_invoices (
+            transaction_id, invoice_number, issue_date,
+            supplier_business_number, supplier_name, buyer_business_number, buyer_name,
+            supply_amount, tax_amount, total_amount, status,
+            original_invoice_id, amendment_reason, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'issued', ?, ?, ?, ?)
+        RETURNING *
+        "#
+    )
+    .bind(original.transaction_id)
+    .bind(&invoice_number)
+    .bind(issue_date)
+    .bind(&original.supplier_business_number)
+    .bind(&original.supplier_name)
+    .bind(&original.buyer_business_number)
+    .bind(&original.buyer_name)
+    .bind(supply_amount)
+    .bind(tax_amount)
+    .bind(supply_amount + tax_amount)
+    .bind(original.id)
+    .bind(&request.reason)
+    .bind(now)
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(invoice)
+}
+
+/// The original invoice of `id`'s chain followed by its amendments, in issue order
+pub async fn get_tax_invoice_chain(pool: &DbPool, id: i64) -> AppResult<Vec<TaxInvoice>> {
+    let root: Option<(i64,)> = sqlx::query_as(
+        "SELECT COALESCE(original_invoice_id, id) FROM tax_invoices WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    let root = root.ok_or_else(|| AppError::NotFound("Tax invoice not found".to_string()))?.0;
+
+    let invoices = sqlx::query_as::<_, TaxInvoice>(
+        r#"
+        SELECT * FROM tax_invoices
+        WHERE id = ? OR original_invoice_id = ?
+        ORDER BY original_invoice_id IS NOT NULL, issue_date, id
+        "#
+    )
+    .bind(root)
+    .bind(root)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(invoices)
+}
+
+/// Tax invoices issued within the date range, optionally of one status, newest first
+pub async fn list_tax_invoices(
+    pool: &DbPool,
+    status: Option<String>,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+) -> AppResult<Vec<TaxInvoice>> {
+    if let Some(ref status) = status {
+        if !INVOICE_STATUSES.contains(&status.as_str()) {
+            return Err(AppError::validation(
+                "Status must be 'issued', 'sent', 'received' or 'void'".to_string()
+            ));
+        }
+    }
+
+    let invoices = sqlx::query_as::<_, TaxInvoice>(
+        r#"
+        SELECT * FROM tax_invoices
+        WHERE (? IS NULL OR status = ?)
+            AND (? IS NULL OR issue_date >= ?)
+            AND (? IS NULL OR issue_date <= ?)
+        ORDER BY issue_date DESC, id DESC
+        "#
+    )
+    .bind(&status)
+    .bind(&status)
+    .bind(start_date)
+    .bind(start_date)
+    .bind(end_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(invoices)
+}
+
+/// Voids an invoice that has not been sent yet. The row and its number are
+/// kept; the transaction can then be invoiced again or cancelled. An
+/// original invoice with amendments cannot be voided.
+pub async fn void_tax_invoice(pool: &DbPool, id: i64, reason: Option<String>) -> AppResult<TaxInvoice> {
+    let now = Utc::now();
+
+    let mut tx = pool.begin().await?;
+
+    // Take the write lock first so no amendment can be issued between the
+    // check and the update; see issue_tax_invoice
+    sqlx::query("UPDATE tax_invoices SET status = status WHERE 0")
+        .execute(&mut *tx)
+        .await?;
+
+    let amendment_count: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM tax_invoices WHERE original_invoice_id = ? AND status != 'void'"
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if amendment_count.0 > 0 {
+        return Err(AppError::Business(
+            "Tax invoice has amended invoices and cannot be voided".to_string()
+        ));
+    }
+
+    let invoice = sqlx::query_as::<_, TaxInvoice>(
+        r#"
+        UPDATE tax_invoices SET status = 'void', voided_at = ?, void_reason = ?, updated_at = ?
+        WHERE id = ? AND status = 'issued'
+        RETURNING *
+        "#
+    )
+    .bind(now)
+    .bind(&reason)
+    .bind(now)
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    match invoice {
+        Some(invoice) => {
+            tx.commit().await?;
+            Ok(invoice)
+        }
+        None => Err(invoice_status_error(&mut tx, id, "voided").await?),
+    }
+}
+
+/// Records that an issued invoice was sent to the buyer
+pub async fn mark_invoice_sent(pool: &DbPool, id: i64) -> AppResult<TaxInvoice> {
+    let now = Utc::now();
+
+    let invoice = sqlx::query_as::<_, TaxInvoice>(
+        r#"
+        UPDATE tax_invoices SET status = 'sent', sent_at = ?, updated_at = ?
+        WHERE id = ? AND status = 'issued'
+        RETURNING *
+        "#
+    )
+    .bind(now)
+    .bind(now)
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    match invoice {
+        Some(invoice) => Ok(invoice),
+        None => Err(invoice_status_error(&mut *pool.acquire().await?, id, "marked as sent").await?),
+    }
+}
+
+/// Explains why a status change of a non-'issued' (or missing) invoice was refused
+async fn invoice_status_error(conn: &mut SqliteConnection, id: i64, action: &str) -> AppResult<AppError> {
+    let status: Option<(String,)> = sqlx::query_as("SELECT status FROM tax_invoices WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    Ok(match status {
+        None => AppError::NotFound("Tax invoice not found".to_string()),
+        Some((status,)) => AppError::Business(format!(
+            "Tax invoice is {} and cannot be {}", status, action
+        )),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use crate::services::transaction;
+    use serde_json::json;
+
+    /// A confirmed sale of 2.5 kg: 25,000 won supply and 2,500 won VAT
+    async fn confirmed_sale(pool: &DbPool) -> i64 {
+        let buyer = fixtures::buyer(pool).await;
+        let beef = fixtures::beef(pool).await;
+        fixtures::confirmed_sale(pool, buyer.id, beef.id, 2.5).await.transaction.id
+    }
+
+    #[tokio::test]
+    async fn issues_one_invoice_per_sale_until_it_is_voided() {
+        let pool = test_pool().await;
+        fixtures::company(&pool).await;
+        let sale_id = confirmed_sale(&pool).await;
+
+        let invoice = issue_tax_invoice(&pool, sale_id).await.unwrap();
+
+        assert!(invoice.invoice_number.ends_with("-0001"), "{}", invoice.invoice_number);
+        assert_eq!(invoice.supplier_business_number, "105-87-12345");
+        assert_eq!(invoice.buyer_name, "맛있는식당");
+        assert_eq!((invoice.supply_amount.as_won(), invoice.tax_amount.as_won()), (25000, 2500));
+        assert_eq!(invoice.total_amount.as_won(), 27500);
+        let result = issue_tax_invoice(&pool, sale_id).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|i| i.invoice_number));
+
+        let voided = void_tax_invoice(&pool, invoice.id, Some("금액 오기".to_string())).await.unwrap();
+        assert_eq!(voided.status, "void");
+        assert_eq!(voided.void_reason.as_deref(), Some("금액 오기"));
+
+        // The void invoice keeps its number
+        let reissued = issue_tax_invoice(&pool, sale_id).await.unwrap();
+        assert!(reissued.invoice_number.ends_with("-0002"), "{}", reissued.invoice_number);
+    }
+
+    #[tokio::test]
+    async fn refuses_drafts_and_sent_invoices() {
+        let pool = test_pool().await;
+        fixtures::company(&pool).await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let draft = fixtures::draft(&pool, "sale", buyer.id, json!([
+            { "product_id": beef.id, "quantity": 1.0, "tax_rate": 0.1 },
+        ])).await;
+
+        let result = issue_tax_invoice(&pool, draft.transaction.id).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|i| i.invoice_number));
+
+        transaction::confirm_transaction(&pool, draft.transaction.id).await.unwrap();
+        let invoice = issue_tax_invoice(&pool, draft.transaction.id).await.unwrap();
+        mark_invoice_sent(&pool, invoice.id).await.unwrap();
+
+        let result = void_tax_invoice(&pool, invoice.id, None).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|i| i.status));
+        let result = void_tax_invoice(&pool, invoice.id + 1, None).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))), "{:?}", result.map(|i| i.status));
+    }
+
+    fn amendment(original_invoice_id: i64, reason: &str, supply_amount: Option<i64>) -> AmendTaxInvoiceRequest {
+        serde_json::from_value(json!({
+            "original_invoice_id": original_invoice_id,
+            "reason": reason,
+            "supply_amount": supply_amount,
+        })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn amendments_prorate_vat_and_reverse_the_rest() {
+        let pool = test_pool().await;
+        fixtures::company(&pool).await;
+        let sale_id = confirmed_sale(&pool).await;
+        let original = issue_tax_invoice(&pool, sale_id).await.unwrap();
+
+        let returned = amend_tax_invoice(&pool, amendment(original.id, "return", Some(-3336))).await.unwrap();
+        assert_eq!((returned.supply_amount.as_won(), returned.tax_amount.as_won()), (-3336, -334));
+        assert_eq!(returned.original_invoice_id, Some(original.id));
+        assert_eq!(amendment_status_code(returned.amendment_reason.as_deref().unwrap()), Some("03"));
+
+        let cancelled = amend_tax_invoice(&pool, amendment(original.id, "cancellation", None)).await.unwrap();
+        assert_eq!((cancelled.supply_amount.as_won(), cancelled.tax_amount.as_won()), (-21664, -2166));
+
+        let chain = get_tax_invoice_chain(&pool, cancelled.id).await.unwrap();
+        let ids: Vec<i64> = chain.iter().map(|i| i.id).collect();
+        assert_eq!(ids, vec![original.id, returned.id, cancelled.id]);
+        // Amended chains are settled by amendments, not by voiding the original
+        let result = void_tax_invoice(&pool, original.id, None).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|i| i.status));
+    }
+
+    #[tokio::test]
+    async fn amendments_cannot_take_the_chain_below_zero() {
+        let pool = test_pool().await;
+        fixtures::company(&pool).await;
+        let sale_id = confirmed_sale(&pool).await;
+        let original = issue_tax_invoice(&pool, sale_id).await.unwrap();
+
+        let result = amend_tax_invoice(&pool, amendment(original.id, "return", Some(-25001))).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|i| i.supply_amount));
+        let result = amend_tax_invoice(&pool, amendment(original.id, "return", Some(1000))).await;
+        assert!(matches!(result, Err(AppError::Validation { .. })), "{:?}", result.map(|i| i.supply_amount));
+        let result = amend_tax_invoice(&pool, amendment(original.id, "cancellation", Some(-1000))).await;
+        assert!(matches!(result, Err(AppError::Validation { .. })), "{:?}", result.map(|i| i.supply_amount));
+
+        let reversed = amend_tax_invoice(&pool, amendment(original.id, "duplicate", None)).await.unwrap();
+        let result = amend_tax_invoice(&pool, amendment(original.id, "cancellation", None)).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|i| i.supply_amount));
+        let result = amend_tax_invoice(&pool, amendment(reversed.id, "price_change", Some(1000))).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|i| i.supply_amount));
+    }
+}
@@ -0,0 +1,790 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::TaxInvoice;
+use crate::money::Money;
+use chrono::Local;
+use regex::Regex;
+use roxmltree::{Document, Node};
+use super::{company, tax_invoice, transaction};
+
+/// Layout of the elements the exporter writes, in XSD syntax. It is our own
+/// summary of the KEC e-tax invoice format (v3.0), not the official NTS schema.
+const TAX_INVOICE_LAYOUT: &str = include_str!("../../schemas/tax_invoice_layout.xsd");
+
+const XS_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+const KEC_NAMESPACE: &str = "urn:kr:or:kec:standard:Tax:ReusableAggregateBusinessInformationEntitySchemaModule:1:0";
+
+/// Indented XML text built element by element
+struct XmlWriter {
+    out: String,
+    depth: usize,
+}
+
+impl XmlWriter {
+    fn new() -> Self {
+        XmlWriter {
+            out: String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"),
+            depth: 0,
+        }
+    }
+
+    fn indent(&mut self) {
+        self.out.push_str(&"  ".repeat(self.depth));
+    }
+
+    fn open(&mut self, name: &str) {
+        self.indent();
+        self.out.push_str(&format!("<{}>\n", name));
+        self.depth += 1;
+    }
+
+    fn open_root(&mut self, name: &str, namespace: &str) {
+        self.out.push_str(&format!("<{} xmlns=\"{}\">\n", name, escape(namespace)));
+        self.depth += 1;
+    }
+
+    fn close(&mut self, name: &str) {
+        self.depth -= 1;
+        self.indent();
+        self.out.push_str(&format!("</{}>\n", name));
+    }
+
+    fn leaf(&mut self, name: &str, value: &str) {
+        self.indent();
+        self.out.push_str(&format!("<{}>{}</{}>\n", name, escape(value), name));
+    }
+
+    /// Writes the element only when there is a non-blank value
+    fn optional_leaf(&mut self, name: &str, value: Option<&str>) {
+        if let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) {
+            self.leaf(name, value);
+        }
+    }
+
+    fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Business registration number as the 10 digits the e-tax invoice format expects
+fn business_number_digits(value: &str) -> String {
+    value.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+/// Whole won
+fn amount(value: Money) -> String {
+    value.to_string()
+}
+
+/// Up to two decimal places, without trailing zeros
+fn decimal(value: f64) -> String {
+    let formatted = format!("{:.2}", value);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+struct PartyInfo<'a> {
+    business_number: &'a str,
+    name: &'a str,
+    business_type: Option<&'a str>,
+    business_item: Option<&'a str>,
+    representative: Option<&'a str>,
+    address: Option<&'a str>,
+    phone: Option<&'a str>,
+    email: Option<&'a str>,
+}
+
+fn write_party(xml: &mut XmlWriter, element: &str, party: &PartyInfo) {
+    xml.open(element);
+    xml.leaf("ID", &business_number_digits(party.business_number));
+    xml.optional_leaf("TypeCode", party.business_type);
+    xml.leaf("NameText", party.name);
+    xml.optional_leaf("ClassificationCode", party.business_item);
+    if let Some(representative) = party.representative.filter(|r| !r.trim().is_empty()) {
+        xml.open("SpecifiedPerson");
+        xml.leaf("NameText", representative);
+        xml.close("SpecifiedPerson");
+    }
+    if let Some(address) = party.address.filter(|a| !a.trim().is_empty()) {
+        xml.open("SpecifiedAddress");
+        xml.leaf("LineOneText", address);
+        xml.close("SpecifiedAddress");
+    }
+    if party.phone.is_some() || party.email.is_some() {
+        xml.open("DefinedContact");
+        xml.optional_leaf("TelephoneCommunication", party.phone);
+        xml.optional_leaf("URICommunication", party.email);
+        xml.close("DefinedContact");
+    }
+    xml.close(element);
+}
+
+/// Renders an issued tax invoice and its transaction items as an e-tax
+/// invoice XML document (KEC standard, as uploaded to Hometax) and checks it
+/// against the exporter's own layout (see `check_tax_invoice_layout`).
+///
+/// Business numbers and names come from the invoice's snapshot; the other
+/// party details (representative, address, 업태/종목, contact) are read from
+/// the current company and customer records.
+pub async fn render_tax_invoice_xml(pool: &DbPool, invoice_id: i64) -> AppResult<String> {
+    let invoice = sqlx::query_as::<_, TaxInvoice>("SELECT * FROM tax_invoices WHERE id = ?")
+        .bind(invoice_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tax invoice not found".to_string()))?;
+
+    if invoice.status == "void" {
+        return Err(AppError::Business("A void tax invoice cannot be exported".to_string()));
+    }
+
+    let transaction = transaction::get_transaction_by_id(pool, invoice.transaction_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+
+    let company = company::get_company(pool)
+        .await?
+        .ok_or_else(|| AppError::validation("Company information is not registered".to_string()))?;
+
+    let original_number: Option<(String,)> = match invoice.original_invoice_id {
+        Some(original_id) => sqlx::query_as("SELECT invoice_number FROM tax_invoices WHERE id = ?")
+            .bind(original_id)
+            .fetch_optional(pool)
+            .await?,
+        None => None,
+    };
+    let amendment_code = invoice.amendment_reason.as_deref().and_then(tax_invoice::amendment_status_code);
+
+    let customer = &transaction.customer;
+    let issue_date = invoice.issue_date.with_timezone(&Local).format("%Y%m%d").to_string();
+
+    let mut xml = XmlWriter::new();
+    xml.open_root("TaxInvoice", KEC_NAMESPACE);
+
+    xml.open("ExchangedDocument");
+    xml.leaf("IssueDateTime", &invoice.created_at.with_timezone(&Local).format("%Y%m%d%H%M%S").to_string());
+    xml.close("ExchangedDocument");
+
+    xml.open("TaxInvoiceDocument");
+    xml.leaf("IssueID", &invoice.invoice_number);
+    // 일반/영세율 세금계산서, 수정세금계산서
+    let zero_rated = transaction.items.iter().any(|line| line.item.tax_category == "zero_rated");
+    let type_code = match (invoice.original_invoice_id.is_some(), zero_rated) {
+        (false, false) => "0101",
+        (false, true) => "0102",
+        (true, false) => "0201",
+        (true, true) => "0202",
+    };
+    xml.leaf("TypeCode", type_code);
+    xml.optional_leaf("DescriptionText", company.default_invoice_memo.as_deref());
+    xml.leaf("IssueDateTime", &issue_date);
+    xml.optional_leaf("AmendmentStatusCode", amendment_code);
+    xml.leaf("PurposeCode", "02"); // 청구
+    xml.optional_leaf("OriginalIssueID", original_number.as_ref().map(|n| n.0.as_str()));
+    xml.close("TaxInvoiceDocument");
+
+    xml.open("TaxInvoiceTradeSettlement");
+    write_party(&mut xml, "InvoicerParty", &PartyInfo {
+        business_number: &invoice.supplier_business_number,
+        name: &invoice.supplier_name,
+        business_type: company.business_type.as_deref(),
+        business_item: company.business_item.as_deref(),
+        representative: Some(&company.representative),
+        address: Some(&company.address),
+        phone: company.phone.as_deref(),
+        email: company.email.as_deref(),
+    });
+    write_party(&mut xml, "InvoiceeParty", &PartyInfo {
+        business_number: &invoice.buyer_business_number,
+        name: &invoice.buyer_name,
+        business_type: customer.business_type.as_deref(),
+        business_item: customer.business_item.as_deref(),
+        representative: customer.representative.as_deref(),
+        address: customer.address.as_deref(),
+        phone: customer.phone.as_deref(),
+        email: customer.email.as_deref(),
+    });
+    xml.open("SpecifiedMonetarySummation");
+    xml.leaf("ChargeTotalAmount", &amount(invoice.supply_amount));
+    xml.leaf("TaxTotalAmount", &amount(invoice.tax_amount));
+    xml.leaf("GrandTotalAmount", &amount(invoice.total_amount));
+    xml.close("SpecifiedMonetarySummation");
+    xml.close("TaxInvoiceTradeSettlement");
+
+    // An amendment carries only the change, as one line against the original invoice
+    if let (Some(reason), Some((original_number,))) = (invoice.amendment_reason.as_deref(), &original_number) {
+        let label = match reason {
+            "return" => "환입",
+            "price_change" => "공급가액 변동",
+            "cancellation" => "계약의 해제",
+            _ => "착오에 의한 이중발급",
+        };
+
+        xml.open("TaxInvoiceTradeLineItem");
+        xml.leaf("SequenceNumeric", "1");
+        xml.leaf("PurchaseExpiryDateTime", &issue_date);
+        xml.leaf("NameText", label);
+        xml.leaf("DescriptionText", &format!("당초 {}", original_number));
+        xml.leaf("InvoiceAmount", &amount(invoice.supply_amount));
+        xml.open("TotalTax");
+        xml.leaf("CalculatedAmount", &amount(invoice.tax_amount));
+        xml.close("TotalTax");
+        xml.close("TaxInvoiceTradeLineItem");
+    } else {
+        // Exempt items are not part of a tax invoice
+        let lines = transaction.items.iter().filter(|line| line.item.tax_category != "exempt");
+        for (index, line) in lines.enumerate() {
+            let traceability = line.item.traceability_number.as_ref().map(|n| format!("이력번호 {}", n));
+
+            xml.open("TaxInvoiceTradeLineItem");
+            xml.leaf("SequenceNumeric", &(index + 1).to_string());
+            xml.leaf("PurchaseExpiryDateTime", &issue_date);
+            xml.leaf("NameText", &line.product.name);
+            xml.optional_leaf("InformationText", Some(&line.product.unit));
+            xml.optional_leaf("DescriptionText", traceability.as_deref().or(line.item.notes.as_deref()));
+            xml.leaf("ChargeableUnitQuantity", &decimal(line.item.quantity));
+            xml.open("UnitPrice");
+            xml.leaf("UnitAmount", &amount(line.item.unit_price));
+            xml.close("UnitPrice");
+            xml.leaf("InvoiceAmount", &amount(line.item.subtotal));
+            xml.open("TotalTax");
+            xml.leaf("CalculatedAmount", &amount(line.item.tax_amount));
+            xml.close("TotalTax");
+            xml.close("TaxInvoiceTradeLineItem");
+        }
+    }
+
+    xml.close("TaxInvoice");
+    let document = xml.finish();
+
+    if let Err(errors) = check_tax_invoice_layout(&document) {
+        return Err(AppError::validation(format!(
+            "Tax invoice {} does not match the exporter's e-tax invoice layout: {}",
+            invoice.invoice_number,
+            errors.join("; ")
+        )));
+    }
+
+    Ok(document)
+}
+
+/// Renders the invoice (see `render_tax_invoice_xml`) and writes it to `path`
+pub async fn export_tax_invoice_xml(pool: &DbPool, invoice_id: i64, path: &str) -> AppResult<()> {
+    if path.trim().is_empty() {
+        return Err(AppError::validation("Export path is required".to_string()));
+    }
+
+    let document = render_tax_invoice_xml(pool, invoice_id).await?;
+    tokio::fs::write(path, document).await?;
+    Ok(())
+}
+
+/// Checks that an e-tax invoice document follows the bundled layout, listing
+/// every problem found. This catches exporter mistakes only; it is not the
+/// official NTS schema and passing it does not mean Hometax will accept the file.
+pub fn check_tax_invoice_layout(xml: &str) -> Result<(), Vec<String>> {
+    check_against_layout(TAX_INVOICE_LAYOUT, xml)
+}
+
+fn check_against_layout(xsd: &str, xml: &str) -> Result<(), Vec<String>> {
+    let schema = Document::parse(xsd)
+        .map_err(|e| vec![format!("Bundled layout is invalid: {}", e)])?;
+    let document = Document::parse(xml)
+        .map_err(|e| vec![format!("Malformed XML: {}", e)])?;
+
+    let checker = LayoutChecker {
+        schema: schema.root_element(),
+        target_namespace: schema.root_element().attribute("targetNamespace"),
+    };
+
+    // A construct the checker would skip could let an invalid document through
+    let unsupported = checker.unsupported_constructs();
+    if !unsupported.is_empty() {
+        return Err(unsupported);
+    }
+
+    let root = document.root_element();
+    let mut errors = Vec::new();
+
+    match checker.top_level_element(root.tag_name().name()) {
+        Some(declaration) => checker.check_element(declaration, root, &format!("/{}", root.tag_name().name()), &mut errors),
+        None => errors.push(format!("Unexpected root element {}", root.tag_name().name())),
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Interprets the XSD constructs the bundled layout uses: named and inline
+/// complex types holding an `xs:sequence` of local elements (`name`, `type`,
+/// `minOccurs`, `maxOccurs`), and simple types restricting xs:string,
+/// xs:decimal, xs:integer or another simple type with pattern, length,
+/// minLength, maxLength and enumeration facets.
+///
+/// It is not a general XSD processor. Any other construct in the layout
+/// (xs:choice, xs:all, xs:group, xs:any, attribute declarations, element
+/// references, complex or simple content, xs:list, xs:union, range and digit
+/// facets, xs:import and xs:include) is reported by `unsupported_constructs`
+/// and fails the check instead of being skipped. Since the layout declares
+/// no attributes, attributes in the document are errors; namespace
+/// declarations are not attributes. Identity constraints, default and fixed
+/// values and `xsi:type` are not checked.
+struct LayoutChecker<'a, 'input> {
+    schema: Node<'a, 'input>,
+    target_namespace: Option<&'a str>,
+}
+
+enum TypeDefinition<'a, 'input> {
+    Complex(Node<'a, 'input>),
+    Simple(Node<'a, 'input>),
+    BuiltIn(&'a str),
+}
+
+fn is_xs(node: &Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().namespace() == Some(XS_NAMESPACE) && node.tag_name().name() == name
+}
+
+/// XSD children and attributes `LayoutChecker` understands for an XSD element
+fn supported_construct(name: &str) -> Option<(&'static [&'static str], &'static [&'static str])> {
+    const FACETS: &[&str] = &["pattern", "enumeration", "length", "minLength", "maxLength"];
+    match name {
+        "schema" => Some((&["element", "complexType", "simpleType"], &["targetNamespace", "elementFormDefault"])),
+        "element" => Some((&["complexType", "simpleType"], &["name", "type", "minOccurs", "maxOccurs"])),
+        "complexType" => Some((&["sequence"], &["name"])),
+        "sequence" => Some((&["element"], &[])),
+        "simpleType" => Some((&["restriction"], &["name"])),
+        "restriction" => Some((FACETS, &["base"])),
+        "pattern" | "enumeration" | "length" | "minLength" | "maxLength" => Some((&[], &["value"])),
+        _ => None,
+    }
+}
+
+fn occurs(declaration: Node, attribute: &str) -> usize {
+    match declaration.attribute(attribute) {
+        Some("unbounded") => usize::MAX,
+        Some(value) => value.parse().unwrap_or(1),
+        None => 1,
+    }
+}
+
+impl<'a, 'input> LayoutChecker<'a, 'input> {
+    /// Every construct of the layout outside what the checker interprets
+    fn unsupported_constructs(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.schema.attribute("elementFormDefault") != Some("qualified") {
+            problems.push("Schema must set elementFormDefault=\"qualified\"".to_string());
+        }
+        collect_unsupported(self.schema, &mut problems);
+        problems
+    }
+
+    fn top_level_element(&self, name: &str) -> Option<Node<'a, 'input>> {
+        self.schema
+            .children()
+            .find(|n| is_xs(n, "element") && n.attribute("name") == Some(name))
+    }
+
+    fn named_type(&self, kind: &str, name: &str) -> Option<Node<'a, 'input>> {
+        self.schema
+            .children()
+            .find(|n| is_xs(n, kind) && n.attribute("name") == Some(name))
+    }
+
+    /// Resolves a `type`/`base` reference like "xs:string" or "AmountType"
+    fn resolve_type(&self, context: Node<'a, 'input>, reference: &'a str) -> Option<TypeDefinition<'a, 'input>> {
+        let (prefix, local) = match reference.split_once(':') {
+            Some((prefix, local)) => (Some(prefix), local),
+            None => (None, reference),
+        };
+
+        if context.lookup_namespace_uri(prefix) == Some(XS_NAMESPACE) {
+            return Some(TypeDefinition::BuiltIn(local));
+        }
+
+        self.named_type("complexType", local)
+            .map(TypeDefinition::Complex)
+            .or_else(|| self.named_type("simpleType", local).map(TypeDefinition::Simple))
+    }
+
+    fn element_type(&self, declaration: Node<'a, 'input>) -> Option<TypeDefinition<'a, 'input>> {
+        match declaration.attribute("type") {
+            Some(reference) => self.resolve_type(declaration, reference),
+            None => declaration.children().find_map(|n| {
+                if is_xs(&n, "complexType") {
+                    Some(TypeDefinition::Complex(n))
+                } else if is_xs(&n, "simpleType") {
+                    Some(TypeDefinition::Simple(n))
+                } else {
+                    None
+                }
+            }),
+        }
+    }
+
+    fn check_element(&self, declaration: Node<'a, 'input>, node: Node, path: &str, errors: &mut Vec<String>) {
+        if node.tag_name().namespace() != self.target_namespace {
+            errors.push(format!("{}: element is not in namespace {}", path, self.target_namespace.unwrap_or("")));
+        }
+
+        for attribute in node.attributes() {
+            errors.push(format!("{}: unexpected attribute {}", path, attribute.name()));
+        }
+
+        match self.element_type(declaration) {
+            Some(TypeDefinition::Complex(complex_type)) => self.check_complex(complex_type, node, path, errors),
+            Some(simple) => {
+                if node.children().any(|c| c.is_element()) {
+                    errors.push(format!("{}: must not contain child elements", path));
+                    return;
+                }
+                let text = node.text().unwrap_or("");
+                self.check_simple(simple, text, path, errors);
+            }
+            None => errors.push(format!("{}: layout declares no usable type", path)),
+        }
+    }
+
+    fn check_complex(&self, complex_type: Node<'a, 'input>, node: Node, path: &str, errors: &mut Vec<String>) {
+        if node.children().any(|c| c.is_text() && !c.text().unwrap_or("").trim().is_empty()) {
+            errors.push(format!("{}: must not contain text", path));
+        }
+
+        let children: Vec<Node> = node.children().filter(|c| c.is_element()).collect();
+        let particles: Vec<Node<'a, 'input>> = complex_type
+            .children()
+            .find(|n| is_xs(n, "sequence"))
+            .map(|sequence| sequence.children().filter(|n| is_xs(n, "element")).collect())
+            .unwrap_or_default();
+
+        let mut index = 0;
+        for particle in particles {
+            let name = particle.attribute("name").unwrap_or("");
+            let min_occurs = occurs(particle, "minOccurs");
+            let max_occurs = occurs(particle, "maxOccurs");
+
+            let mut count = 0;
+            while index < children.len() && children[index].tag_name().name() == name && count < max_occurs {
+                count += 1;
+                let child_path = if max_occurs > 1 {
+                    format!("{}/{}[{}]", path, name, count)
+                } else {
+                    format!("{}/{}", path, name)
+                };
+                self.check_element(particle, children[index], &child_path, errors);
+                index += 1;
+            }
+
+            if count < min_occurs {
+                errors.push(format!("{}: missing required element {}", path, name));
+            }
+        }
+
+        for unexpected in &children[index..] {
+            errors.push(format!("{}: unexpected element {}", path, unexpected.tag_name().name()));
+        }
+    }
+
+    fn check_simple(&self, definition: TypeDefinition<'a, 'input>, text: &str, path: &str, errors: &mut Vec<String>) {
+        let simple_type = match definition {
+            TypeDefinition::BuiltIn(name) => return check_built_in(name, text, path, errors),
+            TypeDefinition::Simple(simple_type) => simple_type,
+            TypeDefinition::Complex(_) => {
+                errors.push(format!("{}: expected a simple type", path));
+                return;
+            }
+        };
+
+        let Some(restriction) = simple_type.children().find(|n| is_xs(n, "restriction")) else {
+            errors.push(format!("{}: only xs:restriction simple types are supported", path));
+            return;
+        };
+
+        // The base type's own facets apply as well
+        match restriction.attribute("base").and_then(|base| self.resolve_type(restriction, base)) {
+            Some(base) => self.check_simple(base, text, path, errors),
+            None => errors.push(format!("{}: unknown base type", path)),
+        }
+
+        let length = text.chars().count();
+        let mut patterns = Vec::new();
+        let mut enumeration = Vec::new();
+
+        for facet in restriction.children().filter(|n| n.is_element()) {
+            let value = facet.attribute("value").unwrap_or("");
+            let limit = value.parse::<usize>().ok();
+            match facet.tag_name().name() {
+                "pattern" => patterns.push(value),
+                "enumeration" => enumeration.push(value),
+                "length" if limit.is_some_and(|l| length != l) => {
+                    errors.push(format!("{}: '{}' must be {} characters", path, text, value));
+                }
+                "minLength" if limit.is_some_and(|l| length < l) => {
+                    errors.push(format!("{}: '{}' must be at least {} characters", path, text, value));
+                }
+                "maxLength" if limit.is_some_and(|l| length > l) => {
+                    errors.push(format!("{}: '{}' must be at most {} characters", path, text, value));
+                }
+                _ => {}
+            }
+        }
+
+        // Patterns of one restriction are alternatives and always match the whole value
+        if !patterns.is_empty() {
+            let mut matched = false;
+            for pattern in &patterns {
+                match Regex::new(&format!("^(?:{})$", pattern)) {
+                    Ok(regex) => matched |= regex.is_match(text),
+                    Err(e) => errors.push(format!("{}: layout pattern '{}' is invalid: {}", path, pattern, e)),
+                }
+            }
+            if !matched {
+                errors.push(format!("{}: '{}' does not match the required format", path, text));
+            }
+        }
+
+        if !enumeration.is_empty() && !enumeration.contains(&text) {
+            errors.push(format!("{}: '{}' must be one of {}", path, text, enumeration.join(", ")));
+        }
+    }
+}
+
+fn collect_unsupported(node: Node, problems: &mut Vec<String>) {
+    let name = node.tag_name().name();
+    let Some((children, attributes)) = supported_construct(name).filter(|_| is_xs(&node, name)) else {
+        problems.push(format!("Schema construct {} is not supported by the layout checker", name));
+        return;
+    };
+
+    for attribute in node.attributes() {
+        if !attributes.contains(&attribute.name()) {
+            problems.push(format!(
+                "Schema attribute {} on xs:{} is not supported by the layout checker", attribute.name(), name
+            ));
+        }
+    }
+
+    // Annotations only document the layout
+    for child in node.children().filter(|c| c.is_element() && !is_xs(c, "annotation")) {
+        let child_name = child.tag_name().name();
+        if is_xs(&child, child_name) && children.contains(&child_name) {
+            collect_unsupported(child, problems);
+        } else {
+            problems.push(format!(
+                "Schema construct {} in xs:{} is not supported by the layout checker", child_name, name
+            ));
+        }
+    }
+}
+
+fn check_built_in(name: &str, text: &str, path: &str, errors: &mut Vec<String>) {
+    let valid = match name {
+        "string" => true,
+        "decimal" => {
+            let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+            let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+            !(whole.is_empty() && fraction.is_empty())
+                && whole.chars().all(|c| c.is_ascii_digit())
+                && fraction.chars().all(|c| c.is_ascii_digit())
+        }
+        "integer" => {
+            let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+            !unsigned.is_empty() && unsigned.chars().all(|c| c.is_ascii_digit())
+        }
+        _ => {
+            errors.push(format!("{}: built-in type xs:{} is not supported", path, name));
+            return;
+        }
+    };
+
+    if !valid {
+        errors.push(format!("{}: '{}' is not a valid xs:{}", path, text, name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use serde_json::json;
+
+    /// What `render_tax_invoice_xml` writes for the invoice of `issued_invoice`
+    const VALID_INVOICE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TaxInvoice xmlns="urn:kr:or:kec:standard:Tax:ReusableAggregateBusinessInformationEntitySchemaModule:1:0">
+  <ExchangedDocument>
+    <IssueDateTime>20260302100000</IssueDateTime>
+  </ExchangedDocument>
+  <TaxInvoiceDocument>
+    <IssueID>TI-20260302-0001</IssueID>
+    <TypeCode>0101</TypeCode>
+    <IssueDateTime>20260302</IssueDateTime>
+    <PurposeCode>02</PurposeCode>
+  </TaxInvoiceDocument>
+  <TaxInvoiceTradeSettlement>
+    <InvoicerParty>
+      <ID>1058712345</ID>
+      <TypeCode>도소매</TypeCode>
+      <NameText>한우상회</NameText>
+      <ClassificationCode>정육</ClassificationCode>
+    </InvoicerParty>
+    <InvoiceeParty>
+      <ID>2345678901</ID>
+      <NameText>맛있는식당</NameText>
+    </InvoiceeParty>
+    <SpecifiedMonetarySummation>
+      <ChargeTotalAmount>25000</ChargeTotalAmount>
+      <TaxTotalAmount>2500</TaxTotalAmount>
+      <GrandTotalAmount>27500</GrandTotalAmount>
+    </SpecifiedMonetarySummation>
+  </TaxInvoiceTradeSettlement>
+  <TaxInvoiceTradeLineItem>
+    <SequenceNumeric>1</SequenceNumeric>
+    <PurchaseExpiryDateTime>20260302</PurchaseExpiryDateTime>
+    <NameText>한우 등심</NameText>
+    <InformationText>kg</InformationText>
+    <DescriptionText>이력번호 002012345678</DescriptionText>
+    <ChargeableUnitQuantity>2.5</ChargeableUnitQuantity>
+    <UnitPrice>
+      <UnitAmount>10000</UnitAmount>
+    </UnitPrice>
+    <InvoiceAmount>25000</InvoiceAmount>
+    <TotalTax>
+      <CalculatedAmount>2500</CalculatedAmount>
+    </TotalTax>
+  </TaxInvoiceTradeLineItem>
+</TaxInvoice>
+"#;
+
+    /// A one-element schema wrapping `body` in the target namespace
+    fn schema_with(body: &str) -> String {
+        format!(
+            r#"<xs:schema xmlns:xs="{}" xmlns="urn:test" targetNamespace="urn:test" elementFormDefault="qualified">{}</xs:schema>"#,
+            XS_NAMESPACE, body
+        )
+    }
+
+    fn assert_error(result: Result<(), Vec<String>>, expected: &str) {
+        match result {
+            Ok(()) => panic!("expected an error containing '{}'", expected),
+            Err(errors) => assert!(
+                errors.iter().any(|e| e.contains(expected)),
+                "no error contains '{}': {:?}", expected, errors
+            ),
+        }
+    }
+
+    /// Issues an invoice for a confirmed sale of 2.5 kg at 10,000 won/kg
+    async fn issued_invoice(pool: &DbPool) -> TaxInvoice {
+        fixtures::company(pool).await;
+        let buyer = fixtures::buyer(pool).await;
+        let beef = fixtures::beef(pool).await;
+
+        let sale = fixtures::draft(pool, "sale", buyer.id, json!([{
+            "product_id": beef.id,
+            "quantity": 2.5,
+            "tax_rate": 0.1,
+            "traceability_number": "002012345678",
+        }])).await;
+        transaction::confirm_transaction(pool, sale.transaction.id).await.unwrap();
+
+        tax_invoice::issue_tax_invoice(pool, sale.transaction.id).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn renders_an_issued_invoice_that_matches_the_layout() {
+        let pool = test_pool().await;
+        let invoice = issued_invoice(&pool).await;
+
+        let xml = render_tax_invoice_xml(&pool, invoice.id).await.unwrap();
+
+        assert_eq!(check_tax_invoice_layout(&xml), Ok(()));
+        assert!(xml.contains("<ID>1058712345</ID>"), "{}", xml);
+        assert!(xml.contains("<ID>2345678901</ID>"), "{}", xml);
+        assert!(xml.contains("<InvoiceAmount>25000</InvoiceAmount>"), "{}", xml);
+        assert!(xml.contains("<CalculatedAmount>2500</CalculatedAmount>"), "{}", xml);
+        assert!(xml.contains("<GrandTotalAmount>27500</GrandTotalAmount>"), "{}", xml);
+        assert!(xml.contains("<DescriptionText>이력번호 002012345678</DescriptionText>"), "{}", xml);
+    }
+
+    #[test]
+    fn fixture_matches_the_layout() {
+        assert_eq!(check_tax_invoice_layout(VALID_INVOICE), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_nine_digit_business_number() {
+        let xml = VALID_INVOICE.replace("<ID>2345678901</ID>", "<ID>234567890</ID>");
+        assert_error(
+            check_tax_invoice_layout(&xml),
+            "/TaxInvoice/TaxInvoiceTradeSettlement/InvoiceeParty/ID: '234567890' does not match the required format",
+        );
+    }
+
+    #[test]
+    fn rejects_a_missing_invoicee_party() {
+        let start = VALID_INVOICE.find("    <InvoiceeParty>").unwrap();
+        let end = VALID_INVOICE.find("    <SpecifiedMonetarySummation>").unwrap();
+        let xml = format!("{}{}", &VALID_INVOICE[..start], &VALID_INVOICE[end..]);
+        assert_error(
+            check_tax_invoice_layout(&xml),
+            "/TaxInvoice/TaxInvoiceTradeSettlement: missing required element InvoiceeParty",
+        );
+    }
+
+    #[test]
+    fn rejects_attributes_in_the_document() {
+        let xml = VALID_INVOICE.replace("<TaxInvoiceDocument>", "<TaxInvoiceDocument kind=\"test\">");
+        assert_error(check_tax_invoice_layout(&xml), "/TaxInvoice/TaxInvoiceDocument: unexpected attribute kind");
+    }
+
+    #[test]
+    fn rejects_layouts_with_unsupported_constructs() {
+        let cases = [
+            (
+                r#"<xs:element name="A"><xs:complexType><xs:choice><xs:element name="B" type="xs:string"/></xs:choice></xs:complexType></xs:element>"#,
+                "Schema construct choice in xs:complexType is not supported",
+            ),
+            (
+                r#"<xs:element name="A"><xs:complexType><xs:all><xs:element name="B" type="xs:string"/></xs:all></xs:complexType></xs:element>"#,
+                "Schema construct all in xs:complexType is not supported",
+            ),
+            (
+                r#"<xs:element name="A"><xs:complexType><xs:sequence/><xs:attribute name="b" type="xs:string"/></xs:complexType></xs:element>"#,
+                "Schema construct attribute in xs:complexType is not supported",
+            ),
+            (
+                r#"<xs:import namespace="urn:other" schemaLocation="other.xsd"/><xs:element name="A" type="xs:string"/>"#,
+                "Schema construct import in xs:schema is not supported",
+            ),
+            (
+                r#"<xs:element name="A"><xs:complexType><xs:sequence><xs:element ref="B"/></xs:sequence></xs:complexType></xs:element>"#,
+                "Schema attribute ref on xs:element is not supported",
+            ),
+            (
+                r#"<xs:element name="A"><xs:simpleType><xs:restriction base="xs:integer"><xs:minInclusive value="1"/></xs:restriction></xs:simpleType></xs:element>"#,
+                "Schema construct minInclusive in xs:restriction is not supported",
+            ),
+        ];
+        for (body, expected) in cases {
+            assert_error(check_against_layout(&schema_with(body), r#"<A xmlns="urn:test"/>"#), expected);
+        }
+    }
+
+    #[test]
+    fn accepts_annotations_in_the_layout() {
+        let schema = schema_with(
+            r#"<xs:annotation><xs:documentation>설명</xs:documentation></xs:annotation><xs:element name="A" type="xs:string"/>"#,
+        );
+        assert_eq!(check_against_layout(&schema, r#"<A xmlns="urn:test">x</A>"#), Ok(()));
+    }
+}
@@ -0,0 +1,305 @@
+use crate::database::DbPool;
+use crate::errors::{AppError, AppResult};
+use crate::models::StockLot;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::Row;
+use super::traceability;
+
+/// Quantities below this are treated as zero
+const QUANTITY_EPSILON: f64 = 0.0005;
+
+/// A confirmed purchase item that brought the traceability number in
+#[derive(Debug, serde::Serialize)]
+pub struct TracePurchase {
+    pub transaction_id: i64,
+    pub transaction_number: String,
+    pub transaction_date: DateTime<Utc>,
+    pub supplier_id: i64,
+    pub supplier_name: String,
+    pub transaction_item_id: i64,
+    pub product_id: i64,
+    pub product_name: String,
+    pub quantity: f64,
+    pub lot_number: Option<String>,
+    pub expiry_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct TraceLot {
+    #[serde(flatten)]
+    pub lot: StockLot,
+    pub product_name: String,
+}
+
+/// A confirmed sale item that shipped the traceability number, per lot drawn from
+#[derive(Debug, serde::Serialize)]
+pub struct TraceSale {
+    pub transaction_id: i64,
+    pub transaction_number: String,
+    pub transaction_date: DateTime<Utc>,
+    pub customer_id: i64,
+    pub customer_name: String,
+    pub customer_phone: Option<String>,
+    pub transaction_item_id: i64,
+    pub product_id: i64,
+    pub product_name: String,
+    pub quantity: f64,
+    pub lot_number: Option<String>, // None when the item was not drawn from a lot
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct TraceReport {
+    pub traceability_number: String,
+    pub purchases: Vec<TracePurchase>,
+    pub lots: Vec<TraceLot>,
+    pub sales: Vec<TraceSale>,
+    pub quantity_purchased: f64,
+    pub quantity_sold: f64,
+    /// Still on hand in active or expired lots
+    pub quantity_in_stock: f64,
+}
+
+/// Follows a traceability number (이력번호) from the supplier purchase that
+/// brought it in, through the lots holding it, to the customers it was sold to.
+///
+/// A lot belongs to the chain when it carries the number; a sale belongs when
+/// it drew from such a lot or its item was entered with the number. Only
+/// confirmed transactions are listed; lots are listed in every status.
+///
+/// Sales show what the customer still holds. Returned goods leave the lot
+/// draws of the sale (see `lots::return_to_lots`); items that drew from no
+/// lot are listed net of their returns, and fully returned ones not at all.
+pub async fn trace_by_traceability_number(pool: &DbPool, traceability_number: &str) -> AppResult<TraceReport> {
+    let traceability_number = &traceability::normalize(traceability_number);
+    if traceability_number.is_empty() {
+        return Err(AppError::validation("Traceability number is required".to_string()));
+    }
+
+    let purchase_rows = sqlx::query(
+        r#"
+        SELECT t.id as transaction_id, t.transaction_number, t.transaction_date,
+            c.id as supplier_id, c.name as supplier_name,
+            ti.id as transaction_item_id, ti.product_id, p.name as product_name, ti.quantity,
+            l.lot_number, COALESCE(l.expiry_date, ti.expiry_date) as expiry_date
+        FROM transaction_items ti
+        JOIN transactions t ON ti.transaction_id = t.id
+        JOIN customers c ON t.customer_id = c.id
+        JOIN products p ON ti.product_id = p.id
+        LEFT JOIN stock_lots l ON l.transaction_item_id = ti.id
+        WHERE t.transaction_type = 'purchase' AND t.status = 'confirmed'
+            AND (ti.traceability_number = ? OR l.traceability_number = ?)
+        ORDER BY t.transaction_date, ti.id
+        "#
+    )
+    .bind(traceability_number)
+    .bind(traceability_number)
+    .fetch_all(pool)
+    .await?;
+
+    let purchases: Vec<TracePurchase> = purchase_rows
+        .iter()
+        .map(|row| TracePurchase {
+            transaction_id: row.get("transaction_id"),
+            transaction_number: row.get("transaction_number"),
+            transaction_date: row.get("transaction_date"),
+            supplier_id: row.get("supplier_id"),
+            supplier_name: row.get("supplier_name"),
+            transaction_item_id: row.get("transaction_item_id"),
+            product_id: row.get("product_id"),
+            product_name: row.get("product_name"),
+            quantity: row.get("quantity"),
+            lot_number: row.get("lot_number"),
+            expiry_date: row.get("expiry_date"),
+        })
+        .collect();
+
+    let lot_rows = sqlx::query(
+        r#"
+        SELECT l.*, p.name as product_name
+        FROM stock_lots l
+        JOIN products p ON l.product_id = p.id
+        WHERE l.traceability_number = ?
+        ORDER BY l.created_at, l.id
+        "#
+    )
+    .bind(traceability_number)
+    .fetch_all(pool)
+    .await?;
+
+    let lots: Vec<TraceLot> = lot_rows
+        .iter()
+        .map(|row| TraceLot {
+            lot: StockLot {
+                id: row.get("id"),
+                product_id: row.get("product_id"),
+                lot_number: row.get("lot_number"),
+                initial_quantity: row.get("initial_quantity"),
+                remaining_quantity: row.get("remaining_quantity"),
+                expiry_date: row.get("expiry_date"),
+                traceability_number: row.get("traceability_number"),
+                origin: row.get("origin"),
+                slaughterhouse: row.get("slaughterhouse"),
+                supplier_id: row.get("supplier_id"),
+                status: row.get("status"),
+                created_at: row.get("created_at"),
+                transaction_item_id: row.get("transaction_item_id"),
+            },
+            product_name: row.get("product_name"),
+        })
+        .collect();
+
+    // Items drawn from a lot with the number, plus items entered with the
+    // number that drew from no lot (returns are netted into the latter, never
+    // listed themselves)
+    let sale_rows = sqlx::query(
+        r#"
+        SELECT t.id as transaction_id, t.transaction_number, t.transaction_date,
+            c.id as customer_id, c.name as customer_name, c.phone as customer_phone,
+            ti.id as transaction_item_id, ti.product_id, p.name as product_name,
+            sc.quantity, l.lot_number
+        FROM stock_lot_consumptions sc
+        JOIN stock_lots l ON sc.lot_id = l.id
+        JOIN transaction_items ti ON sc.transaction_item_id = ti.id
+        JOIN transactions t ON ti.transaction_id = t.id
+        JOIN customers c ON t.customer_id = c.id
+        JOIN products p ON ti.product_id = p.id
+        WHERE l.traceability_number = ? AND t.transaction_type = 'sale' AND t.status = 'confirmed'
+
+        UNION ALL
+
+        SELECT t.id, t.transaction_number, t.transaction_date,
+            c.id, c.name, c.phone,
+            ti.id, ti.product_id, p.name,
+            ti.quantity + COALESCE((
+                SELECT SUM(r.quantity) FROM transaction_items r
+                JOIN transactions rt ON r.transaction_id = rt.id
+                WHERE r.returned_item_id = ti.id AND rt.status = 'confirmed'
+            ), 0), NULL
+        FROM transaction_items ti
+        JOIN transactions t ON ti.transaction_id = t.id
+        JOIN customers c ON t.customer_id = c.id
+        JOIN products p ON ti.product_id = p.id
+        WHERE ti.traceability_number = ? AND t.transaction_type = 'sale' AND t.status = 'confirmed'
+            AND t.return_of_transaction_id IS NULL
+            AND NOT EXISTS (SELECT 1 FROM stock_lot_consumptions sc WHERE sc.transaction_item_id = ti.id)
+
+        ORDER BY 3, 7
+        "#
+    )
+    .bind(traceability_number)
+    .bind(traceability_number)
+    .fetch_all(pool)
+    .await?;
+
+    let sales: Vec<TraceSale> = sale_rows
+        .iter()
+        .map(|row| TraceSale {
+            transaction_id: row.get("transaction_id"),
+            transaction_number: row.get("transaction_number"),
+            transaction_date: row.get("transaction_date"),
+            customer_id: row.get("customer_id"),
+            customer_name: row.get("customer_name"),
+            customer_phone: row.get("customer_phone"),
+            transaction_item_id: row.get("transaction_item_id"),
+            product_id: row.get("product_id"),
+            product_name: row.get("product_name"),
+            quantity: row.get("quantity"),
+            lot_number: row.get("lot_number"),
+        })
+        .filter(|sale| sale.quantity > QUANTITY_EPSILON)
+        .collect();
+
+    let quantity_purchased = purchases.iter().map(|p| p.quantity).sum();
+    let quantity_sold = sales.iter().map(|s| s.quantity).sum();
+    let quantity_in_stock = lots
+        .iter()
+        .filter(|l| matches!(l.lot.status.as_str(), "active" | "expired"))
+        .map(|l| l.lot.remaining_quantity)
+        .sum();
+
+    Ok(TraceReport {
+        traceability_number: traceability_number.to_string(),
+        purchases,
+        lots,
+        sales,
+        quantity_purchased,
+        quantity_sold,
+        quantity_in_stock,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use crate::models::{ReturnItemRequest, TransactionWithItems};
+    use crate::services::{returns, transaction};
+    use chrono::Local;
+    use serde_json::json;
+
+    const NUMBER: &str = "002012345678";
+
+    async fn return_one(pool: &DbPool, sale: &TransactionWithItems) {
+        let items = vec![ReturnItemRequest { transaction_item_id: sale.items[0].item.id, quantity: 1.0 }];
+        returns::create_return(pool, sale.transaction.id, items, None, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn partial_returns_of_lot_draws_count_once() {
+        let pool = test_pool().await;
+        let farm = fixtures::supplier(&pool).await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::stocked_beef(&pool).await;
+        let purchase = fixtures::draft(&pool, "purchase", farm.id, json!([{
+            "product_id": beef.id,
+            "quantity": 5.0,
+            "tax_rate": 0.1,
+            "traceability_number": NUMBER,
+            "expiry_date": Local::now().date_naive() + chrono::Duration::days(10),
+        }])).await;
+        transaction::confirm_transaction(&pool, purchase.transaction.id).await.unwrap();
+        let sale = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 3.0).await;
+
+        return_one(&pool, &sale).await;
+        let report = trace_by_traceability_number(&pool, NUMBER).await.unwrap();
+
+        assert_eq!(report.quantity_purchased, 5.0);
+        assert_eq!(report.sales.len(), 1);
+        assert_eq!(report.sales[0].transaction_id, sale.transaction.id);
+        assert_eq!(report.quantity_sold, 2.0);
+        assert_eq!(report.quantity_in_stock, 3.0);
+    }
+
+    #[tokio::test]
+    async fn sales_without_lots_are_listed_net_of_returns() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let sale = fixtures::draft(&pool, "sale", buyer.id, json!([
+            { "product_id": beef.id, "quantity": 3.0, "tax_rate": 0.1, "traceability_number": NUMBER },
+        ])).await;
+        let sale = transaction::confirm_transaction(&pool, sale.transaction.id).await.unwrap();
+
+        return_one(&pool, &sale).await;
+        let report = trace_by_traceability_number(&pool, NUMBER).await.unwrap();
+        assert_eq!(report.sales.len(), 1);
+        assert_eq!(report.sales[0].lot_number, None);
+        assert_eq!(report.quantity_sold, 2.0);
+
+        return_one(&pool, &sale).await;
+        return_one(&pool, &sale).await;
+        let report = trace_by_traceability_number(&pool, NUMBER).await.unwrap();
+        assert!(report.sales.is_empty());
+        assert_eq!(report.quantity_sold, 0.0);
+    }
+
+    #[tokio::test]
+    async fn rejects_a_blank_number() {
+        let pool = test_pool().await;
+        assert!(matches!(
+            trace_by_traceability_number(&pool, " - ").await,
+            Err(AppError::Validation { .. })
+        ));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::errors::{AppError, AppResult};
+use sqlx::SqliteConnection;
+
+/// Kind of a Korean livestock traceability number (이력번호)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceabilityKind {
+    /// 소 개체식별번호: 12 digits, leading 0 (e.g. 002012345678)
+    Cattle,
+    /// 돼지 이력번호: 12 digits, leading 1
+    Pork,
+    /// 묶음번호: 'L' + species digit + YYMMDD + 4-digit business code + 3-digit serial
+    Bundle,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TraceabilityNumber {
+    pub number: String,
+    pub kind: TraceabilityKind,
+}
+
+/// Removes whitespace and hyphens and upper-cases the bundle prefix
+pub fn normalize(input: &str) -> String {
+    input
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Normalises and classifies a traceability number, or explains why it is malformed
+pub fn parse(input: &str) -> Result<TraceabilityNumber, String> {
+    let number = normalize(input);
+    let all_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    let kind = if let Some(rest) = number.strip_prefix('L') {
+        if rest.len() != 14 || !all_digits(rest) {
+            return Err(format!(
+                "Bundle traceability number '{}' must be 'L' followed by 14 digits", number
+            ));
+        }
+        TraceabilityKind::Bundle
+    } else if number.len() == 12 && all_digits(&number) {
+        match number.as_bytes()[0] {
+            b'0' => TraceabilityKind::Cattle,
+            b'1' => TraceabilityKind::Pork,
+            _ => {
+                return Err(format!(
+                    "Traceability number '{}' must start with 0 (cattle) or 1 (pork)", number
+                ))
+            }
+        }
+    } else {
+        return Err(format!(
+            "Traceability number '{}' must be 12 digits or 'L' followed by 14 digits", number
+        ));
+    };
+
+    Ok(TraceabilityNumber { number, kind })
+}
+
+/// A traceability number checked for writing, with the warning to store
+/// next to it when 'warn' mode let a malformed number through
+#[derive(Debug, Default, PartialEq)]
+pub struct CheckedTraceability {
+    pub number: Option<String>,
+    pub warning: Option<String>,
+}
+
+/// Validates a traceability number about to be written.
+///
+/// Returns the normalised number. A malformed number fails with
+/// `AppError::Validation`, unless the company's `traceability_validation`
+/// is 'warn', in which case the number is kept as entered (normalised) and
+/// the parse message comes back as `warning` for the caller to store in its
+/// `traceability_warning` column. `None` and blank values pass through
+/// unchanged.
+pub async fn check_traceability_number(
+    conn: &mut SqliteConnection,
+    value: Option<String>,
+) -> AppResult<CheckedTraceability> {
+    let value = match value {
+        Some(v) if !v.trim().is_empty() => v,
+        other => return Ok(CheckedTraceability { number: other, warning: None }),
+    };
+
+    match parse(&value) {
+        Ok(parsed) => Ok(CheckedTraceability { number: Some(parsed.number), warning: None }),
+        Err(message) => {
+            let mode: Option<(String,)> = sqlx::query_as(
+                "SELECT traceability_validation FROM companies ORDER BY created_at DESC LIMIT 1"
+            )
+            .fetch_optional(&mut *conn)
+            .await?;
+
+            if mode.map(|m| m.0).as_deref() == Some("warn") {
+                Ok(CheckedTraceability {
+                    number: Some(normalize(&value)),
+                    warning: Some(message),
+                })
+            } else {
+                Err(AppError::validation(message))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_strips_separators_and_uppercases() {
+        let cases = [
+            ("002012345678", "002012345678"),
+            ("0020-1234-5678", "002012345678"),
+            (" 1 1234 5678 901 ", "112345678901"),
+            ("l1-240115-1234-001", "L12401151234001"),
+            ("\tL12401151234001\n", "L12401151234001"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize(input), expected, "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn parse_classifies_valid_numbers() {
+        let cases = [
+            ("002012345678", "002012345678", TraceabilityKind::Cattle),
+            ("0020-1234-5678", "002012345678", TraceabilityKind::Cattle),
+            ("112345678901", "112345678901", TraceabilityKind::Pork),
+            ("1 1234 5678 901", "112345678901", TraceabilityKind::Pork),
+            ("L12401151234001", "L12401151234001", TraceabilityKind::Bundle),
+            ("l1-240115-1234-001", "L12401151234001", TraceabilityKind::Bundle),
+        ];
+        for (input, number, kind) in cases {
+            let parsed = parse(input).unwrap_or_else(|e| panic!("input {:?}: {}", input, e));
+            assert_eq!(parsed.number, number, "input {:?}", input);
+            assert_eq!(parsed.kind, kind, "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn parse_rejects_malformed_numbers() {
+        let cases = [
+            ("", "must be 12 digits"),
+            ("00201234567", "must be 12 digits"),
+            ("0020123456789", "must be 12 digits"),
+            ("00201234567A", "must be 12 digits"),
+            ("212345678901", "must start with 0 (cattle) or 1 (pork)"),
+            ("912345678901", "must start with 0 (cattle) or 1 (pork)"),
+            ("L1240115123400", "'L' followed by 14 digits"),
+            ("L124011512340012", "'L' followed by 14 digits"),
+            ("L1240115123400X", "'L' followed by 14 digits"),
+            ("L", "'L' followed by 14 digits"),
+        ];
+        for (input, message) in cases {
+            match parse(input) {
+                Ok(parsed) => panic!("input {:?} parsed as {:?}", input, parsed),
+                Err(e) => assert!(e.contains(message), "input {:?}: {}", input, e),
+            }
+        }
+    }
+}
@@ -2,11 +2,14 @@ use crate::database::DbPool;
 use crate::errors::{AppError, AppResult};
 use crate::models::{
     Transaction, TransactionItem, TransactionWithItems, TransactionItemWithProduct,
-    CreateTransactionRequest, UpdateTransactionRequest,
+    CreateTransactionRequest, CreateTransactionItemRequest, UpdateTransactionRequest,
+    UpdateTransactionItemsRequest,
     Customer, Product
 };
+use crate::money::Money;
 use chrono::Utc;
-use sqlx::Row;
+use sqlx::{Row, SqliteConnection};
+use super::{balance, company, inventory, numbering, orders, pricing, product, returns, traceability};
 
 pub async fn get_transactions(
     pool: &DbPool, 
@@ -104,10 +107,18 @@ async fn get_transaction_with_items(pool: &DbPool, id: i64) -> AppResult<Option<
         r#"
         SELECT 
             ti.id, ti.transaction_id, ti.product_id, ti.quantity, 
-            ti.unit_price, ti.tax_rate, ti.subtotal, ti.tax_amount, ti.total_amount,
+            ti.unit_price, ti.tax_rate, ti.tax_category, ti.subtotal, ti.tax_amount, ti.total_amount,
+            ti.traceability_number, ti.traceability_warning, ti.origin, ti.slaughterhouse, ti.expiry_date, ti.notes, ti.created_at,
+            ti.sales_order_item_id, ti.purchase_order_item_id, ti.returned_item_id,
             p.id as p_id, p.name as p_name, p.code as p_code, p.description as p_description,
             p.unit_price as p_unit_price, p.unit as p_unit, p.tax_rate as p_tax_rate,
-            p.category as p_category, p.is_active as p_is_active, 
+            p.tax_category as p_tax_category,
+            p.category as p_category, p.traceability_number as p_traceability_number,
+            p.traceability_warning as p_traceability_warning,
+            p.origin as p_origin, p.slaughterhouse as p_slaughterhouse,
+            p.use_inventory_management as p_use_inventory_management,
+            p.lot_consumption as p_lot_consumption, p.track_expiry as p_track_expiry,
+            p.is_active as p_is_active,
             p.created_at as p_created_at, p.updated_at as p_updated_at
         FROM transaction_items ti
         JOIN products p ON ti.product_id = p.id
@@ -128,9 +139,20 @@ async fn get_transaction_with_items(pool: &DbPool, id: i64) -> AppResult<Option<
             quantity: row.get("quantity"),
             unit_price: row.get("unit_price"),
             tax_rate: row.get("tax_rate"),
+            tax_category: row.get("tax_category"),
             subtotal: row.get("subtotal"),
             tax_amount: row.get("tax_amount"),
             total_amount: row.get("total_amount"),
+            traceability_number: row.get("traceability_number"),
+            traceability_warning: row.get("traceability_warning"),
+            origin: row.get("origin"),
+            slaughterhouse: row.get("slaughterhouse"),
+            expiry_date: row.get("expiry_date"),
+            notes: row.get("notes"),
+            created_at: row.get("created_at"),
+            sales_order_item_id: row.get("sales_order_item_id"),
+            purchase_order_item_id: row.get("purchase_order_item_id"),
+            returned_item_id: row.get("returned_item_id"),
         };
         
         let product = Product {
@@ -141,7 +163,15 @@ async fn get_transaction_with_items(pool: &DbPool, id: i64) -> AppResult<Option<
             unit_price: row.get("p_unit_price"),
             unit: row.get("p_unit"),
             tax_rate: row.get("p_tax_rate"),
+            tax_category: row.get("p_tax_category"),
             category: row.get("p_category"),
+            traceability_number: row.get("p_traceability_number"),
+            traceability_warning: row.get("p_traceability_warning"),
+            origin: row.get("p_origin"),
+            slaughterhouse: row.get("p_slaughterhouse"),
+            use_inventory_management: row.get("p_use_inventory_management"),
+            lot_consumption: row.get("p_lot_consumption"),
+            track_expiry: row.get("p_track_expiry"),
             is_active: row.get("p_is_active"),
             created_at: row.get("p_created_at"),
             updated_at: row.get("p_updated_at"),
@@ -157,20 +187,54 @@ async fn get_transaction_with_items(pool: &DbPool, id: i64) -> AppResult<Option<
     }))
 }
 
+/// Checks a price mode: 'exclusive' (VAT on top of unit prices) or
+/// 'inclusive' (unit prices already contain VAT)
+pub(crate) fn validate_price_mode(price_mode: &str) -> AppResult<()> {
+    if !matches!(price_mode, "exclusive" | "inclusive") {
+        return Err(AppError::invalid_field("price_mode",
+            "Price mode must be 'exclusive' or 'inclusive'".to_string()
+        ));
+    }
+    Ok(())
+}
+
 pub async fn create_transaction(pool: &DbPool, request: CreateTransactionRequest) -> AppResult<TransactionWithItems> {
+    let mut tx = pool.begin().await?;
+    let transaction_id = insert_transaction(&mut tx, request).await?;
+    tx.commit().await?;
+    
+    let transaction = get_transaction_with_items(pool, transaction_id).await?
+        .ok_or_else(|| AppError::NotFound("Created transaction not found".to_string()))?;
+    
+    Ok(transaction)
+}
+
+/// Validates and inserts a draft sale or purchase with its items on the
+/// caller's DB transaction, returning the new transaction's id. Shared by
+/// every path that creates transactions so they all get the same checks.
+pub(crate) async fn insert_transaction(conn: &mut SqliteConnection, mut request: CreateTransactionRequest) -> AppResult<i64> {
     let now = Utc::now();
     
+    // Take the write lock first; validating on a read lock and upgrading
+    // later fails with "database is locked" under concurrent writers
+    sqlx::query("UPDATE transactions SET status = status WHERE 0")
+        .execute(&mut *conn)
+        .await?;
+    
     // Validate transaction type
     if !matches!(request.transaction_type.as_str(), "sale" | "purchase") {
-        return Err(AppError::Validation(
+        return Err(AppError::invalid_field("transaction_type",
             "Transaction type must be 'sale' or 'purchase'".to_string()
         ));
     }
     
+    let price_mode = request.price_mode.take().unwrap_or_else(|| "exclusive".to_string());
+    validate_price_mode(&price_mode)?;
+    
     // Validate that customer exists
     let customer_exists = sqlx::query("SELECT id FROM customers WHERE id = ?")
         .bind(request.customer_id)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *conn)
         .await?;
     
     if customer_exists.is_none() {
@@ -179,120 +243,242 @@ pub async fn create_transaction(pool: &DbPool, request: CreateTransactionRequest
     
     // Validate items and calculate totals
     if request.items.is_empty() {
-        return Err(AppError::Validation("Transaction must have at least one item".to_string()));
+        return Err(AppError::invalid_field("items", "Transaction must have at least one item".to_string()));
     }
     
-    let mut total_amount = 0.0;
-    let mut tax_amount = 0.0;
+    // Resolved unit price and traceability warning of each item, in request order
+    let mut validated = Vec::with_capacity(request.items.len());
     
-    // Validate each item and calculate totals
-    for item in &request.items {
-        if item.quantity <= 0.0 {
-            return Err(AppError::Validation("Item quantity must be positive".to_string()));
-        }
-        
-        if item.unit_price < 0.0 {
-            return Err(AppError::Validation("Item unit price cannot be negative".to_string()));
-        }
-        
-        if item.tax_rate < 0.0 || item.tax_rate > 1.0 {
-            return Err(AppError::Validation("Tax rate must be between 0.0 and 1.0".to_string()));
-        }
-        
-        // Validate that product exists
-        let product_exists = sqlx::query("SELECT id FROM products WHERE id = ? AND is_active = true")
-            .bind(item.product_id)
-            .fetch_optional(pool)
-            .await?;
-        
-        if product_exists.is_none() {
-            return Err(AppError::NotFound("Product not found or inactive".to_string()));
-        }
-        
-        let subtotal = item.quantity * item.unit_price;
-        let item_tax_amount = subtotal * item.tax_rate;
-        let item_total = subtotal + item_tax_amount;
-        
-        total_amount += item_total;
-        tax_amount += item_tax_amount;
+    // Validate each item
+    for (index, item) in request.items.iter_mut().enumerate() {
+        let checked = validate_item(&mut *conn, request.customer_id, price_mode == "inclusive", item)
+            .await
+            .map_err(|e| e.within(&format!("items[{}]", index)))?;
+        validated.push(checked);
     }
     
-    // Start transaction
-    let mut tx = pool.begin().await?;
+    // Allocate the number first so it rolls back together with the insert
+    let transaction_number = numbering::allocate_transaction_number(
+        &mut *conn, &request.transaction_type, request.transaction_date
+    ).await?;
     
     // Create transaction
     let transaction_id = sqlx::query(
         r#"
         INSERT INTO transactions (
-            customer_id, transaction_type, transaction_date, 
-            total_amount, tax_amount, status, notes, created_at, updated_at
-        ) VALUES (?, ?, ?, ?, ?, 'draft', ?, ?, ?)
+            transaction_number, customer_id, transaction_type, transaction_date, due_date,
+            price_mode, status, notes, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, 'draft', ?, ?, ?)
         "#
     )
+    .bind(&transaction_number)
     .bind(request.customer_id)
     .bind(&request.transaction_type)
     .bind(request.transaction_date)
-    .bind(total_amount)
-    .bind(tax_amount)
+    .bind(request.due_date)
+    .bind(&price_mode)
     .bind(&request.notes)
     .bind(now)
     .bind(now)
-    .execute(&mut *tx)
+    .execute(&mut *conn)
     .await?
     .last_insert_rowid();
     
     // Create transaction items
-    for item in &request.items {
-        let subtotal = item.quantity * item.unit_price;
-        let item_tax_amount = subtotal * item.tax_rate;
-        let item_total = subtotal + item_tax_amount;
-        
-        sqlx::query(
-            r#"
-            INSERT INTO transaction_items (
-                transaction_id, product_id, quantity, unit_price, 
-                tax_rate, subtotal, tax_amount, total_amount
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-            "#
-        )
+    for (item, (unit_price, traceability_warning)) in request.items.iter().zip(validated) {
+        insert_item(&mut *conn, transaction_id, item, unit_price, traceability_warning).await?;
+    }
+    
+    recompute_totals(&mut *conn, transaction_id).await?;
+    balance::recompute_customer_balance(&mut *conn, request.customer_id).await?;
+    
+    Ok(transaction_id)
+}
+
+/// Recomputes every item's supply value, VAT and total from its quantity
+/// and unit price, following the transaction's price mode and the company's
+/// VAT rounding policy, and the header's taxable supply value, VAT, exempt
+/// amount and total as the sums of its items.
+pub(crate) async fn recompute_totals(conn: &mut SqliteConnection, transaction_id: i64) -> AppResult<()> {
+    let policy = company::vat_policy(&mut *conn).await?;
+    
+    let (price_mode,): (String,) = sqlx::query_as("SELECT price_mode FROM transactions WHERE id = ?")
         .bind(transaction_id)
-        .bind(item.product_id)
-        .bind(item.quantity)
-        .bind(item.unit_price)
-        .bind(item.tax_rate)
-        .bind(subtotal)
-        .bind(item_tax_amount)
-        .bind(item_total)
-        .execute(&mut *tx)
+        .fetch_one(&mut *conn)
         .await?;
+    
+    let items: Vec<(i64, f64, Money, f64, String)> = sqlx::query_as(
+        "SELECT id, quantity, unit_price, tax_rate, tax_category FROM transaction_items WHERE transaction_id = ? ORDER BY id"
+    )
+    .bind(transaction_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    
+    let lines: Vec<(Money, f64)> = items
+        .iter()
+        .map(|(_, quantity, unit_price, tax_rate, _)| (unit_price.times(*quantity), *tax_rate))
+        .collect();
+    let amounts = policy.split_lines(&lines, price_mode == "inclusive");
+    
+    let mut subtotal = Money::ZERO;
+    let mut tax_amount = Money::ZERO;
+    let mut exempt_amount = Money::ZERO;
+    
+    for ((item_id, _, _, _, tax_category), (supply, tax)) in items.iter().zip(&amounts) {
+        sqlx::query("UPDATE transaction_items SET subtotal = ?, tax_amount = ?, total_amount = ? WHERE id = ?")
+            .bind(supply)
+            .bind(tax)
+            .bind(*supply + *tax)
+            .bind(item_id)
+            .execute(&mut *conn)
+            .await?;
+        
+        if tax_category == "exempt" {
+            exempt_amount += *supply;
+        } else {
+            subtotal += *supply;
+            tax_amount += *tax;
+        }
     }
     
-    tx.commit().await?;
+    sqlx::query(
+        "UPDATE transactions SET subtotal = ?, tax_amount = ?, exempt_amount = ?, total_amount = ? WHERE id = ?"
+    )
+    .bind(subtotal)
+    .bind(tax_amount)
+    .bind(exempt_amount)
+    .bind(subtotal + tax_amount + exempt_amount)
+    .bind(transaction_id)
+    .execute(&mut *conn)
+    .await?;
     
-    let transaction = get_transaction_with_items(pool, transaction_id).await?
-        .ok_or_else(|| AppError::NotFound("Created transaction not found".to_string()))?;
+    Ok(())
+}
+
+/// Checks one item of a sale or purchase and normalises its traceability
+/// number and tax category, which defaults to the product's; zero-rated and
+/// exempt items get a tax rate of 0. Returns the unit price it gets: the
+/// requested one, else the customer's price book price (see
+/// `pricing::default_unit_price`), with VAT added when the transaction's
+/// prices include VAT, and the traceability warning to store on the item
+/// when 'warn' mode let a malformed number through.
+async fn validate_item(
+    conn: &mut SqliteConnection,
+    customer_id: i64,
+    prices_include_vat: bool,
+    item: &mut CreateTransactionItemRequest,
+) -> AppResult<(Money, Option<String>)> {
+    if item.quantity <= 0.0 {
+        return Err(AppError::invalid_field("quantity", "Item quantity must be positive".to_string()));
+    }
     
-    Ok(transaction)
+    if item.unit_price.is_some_and(|price| price.is_negative()) {
+        return Err(AppError::invalid_field("unit_price", "Item unit price cannot be negative".to_string()));
+    }
+    
+    if item.tax_rate < 0.0 || item.tax_rate > 1.0 {
+        return Err(AppError::invalid_field("tax_rate", "Tax rate must be between 0.0 and 1.0".to_string()));
+    }
+    
+    let traceability = traceability::check_traceability_number(
+        &mut *conn, item.traceability_number.take()
+    ).await?;
+    item.traceability_number = traceability.number;
+    
+    // Validate that product exists; its price book price is the default unit price
+    let default_price = pricing::default_unit_price(&mut *conn, customer_id, item.product_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Product not found or inactive".to_string()))?;
+    
+    let tax_category = match item.tax_category.take() {
+        Some(tax_category) => tax_category,
+        None => {
+            let (tax_category,): (String,) = sqlx::query_as("SELECT tax_category FROM products WHERE id = ?")
+                .bind(item.product_id)
+                .fetch_one(&mut *conn)
+                .await?;
+            tax_category
+        }
+    };
+    product::validate_tax_category(&tax_category)?;
+    if tax_category != "taxable" {
+        item.tax_rate = 0.0;
+    }
+    item.tax_category = Some(tax_category);
+    
+    // The price book holds VAT-exclusive prices
+    let default_price = if prices_include_vat { default_price.including_vat(item.tax_rate) } else { default_price };
+    
+    Ok((item.unit_price.unwrap_or(default_price), traceability.warning))
 }
 
+async fn insert_item(
+    conn: &mut SqliteConnection,
+    transaction_id: i64,
+    item: &CreateTransactionItemRequest,
+    unit_price: Money,
+    traceability_warning: Option<String>,
+) -> AppResult<i64> {
+    // Amounts are filled in by recompute_totals once all items are in
+    let item_id = sqlx::query(
+        r#"
+        INSERT INTO transaction_items (
+            transaction_id, product_id, quantity, unit_price, 
+            tax_rate, tax_category, subtotal, tax_amount, total_amount,
+            traceability_number, traceability_warning, origin, slaughterhouse, expiry_date,
+            notes, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, 0, 0, 0, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(transaction_id)
+    .bind(item.product_id)
+    .bind(item.quantity)
+    .bind(unit_price)
+    .bind(item.tax_rate)
+    .bind(item.tax_category.as_deref().unwrap_or("taxable"))
+    .bind(&item.traceability_number)
+    .bind(traceability_warning)
+    .bind(&item.origin)
+    .bind(&item.slaughterhouse)
+    .bind(item.expiry_date)
+    .bind(&item.notes)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+    
+    Ok(item_id)
+}
+
+/// Updates the header of a transaction. Drafts may change anything; confirmed
+/// transactions already carry stock, lot, price-book and balance effects under
+/// their customer, so they keep it, and once a tax invoice is issued for them
+/// their date is fixed as well. Checks run after taking the write lock.
 pub async fn update_transaction(pool: &DbPool, id: i64, request: UpdateTransactionRequest) -> AppResult<TransactionWithItems> {
     let now = Utc::now();
     
-    // Validate that transaction exists
-    let existing = sqlx::query("SELECT id FROM transactions WHERE id = ?")
-        .bind(id)
-        .fetch_optional(pool)
+    let mut tx = pool.begin().await?;
+    
+    // Take the write lock first, as in insert_transaction
+    sqlx::query("UPDATE transactions SET status = status WHERE 0")
+        .execute(&mut *tx)
         .await?;
     
-    if existing.is_none() {
-        return Err(AppError::NotFound("Transaction not found".to_string()));
-    }
+    let existing = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+    
+    let previous_customer_id = existing.customer_id;
+    let customer_changes = request.customer_id.is_some_and(|c| c != existing.customer_id);
+    let date_changes = request.transaction_date.is_some_and(|d| d != existing.transaction_date);
     
     // Validate customer if provided
     if let Some(customer_id) = request.customer_id {
         let customer_exists = sqlx::query("SELECT id FROM customers WHERE id = ?")
             .bind(customer_id)
-            .fetch_optional(pool)
+            .fetch_optional(&mut *tx)
             .await?;
         
         if customer_exists.is_none() {
@@ -300,15 +486,39 @@ pub async fn update_transaction(pool: &DbPool, id: i64, request: UpdateTransacti
         }
     }
     
-    // Validate status if provided
-    if let Some(ref status) = request.status {
-        if !matches!(status.as_str(), "draft" | "confirmed" | "cancelled") {
-            return Err(AppError::Validation(
-                "Status must be 'draft', 'confirmed', or 'cancelled'".to_string()
+    if customer_changes && existing.status != "draft" {
+        return Err(AppError::Business(format!(
+            "Only draft transactions can change their customer (this one is {}). Cancel it and re-enter instead.",
+            existing.status
+        )));
+    }
+    
+    if date_changes {
+        let (invoice_count,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM tax_invoices WHERE transaction_id = ? AND status != 'void'"
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        
+        if invoice_count > 0 {
+            return Err(AppError::Business(
+                "Cannot change the date of a transaction that has a tax invoice. Void the invoice first.".to_string()
             ));
         }
     }
     
+    // Changing the price mode changes the amounts, so only drafts allow it
+    if let Some(ref price_mode) = request.price_mode {
+        validate_price_mode(price_mode)?;
+        if existing.status != "draft" {
+            return Err(AppError::Business(format!(
+                "Only draft transactions can change their price mode (this one is {})",
+                existing.status
+            )));
+        }
+    }
+    
     // Build dynamic update query
     let mut query_parts = Vec::new();
     
@@ -318,15 +528,18 @@ pub async fn update_transaction(pool: &DbPool, id: i64, request: UpdateTransacti
     if request.transaction_date.is_some() {
         query_parts.push("transaction_date = ?");
     }
-    if request.status.is_some() {
-        query_parts.push("status = ?");
+    if request.due_date.is_some() {
+        query_parts.push("due_date = ?");
+    }
+    if request.price_mode.is_some() {
+        query_parts.push("price_mode = ?");
     }
     if request.notes.is_some() {
         query_parts.push("notes = ?");
     }
     
     if query_parts.is_empty() {
-        return Err(AppError::Validation("No fields to update".to_string()));
+        return Err(AppError::validation("No fields to update".to_string()));
     }
     
     query_parts.push("updated_at = ?");
@@ -345,8 +558,11 @@ pub async fn update_transaction(pool: &DbPool, id: i64, request: UpdateTransacti
     if let Some(transaction_date) = request.transaction_date {
         query = query.bind(transaction_date);
     }
-    if let Some(status) = &request.status {
-        query = query.bind(status);
+    if let Some(due_date) = request.due_date {
+        query = query.bind(due_date);
+    }
+    if let Some(price_mode) = &request.price_mode {
+        query = query.bind(price_mode);
     }
     if let Some(notes) = &request.notes {
         query = query.bind(notes);
@@ -354,7 +570,184 @@ pub async fn update_transaction(pool: &DbPool, id: i64, request: UpdateTransacti
     
     query = query.bind(now).bind(id);
     
-    query.execute(pool).await?;
+    query.execute(&mut *tx).await?;
+    
+    if request.price_mode.is_some() {
+        recompute_totals(&mut tx, id).await?;
+    }
+    
+    // Date or customer changes move the transaction within the running balances
+    balance::recompute_customer_balance(&mut tx, previous_customer_id).await?;
+    if let Some(customer_id) = request.customer_id {
+        if customer_id != previous_customer_id {
+            balance::recompute_customer_balance(&mut tx, customer_id).await?;
+        }
+    }
+    
+    tx.commit().await?;
+    
+    let transaction = get_transaction_with_items(pool, id).await?
+        .ok_or_else(|| AppError::NotFound("Updated transaction not found".to_string()))?;
+    
+    Ok(transaction)
+}
+
+/// Adds, updates and removes items of a draft transaction, then recomputes
+/// the header totals from the items, all in one DB transaction. Confirmed
+/// and cancelled transactions cannot be edited; cancel and re-enter instead.
+/// A transaction only reaches 'confirmed' or 'cancelled' through
+/// `confirm_transaction` / `cancel_transaction`, never back to 'draft'.
+pub async fn update_transaction_items(pool: &DbPool, id: i64, request: UpdateTransactionItemsRequest) -> AppResult<TransactionWithItems> {
+    let now = Utc::now();
+    
+    if request.add.is_empty() && request.update.is_empty() && request.remove.is_empty() {
+        return Err(AppError::validation("No item changes given".to_string()));
+    }
+    
+    let mut tx = pool.begin().await?;
+    
+    // Take the write lock first, as in insert_transaction
+    sqlx::query("UPDATE transactions SET status = status WHERE 0")
+        .execute(&mut *tx)
+        .await?;
+    
+    let transaction = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+    
+    if transaction.status != "draft" {
+        return Err(AppError::Business(format!(
+            "Only draft transactions can have their items edited (this one is {})",
+            transaction.status
+        )));
+    }
+    
+    // Drafts reopened by the old direct status update still carry the stock
+    // movements of their earlier confirmation; editing them would record those twice
+    let (movement_count,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM stock_movements WHERE transaction_id = ?"
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+    
+    if movement_count > 0 {
+        return Err(AppError::Business(
+            "This draft has stock movements from an earlier confirmation; cancel it and re-enter instead".to_string()
+        ));
+    }
+    
+    let existing_items = sqlx::query_as::<_, TransactionItem>(
+        "SELECT * FROM transaction_items WHERE transaction_id = ?"
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+    
+    for item_id in request.update.iter().map(|u| u.id).chain(request.remove.iter().copied()) {
+        if !existing_items.iter().any(|item| item.id == item_id) {
+            return Err(AppError::validation(format!(
+                "Item {} does not belong to transaction {}",
+                item_id, transaction.transaction_number
+            )));
+        }
+    }
+    
+    if request.update.iter().any(|u| request.remove.contains(&u.id)) {
+        return Err(AppError::validation("An item cannot be both updated and removed".to_string()));
+    }
+    
+    // Orders linked before the edit, so removed items give back their quantities
+    let mut linked_orders = orders::linked_orders(&mut tx, id).await?;
+    
+    for item_id in &request.remove {
+        sqlx::query("DELETE FROM transaction_items WHERE id = ?")
+            .bind(item_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    
+    for (index, update) in request.update.into_iter().enumerate() {
+        let current = existing_items.iter().find(|item| item.id == update.id)
+            .ok_or_else(|| AppError::NotFound("Transaction item not found".to_string()))?;
+        
+        let mut item = CreateTransactionItemRequest {
+            product_id: current.product_id,
+            quantity: update.quantity.unwrap_or(current.quantity),
+            unit_price: Some(update.unit_price.unwrap_or(current.unit_price)),
+            tax_rate: update.tax_rate.unwrap_or(current.tax_rate),
+            tax_category: update.tax_category.or_else(|| Some(current.tax_category.clone())),
+            traceability_number: update.traceability_number.or_else(|| current.traceability_number.clone()),
+            origin: update.origin.or_else(|| current.origin.clone()),
+            slaughterhouse: update.slaughterhouse.or_else(|| current.slaughterhouse.clone()),
+            expiry_date: update.expiry_date.or(current.expiry_date),
+            notes: update.notes.or_else(|| current.notes.clone()),
+        };
+        let (unit_price, traceability_warning) = validate_item(
+            &mut tx, transaction.customer_id, transaction.price_mode == "inclusive", &mut item
+        )
+        .await
+        .map_err(|e| e.within(&format!("update[{}]", index)))?;
+        
+        // Amounts follow in recompute_totals
+        sqlx::query(
+            r#"
+            UPDATE transaction_items SET
+                quantity = ?, unit_price = ?, tax_rate = ?, tax_category = ?,
+                traceability_number = ?, traceability_warning = ?, origin = ?, slaughterhouse = ?,
+                expiry_date = ?, notes = ?
+            WHERE id = ?
+            "#
+        )
+        .bind(item.quantity)
+        .bind(unit_price)
+        .bind(item.tax_rate)
+        .bind(&item.tax_category)
+        .bind(&item.traceability_number)
+        .bind(traceability_warning)
+        .bind(&item.origin)
+        .bind(&item.slaughterhouse)
+        .bind(item.expiry_date)
+        .bind(&item.notes)
+        .bind(update.id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    
+    for (index, mut item) in request.add.into_iter().enumerate() {
+        let (unit_price, traceability_warning) = validate_item(
+            &mut tx, transaction.customer_id, transaction.price_mode == "inclusive", &mut item
+        )
+        .await
+        .map_err(|e| e.within(&format!("add[{}]", index)))?;
+        insert_item(&mut tx, id, &item, unit_price, traceability_warning).await?;
+    }
+    
+    let item_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM transaction_items WHERE transaction_id = ?")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+    
+    if item_count.0 == 0 {
+        return Err(AppError::validation("Transaction must have at least one item".to_string()));
+    }
+    
+    recompute_totals(&mut tx, id).await?;
+    
+    sqlx::query("UPDATE transactions SET updated_at = ? WHERE id = ?")
+        .bind(now)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    
+    linked_orders.extend(orders::linked_orders(&mut tx, id).await?);
+    orders::recompute_orders(&mut tx, &linked_orders).await?;
+    orders::ensure_not_overdelivered(&mut tx, &linked_orders).await?;
+    balance::recompute_customer_balance(&mut tx, transaction.customer_id).await?;
+    
+    tx.commit().await?;
     
     let transaction = get_transaction_with_items(pool, id).await?
         .ok_or_else(|| AppError::NotFound("Updated transaction not found".to_string()))?;
@@ -362,13 +755,23 @@ pub async fn update_transaction(pool: &DbPool, id: i64, request: UpdateTransacti
     Ok(transaction)
 }
 
+/// Deletes a draft. Confirmed transactions have stock, lot and price-book
+/// effects, so they are cancelled instead (see `cancel_transaction`).
 pub async fn delete_transaction(pool: &DbPool, id: i64) -> AppResult<()> {
+    // Start transaction
+    let mut tx = pool.begin().await?;
+    
+    // Take the write lock first, as in insert_transaction
+    sqlx::query("UPDATE transactions SET status = status WHERE 0")
+        .execute(&mut *tx)
+        .await?;
+    
     // Check if transaction has tax invoices
     let invoice_count: (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM tax_invoices WHERE transaction_id = ?"
     )
     .bind(id)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
     
     if invoice_count.0 > 0 {
@@ -377,25 +780,44 @@ pub async fn delete_transaction(pool: &DbPool, id: i64) -> AppResult<()> {
         ));
     }
     
-    // Start transaction
-    let mut tx = pool.begin().await?;
+    let (customer_id, status): (i64, String) = sqlx::query_as("SELECT customer_id, status FROM transactions WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+    
+    if status != "draft" {
+        return Err(AppError::Business(format!(
+            "Only draft transactions can be deleted (this one is {}). Cancel the transaction instead.",
+            status
+        )));
+    }
     
-    // Delete transaction items first
+    returns::ensure_no_returns(&mut tx, id, "delete").await?;
+    
+    // Orders the items delivered against get their open quantities back
+    let linked_orders = orders::linked_orders(&mut tx, id).await?;
+    
+    // Delete transaction items and payment allocations first
     sqlx::query("DELETE FROM transaction_items WHERE transaction_id = ?")
         .bind(id)
         .execute(&mut *tx)
         .await?;
     
+    sqlx::query("DELETE FROM payment_allocations WHERE payment_id = ? OR transaction_id = ?")
+        .bind(id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    
     // Delete transaction
-    let rows_affected = sqlx::query("DELETE FROM transactions WHERE id = ?")
+    sqlx::query("DELETE FROM transactions WHERE id = ?")
         .bind(id)
         .execute(&mut *tx)
-        .await?
-        .rows_affected();
+        .await?;
     
-    if rows_affected == 0 {
-        return Err(AppError::NotFound("Transaction not found".to_string()));
-    }
+    balance::recompute_customer_balance(&mut tx, customer_id).await?;
+    orders::recompute_orders(&mut tx, &linked_orders).await?;
     
     tx.commit().await?;
     Ok(())
@@ -404,12 +826,14 @@ pub async fn delete_transaction(pool: &DbPool, id: i64) -> AppResult<()> {
 pub async fn confirm_transaction(pool: &DbPool, id: i64) -> AppResult<TransactionWithItems> {
     let now = Utc::now();
     
+    let mut tx = pool.begin().await?;
+    
     let rows_affected = sqlx::query(
         "UPDATE transactions SET status = 'confirmed', updated_at = ? WHERE id = ? AND status = 'draft'"
     )
     .bind(now)
     .bind(id)
-    .execute(pool)
+    .execute(&mut *tx)
     .await?
     .rows_affected();
     
@@ -419,6 +843,20 @@ pub async fn confirm_transaction(pool: &DbPool, id: i64) -> AppResult<Transactio
         ));
     }
     
+    let customer_id: (i64,) = sqlx::query_as("SELECT customer_id FROM transactions WHERE id = ?")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+    
+    balance::recompute_customer_balance(&mut tx, customer_id.0).await?;
+    
+    // Prices confirmed here become the customer's defaults
+    pricing::record_transaction_prices(&mut tx, id).await?;
+    
+    inventory::record_transaction_movements(&mut tx, id).await?;
+    
+    tx.commit().await?;
+    
     let transaction = get_transaction_with_items(pool, id).await?
         .ok_or_else(|| AppError::NotFound("Confirmed transaction not found".to_string()))?;
     
@@ -428,12 +866,14 @@ pub async fn confirm_transaction(pool: &DbPool, id: i64) -> AppResult<Transactio
 pub async fn cancel_transaction(pool: &DbPool, id: i64) -> AppResult<TransactionWithItems> {
     let now = Utc::now();
     
+    let mut tx = pool.begin().await?;
+    
     let rows_affected = sqlx::query(
         "UPDATE transactions SET status = 'cancelled', updated_at = ? WHERE id = ? AND status IN ('draft', 'confirmed')"
     )
     .bind(now)
     .bind(id)
-    .execute(pool)
+    .execute(&mut *tx)
     .await?
     .rows_affected();
     
@@ -443,6 +883,37 @@ pub async fn cancel_transaction(pool: &DbPool, id: i64) -> AppResult<Transaction
         ));
     }
     
+    returns::ensure_no_returns(&mut tx, id, "cancel").await?;
+    
+    // Invoices reversed in full by amendments no longer block cancelling
+    let invoiced: (Money,) = sqlx::query_as(
+        "SELECT COALESCE(SUM(total_amount), 0) FROM tax_invoices WHERE transaction_id = ? AND status != 'void'"
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+    
+    if !invoiced.0.is_zero() {
+        return Err(AppError::Business(
+            "Cannot cancel transaction that has a tax invoice. Void the invoice or issue a cancellation amendment first.".to_string()
+        ));
+    }
+    
+    let customer_id: (i64,) = sqlx::query_as("SELECT customer_id FROM transactions WHERE id = ?")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+    
+    balance::recompute_customer_balance(&mut tx, customer_id.0).await?;
+    
+    // A confirmed transaction's stock movements are reversed; a draft has none
+    inventory::reverse_transaction_movements(&mut tx, id).await?;
+    
+    let linked_orders = orders::linked_orders(&mut tx, id).await?;
+    orders::recompute_orders(&mut tx, &linked_orders).await?;
+    
+    tx.commit().await?;
+    
     let transaction = get_transaction_with_items(pool, id).await?
         .ok_or_else(|| AppError::NotFound("Cancelled transaction not found".to_string()))?;
     
@@ -452,8 +923,10 @@ pub async fn cancel_transaction(pool: &DbPool, id: i64) -> AppResult<Transaction
 #[derive(Debug, serde::Serialize)]
 pub struct TransactionSummary {
     pub count: i64,
-    pub total_amount: f64,
-    pub tax_amount: f64,
+    pub subtotal: Money,      // taxable and zero-rated supply value
+    pub tax_amount: Money,
+    pub exempt_amount: Money,
+    pub total_amount: Money,
 }
 
 pub async fn get_transaction_summary(pool: &DbPool, transaction_type: Option<String>, start_date: Option<chrono::DateTime<Utc>>, end_date: Option<chrono::DateTime<Utc>>) -> AppResult<TransactionSummary> {
@@ -475,8 +948,10 @@ pub async fn get_transaction_summary(pool: &DbPool, transaction_type: Option<Str
         r#"
         SELECT 
             COUNT(*) as count,
-            COALESCE(SUM(total_amount), 0) as total_amount,
-            COALESCE(SUM(tax_amount), 0) as tax_amount
+            COALESCE(SUM(subtotal), 0) as subtotal,
+            COALESCE(SUM(tax_amount), 0) as tax_amount,
+            COALESCE(SUM(exempt_amount), 0) as exempt_amount,
+            COALESCE(SUM(total_amount), 0) as total_amount
         FROM transactions
         {}
         "#,
@@ -499,7 +974,99 @@ pub async fn get_transaction_summary(pool: &DbPool, transaction_type: Option<Str
     
     Ok(TransactionSummary {
         count: row.get("count"),
-        total_amount: row.get("total_amount"),
+        subtotal: row.get("subtotal"),
         tax_amount: row.get("tax_amount"),
+        exempt_amount: row.get("exempt_amount"),
+        total_amount: row.get("total_amount"),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::fixtures;
+    use crate::services::tax_invoice;
+    use serde_json::{json, Value};
+
+    async fn edit_items(pool: &DbPool, id: i64, changes: Value) -> AppResult<TransactionWithItems> {
+        update_transaction_items(pool, id, serde_json::from_value(changes).unwrap()).await
+    }
+
+    async fn edit_header(pool: &DbPool, id: i64, changes: Value) -> AppResult<TransactionWithItems> {
+        update_transaction(pool, id, serde_json::from_value(changes).unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn item_edits_recompute_the_draft_totals() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let sale = fixtures::draft(&pool, "sale", buyer.id, json!([
+            { "product_id": beef.id, "quantity": 1.0, "tax_rate": 0.1 },
+        ])).await;
+        let first = sale.items[0].item.id;
+
+        let edited = edit_items(&pool, sale.transaction.id, json!({
+            "update": [{ "id": first, "quantity": 2.0 }],
+            "add": [{ "product_id": beef.id, "quantity": 1.0, "tax_rate": 0.1, "unit_price": 8000 }],
+        })).await.unwrap();
+        assert_eq!(edited.items.len(), 2);
+        assert_eq!(edited.transaction.total_amount.as_won(), 30800);
+
+        let edited = edit_items(&pool, sale.transaction.id, json!({ "remove": [first] })).await.unwrap();
+        assert_eq!(edited.items.len(), 1);
+        assert_eq!(edited.transaction.total_amount.as_won(), 8800);
+    }
+
+    #[tokio::test]
+    async fn item_edits_are_refused_outside_drafts_and_for_foreign_items() {
+        let pool = test_pool().await;
+        let buyer = fixtures::buyer(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let draft = fixtures::draft(&pool, "sale", buyer.id, json!([
+            { "product_id": beef.id, "quantity": 1.0, "tax_rate": 0.1 },
+        ])).await;
+        let sale = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 1.0).await;
+
+        let result = edit_items(&pool, sale.transaction.id, json!({
+            "update": [{ "id": sale.items[0].item.id, "quantity": 5.0 }],
+        })).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|t| t.items));
+
+        let result = edit_items(&pool, draft.transaction.id, json!({ "remove": [sale.items[0].item.id] })).await;
+        assert!(matches!(result, Err(AppError::Validation { .. })), "{:?}", result.map(|t| t.items));
+
+        let result = edit_items(&pool, draft.transaction.id, json!({ "remove": [draft.items[0].item.id] })).await;
+        assert!(matches!(result, Err(AppError::Validation { .. })), "{:?}", result.map(|t| t.items));
+        let kept = get_transaction_with_items(&pool, draft.transaction.id).await.unwrap().unwrap();
+        assert_eq!(kept.items.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn confirmed_transactions_keep_their_customer_and_invoiced_date() {
+        let pool = test_pool().await;
+        fixtures::company(&pool).await;
+        let buyer = fixtures::buyer(&pool).await;
+        let farm = fixtures::supplier(&pool).await;
+        let beef = fixtures::beef(&pool).await;
+        let sale = fixtures::confirmed_sale(&pool, buyer.id, beef.id, 1.0).await;
+
+        let result = edit_header(&pool, sale.transaction.id, json!({ "customer_id": farm.id })).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|t| t.transaction));
+
+        // Before an invoice is issued the date can still move
+        edit_header(&pool, sale.transaction.id, json!({ "transaction_date": "2026-03-03T01:00:00Z" }))
+            .await
+            .unwrap();
+        tax_invoice::issue_tax_invoice(&pool, sale.transaction.id).await.unwrap();
+
+        let result = edit_header(&pool, sale.transaction.id, json!({ "transaction_date": "2026-03-04T01:00:00Z" })).await;
+        assert!(matches!(result, Err(AppError::Business(_))), "{:?}", result.map(|t| t.transaction));
+        let kept = get_transaction_with_items(&pool, sale.transaction.id).await.unwrap().unwrap();
+        assert_eq!(kept.transaction.customer_id, buyer.id);
+        assert_eq!(kept.transaction.transaction_date.to_rfc3339(), "2026-03-03T01:00:00+00:00");
+
+        edit_header(&pool, sale.transaction.id, json!({ "notes": "택배 발송" })).await.unwrap();
+    }
+}
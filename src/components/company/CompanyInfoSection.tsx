@@ -37,7 +37,8 @@ export default function CompanyInfoSection({
     business_item: '',  // 🆕 종목 추가
     tax_invoice_api_key: '',
     tax_invoice_cert_file: '',
-    default_invoice_memo: ''  // 🆕 기본 메모 추가
+    default_invoice_memo: '',  // 🆕 기본 메모 추가
+    document_font_path: ''
   })
 
   // 도장 이미지 불러오기
@@ -63,7 +64,8 @@ export default function CompanyInfoSection({
         business_item: company.business_item || '',  // 🆕 종목 추가
         tax_invoice_api_key: company.tax_invoice_api_key || '',
         tax_invoice_cert_file: company.tax_invoice_cert_file || '',
-        default_invoice_memo: company.default_invoice_memo || ''  // 🆕 기본 메모 추가
+        default_invoice_memo: company.default_invoice_memo || '',  // 🆕 기본 메모 추가
+        document_font_path: company.document_font_path || ''
       })
     }
   }, [company])
@@ -155,7 +157,8 @@ export default function CompanyInfoSection({
         business_item: company.business_item || '',  // 🆕 종목 추가
         tax_invoice_api_key: company.tax_invoice_api_key || '',
         tax_invoice_cert_file: company.tax_invoice_cert_file || '',
-        default_invoice_memo: company.default_invoice_memo || ''  // 🆕 기본 메모 추가
+        default_invoice_memo: company.default_invoice_memo || '',  // 🆕 기본 메모 추가
+        document_font_path: company.document_font_path || ''
       })
     }
   }
@@ -309,6 +312,24 @@ export default function CompanyInfoSection({
                   placeholder="예: 입금계좌: 국민은행 123-456-789012 (예금주: 고기유통주식회사)&#10;거래 문의: 02-1234-5678"
                 />
               </div>
+
+              <div className="sm:col-span-2">
+                <label className="block text-sm font-medium text-gray-700">
+                  🔤 문서 글꼴 경로
+                </label>
+                <p className="text-xs text-gray-500 mt-1">
+                  거래명세서 PDF에 포함할 한글 TrueType 글꼴(.ttf) 파일 경로. 비워 두면 맑은 고딕/나눔고딕을 찾아 사용합니다.
+                </p>
+                <input
+                  type="text"
+                  name="document_font_path"
+                  value={formData.document_font_path}
+                  onChange={handleChange}
+                  disabled={!isEditing}
+                  className={`mt-1 block w-full border-gray-300 rounded-md shadow-sm focus:ring-blue-500 focus:border-blue-500 sm:text-sm ${!isEditing ? 'bg-gray-50' : ''}`}
+                  placeholder="예: C:\Windows\Fonts\malgun.ttf"
+                />
+              </div>
             </div>
             
             {isEditing && (
@@ -94,12 +94,7 @@ export default function StockMovementModal({ isOpen, onClose, product }: StockMo
           
           const deductQty = Math.min(remainingQty, lot.remaining_quantity)
           
-          // 로트에서 차감
-          await inventoryAPI.updateLot(lot.id!, {
-            remaining_quantity: lot.remaining_quantity - deductQty
-          })
-          
-          // 재고 이동 기록
+          // 재고 이동 기록 (로트 차감은 서버에서 함께 처리)
           await inventoryAPI.createMovement({
             ...data,
             quantity: deductQty,
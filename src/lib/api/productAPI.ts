@@ -123,5 +123,16 @@ export const productAPI = {
         return matchesActive && matchesCategory
       })
     }
+  },
+  
+  // 과세 구분 검토 대상: 과세이면서 세율 0인 상품 (면세/영세율 직접 지정 필요)
+  getForTaxReview: async () => {
+    if (isTauri()) {
+      return invoke<Product[]>('get_products_for_tax_review')
+    } else {
+      await delay(200)
+      const products = getFromStorage<Product[]>(STORAGE_KEYS.PRODUCTS, [])
+      return products.filter(p => (p.tax_category ?? 'taxable') === 'taxable' && p.tax_rate === 0)
+    }
   }
 }
@@ -187,6 +187,15 @@ export const transactionAPI = {
     }
   },
   
+  // 임시저장(draft) 거래의 품목 추가·수정·삭제 후 합계 재계산
+  updateItems: async (id: number, changes: { add?: any[]; update?: any[]; remove?: number[] }) => {
+    if (isTauri()) {
+      return invoke<TransactionWithItems>('update_transaction_items', { id, request: changes })
+    } else {
+      throw new Error('품목 수정은 데스크톱 앱에서만 가능합니다')
+    }
+  },
+  
   delete: async (id: number) => {
     if (isTauri()) {
       return invoke<void>('delete_transaction', { id })
@@ -11,7 +11,7 @@
  */
 
 import { getCurrentSession } from './auth/index'
-import type { Company, CustomerProductPrice, TaxInvoice, ProductInventory, StockMovement, StockLot, InventoryStats, TransactionWithItems } from '../types'
+import type { Company, ErrorResponse, CustomerProductPrice, TaxInvoice, Quotation, Order, ProductInventory, StockMovement, StockLot, InventoryStats, TransactionWithItems, PriceMode, TaxCategory, DatabaseInfo } from '../types'
 import { STORAGE_KEYS, getFromStorage, setToStorage, getNextId, delay, isTauri } from './api/helpers/storage'
 import { backupTrigger } from './api/helpers/backup'
 import { invoke } from '@tauri-apps/api/tauri'
@@ -21,6 +21,17 @@ export { customerAPI } from './api/customerAPI'
 export { productAPI } from './api/productAPI'
 export { transactionAPI, setInventoryAPI } from './api/transactionAPI'
 
+// Tauri 명령 에러(ErrorResponse) 판별 및 표시용 메시지 추출
+export const isErrorResponse = (error: unknown): error is ErrorResponse =>
+  typeof error === 'object' && error !== null && 'code' in error && 'error' in error
+
+export const getErrorMessage = (error: unknown, fallback = '처리 중 오류가 발생했습니다'): string => {
+  if (isErrorResponse(error)) return error.error
+  if (error instanceof Error) return error.message
+  if (typeof error === 'string') return error
+  return fallback
+}
+
 // 초기 데이터 설정 (회사별로 최초 실행 시에만)
 const initializeCompanyData = () => {
   const session = getCurrentSession()
@@ -90,10 +101,8 @@ export const customerProductPriceAPI = {
   
   setPrice: async (customerId: number, productId: number, price: number) => {
     if (isTauri()) {
-      return invoke<CustomerProductPrice>('set_customer_product_price', { 
-        customer_id: customerId, 
-        product_id: productId, 
-        price 
+      return invoke<CustomerProductPrice>('set_customer_product_price', {
+        request: { customer_id: customerId, product_id: productId, price }
       })
     } else {
       await delay(400)
@@ -129,22 +138,243 @@ export const customerProductPriceAPI = {
 
 // 세금계산서 API
 export const invoiceAPI = {
-  generate: async (transactionId: number) => {
+  issue: async (transactionId: number) => {
+    if (isTauri()) {
+      return invoke<TaxInvoice>('issue_tax_invoice', { transactionId })
+    } else {
+      throw new Error('세금계산서 발행은 데스크톱 앱에서만 가능합니다')
+    }
+  },
+  
+  getAll: async (status?: TaxInvoice['status'], startDate?: string, endDate?: string) => {
+    if (isTauri()) {
+      return invoke<TaxInvoice[]>('list_tax_invoices', { status, startDate, endDate })
+    } else {
+      await delay(300)
+      return []
+    }
+  },
+  
+  void: async (id: number, reason?: string) => {
+    if (isTauri()) {
+      return invoke<TaxInvoice>('void_tax_invoice', { id, reason })
+    } else {
+      throw new Error('세금계산서 취소는 데스크톱 앱에서만 가능합니다')
+    }
+  },
+  
+  // supplyAmount: 환입은 음수, 공급가액 변동은 증감액. 계약의 해제/이중발급은 생략 (잔액 전액 취소)
+  amend: async (originalInvoiceId: number, reason: NonNullable<TaxInvoice['amendment_reason']>, supplyAmount?: number, taxAmount?: number) => {
+    if (isTauri()) {
+      return invoke<TaxInvoice>('amend_tax_invoice', {
+        request: { original_invoice_id: originalInvoiceId, reason, supply_amount: supplyAmount, tax_amount: taxAmount }
+      })
+    } else {
+      throw new Error('수정세금계산서 발행은 데스크톱 앱에서만 가능합니다')
+    }
+  },
+  
+  getChain: async (id: number) => {
+    if (isTauri()) {
+      return invoke<TaxInvoice[]>('get_tax_invoice_chain', { id })
+    } else {
+      await delay(300)
+      return []
+    }
+  },
+  
+  markSent: async (id: number) => {
+    if (isTauri()) {
+      return invoke<TaxInvoice>('mark_invoice_sent', { id })
+    } else {
+      throw new Error('세금계산서 전송 처리는 데스크톱 앱에서만 가능합니다')
+    }
+  },
+  
+  // path는 저장 대화상자(@tauri-apps/api/dialog save)에서 선택한 경로
+  exportXml: async (id: number, path: string) => {
+    if (isTauri()) {
+      return invoke<void>('export_tax_invoice_xml', { id, path })
+    } else {
+      throw new Error('전자세금계산서 XML 내보내기는 데스크톱 앱에서만 가능합니다')
+    }
+  }
+}
+
+// 반품 API
+export const returnAPI = {
+  // 확정된 매출/매입의 품목 일부 반품: 음수 거래 생성, 재고·잔액 반영
+  create: async (transactionId: number, items: { transaction_item_id: number; quantity: number }[], returnDate?: string, notes?: string) => {
+    if (isTauri()) {
+      return invoke<TransactionWithItems>('create_return', { transactionId, items, returnDate, notes })
+    } else {
+      throw new Error('반품 처리는 데스크톱 앱에서만 가능합니다')
+    }
+  },
+  
+  getByTransaction: async (transactionId: number) => {
+    if (isTauri()) {
+      return invoke<TransactionWithItems[]>('get_transaction_returns', { transactionId })
+    } else {
+      await delay(200)
+      return []
+    }
+  }
+}
+
+// 견적서 API
+export interface CreateQuotationInput {
+  customer_id: number
+  quotation_date: string
+  valid_until: string
+  price_mode?: PriceMode
+  items: { product_id: number; quantity: number; unit_price?: number; tax_rate: number; tax_category?: TaxCategory; notes?: string }[]
+  notes?: string
+}
+
+export const quotationAPI = {
+  getAll: async (status?: Quotation['status'], customerId?: number) => {
+    if (isTauri()) {
+      return invoke<Quotation[]>('get_quotations', { status, customerId })
+    } else {
+      await delay(300)
+      return []
+    }
+  },
+  
+  getById: async (id: number) => {
     if (isTauri()) {
-      return invoke<string>('generate_tax_invoice', { transactionId })
+      return invoke<Quotation | null>('get_quotation_by_id', { id })
     } else {
-      await delay(1000)
-      return '/mock/invoice.pdf'
+      await delay(200)
+      return null
     }
   },
   
-  getAll: async () => {
+  create: async (request: CreateQuotationInput) => {
     if (isTauri()) {
-      return invoke<TaxInvoice[]>('get_tax_invoices')
+      return invoke<Quotation>('create_quotation', { request })
+    } else {
+      throw new Error('견적서 작성은 데스크톱 앱에서만 가능합니다')
+    }
+  },
+  
+  // draft → sent → accepted, draft/sent → rejected ('expired'는 일일 점검이 설정)
+  updateStatus: async (id: number, status: Quotation['status']) => {
+    if (isTauri()) {
+      return invoke<Quotation>('update_quotation_status', { id, status })
+    } else {
+      throw new Error('견적서 상태 변경은 데스크톱 앱에서만 가능합니다')
+    }
+  },
+  
+  delete: async (id: number) => {
+    if (isTauri()) {
+      return invoke<void>('delete_quotation', { id })
+    } else {
+      throw new Error('견적서 삭제는 데스크톱 앱에서만 가능합니다')
+    }
+  },
+  
+  // 견적 품목/단가로 임시저장 매출 거래 생성, 견적서는 accepted
+  convertToTransaction: async (id: number, transactionDate?: string) => {
+    if (isTauri()) {
+      return invoke<TransactionWithItems>('convert_quotation_to_transaction', { id, transactionDate })
+    } else {
+      throw new Error('견적서 전환은 데스크톱 앱에서만 가능합니다')
+    }
+  }
+}
+
+// 수주/발주 API
+export interface CreateOrderInput {
+  order_type: Order['order_type']
+  customer_id: number
+  order_date: string
+  expected_date?: string
+  price_mode?: PriceMode
+  items: { product_id: number; quantity: number; unit_price?: number; tax_rate: number; notes?: string }[]
+  notes?: string
+}
+
+export interface FulfilOrderInput {
+  order_type: Order['order_type']
+  order_id: number
+  transaction_date: string
+  due_date?: string
+  // 이번에 납품할 주문 품목과 수량 (미납 수량 이하)
+  items: { order_item_id: number; quantity: number; traceability_number?: string; origin?: string; slaughterhouse?: string; expiry_date?: string }[]
+  notes?: string
+}
+
+export const orderAPI = {
+  getAll: async (orderType: Order['order_type'], status?: Order['status'], customerId?: number) => {
+    if (isTauri()) {
+      return invoke<Order[]>('get_orders', { orderType, status, customerId })
     } else {
       await delay(300)
       return []
     }
+  },
+  
+  getById: async (orderType: Order['order_type'], id: number) => {
+    if (isTauri()) {
+      return invoke<Order | null>('get_order_by_id', { orderType, id })
+    } else {
+      await delay(200)
+      return null
+    }
+  },
+  
+  create: async (request: CreateOrderInput) => {
+    if (isTauri()) {
+      return invoke<Order>('create_order', { request })
+    } else {
+      throw new Error('주문 등록은 데스크톱 앱에서만 가능합니다')
+    }
+  },
+  
+  // 미납 수량 일부로 임시저장 매출/매입 거래 생성, 주문 상태는 자동 갱신
+  fulfil: async (request: FulfilOrderInput) => {
+    if (isTauri()) {
+      return invoke<TransactionWithItems>('fulfil_order', { request })
+    } else {
+      throw new Error('주문 납품 처리는 데스크톱 앱에서만 가능합니다')
+    }
+  },
+  
+  close: async (orderType: Order['order_type'], id: number) => {
+    if (isTauri()) {
+      return invoke<Order>('close_order', { orderType, id })
+    } else {
+      throw new Error('주문 종결은 데스크톱 앱에서만 가능합니다')
+    }
+  }
+}
+
+// 거래명세서 API
+export interface DeliveryNoteOptions {
+  template?: 'default' | 'striped'
+  show_balance?: boolean
+}
+
+export const documentAPI = {
+  // path는 저장 대화상자(@tauri-apps/api/dialog save)에서 선택한 경로
+  exportDeliveryNote: async (id: number, path: string, options?: DeliveryNoteOptions) => {
+    if (isTauri()) {
+      return invoke<void>('export_delivery_note', { id, path, options })
+    } else {
+      throw new Error('거래명세서 PDF 내보내기는 데스크톱 앱에서만 가능합니다')
+    }
+  },
+  
+  // 기간 내 매출/매입 거래마다 directory에 PDF 한 개씩 저장, 저장된 경로 목록 반환
+  exportDeliveryNotes: async (startDate: string, endDate: string, directory: string, options?: DeliveryNoteOptions) => {
+    if (isTauri()) {
+      return invoke<string[]>('export_delivery_notes', { startDate, endDate, directory, options })
+    } else {
+      throw new Error('거래명세서 일괄 내보내기는 데스크톱 앱에서만 가능합니다')
+    }
   }
 }
 
@@ -201,6 +431,17 @@ export const companyAPI = {
   }
 }
 
+// 데이터베이스 정보 API
+export const databaseAPI = {
+  getInfo: async () => {
+    if (isTauri()) {
+      return invoke<DatabaseInfo>('get_database_info')
+    } else {
+      throw new Error('데이터베이스 정보는 데스크톱 앱에서만 확인할 수 있습니다')
+    }
+  }
+}
+
 // 통계/보고서 API
 export const reportAPI = {
   getSalesSummary: async (startDate: string, endDate: string) => {
@@ -289,7 +530,7 @@ export const inventoryAPI = {
 
   updateInventory: async (inventoryData: Partial<ProductInventory> & { product_id: number }) => {
     if (isTauri()) {
-      return invoke<ProductInventory>('update_inventory', { data: inventoryData })
+      return invoke<ProductInventory>('update_inventory', { request: inventoryData })
     } else {
       await delay(400)
       const inventory = getFromStorage<ProductInventory[]>(STORAGE_KEYS.PRODUCT_INVENTORY, [])
@@ -321,7 +562,7 @@ export const inventoryAPI = {
 
   createMovement: async (movementData: Omit<StockMovement, 'id' | 'created_at'>) => {
     if (isTauri()) {
-      return invoke<StockMovement>('create_stock_movement', { data: movementData })
+      return invoke<StockMovement>('create_stock_movement', { request: movementData })
     } else {
       await delay(300)
       const movements = getFromStorage<StockMovement[]>(STORAGE_KEYS.STOCK_MOVEMENTS, [])
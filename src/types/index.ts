@@ -70,6 +70,12 @@ export interface Customer {
   updated_at?: string  // 🆕 추가
 }
 
+// 과세 구분: 과세 / 영세율 / 면세
+export type TaxCategory = 'taxable' | 'zero_rated' | 'exempt'
+
+// 단가 부가세: 별도 / 포함
+export type PriceMode = 'exclusive' | 'inclusive'
+
 export interface Product {
   id?: number
   name: string
@@ -77,11 +83,15 @@ export interface Product {
   category?: string
   unit: string
   unit_price?: number  // 선택사항으로 변경 (거래처별로 다르니까)
+  tax_rate?: number  // 0.1 = 10%
+  tax_category?: TaxCategory  // 기본 과세
   description?: string
   traceability_number?: string  // 🆕 기본 이력번호 추가
+  traceability_warning?: string  // 이력번호 형식 경고 (warn 모드에서 저장된 경우)
   origin?: string  // 🆕 원산지 (선택사항)
   slaughterhouse?: string  // 🆕 도축장 (선택사항)
   use_inventory_management?: boolean  // ✅ 재고 관리 사용 여부 추가
+  track_expiry?: boolean  // 유통기한 관리 (기본 true, false면 로트 없이 입고)
   is_active: boolean
   created_at?: string
   updated_at?: string  // 🆕 추가
@@ -94,7 +104,10 @@ export interface Transaction {
   transaction_type: 'sales' | 'purchase' | 'payment_in' | 'payment_out'  // payment_in: 수금, payment_out: 지급
   transaction_date: string
   due_date?: string
-  total_amount: number
+  price_mode?: PriceMode  // 단가 부가세 포함 여부 (기본 별도)
+  subtotal?: number  // 과세·영세율 공급가액
+  exempt_amount?: number  // 면세 금액
+  total_amount: number  // 공급가액 + 세액 + 면세 금액
   tax_amount: number
   notes?: string
   created_at?: string
@@ -104,6 +117,7 @@ export interface Transaction {
   is_displayed_in_invoice?: boolean  // 거래증에 표시되었는지
   displayed_in_transaction_id?: number  // 어느 거래에 표시되었는지
   current_balance?: number  // 현잔액 (매출: 미수금, 매입: 미지급금)
+  return_of_transaction_id?: number  // 반품 거래: 원거래 ID (수량·금액은 음수)
 }
 
 export interface TransactionItem {
@@ -116,6 +130,7 @@ export interface TransactionItem {
   unit_price: number // kg당 가격
   total_price: number // quantity * unit_price
   traceability_number?: string  // 이력번호 - 새로 추가!
+  traceability_warning?: string  // 이력번호 형식 경고 (warn 모드에서 저장된 경우)
   origin?: string  // 🆕 원산지 (선택사항)
   slaughterhouse?: string  // 🆕 도축장 (선택사항)
   notes?: string
@@ -150,15 +165,97 @@ export interface TaxInvoice {
   issue_date: string
   supplier_name: string
   supplier_business_number: string
-  customer_name: string
-  customer_business_number: string
+  buyer_name: string
+  buyer_business_number: string
+  supply_amount: number
   total_amount: number
   tax_amount: number
-  status: 'issued' | 'sent' | 'received'
+  status: 'issued' | 'sent' | 'received' | 'void'
   pdf_path?: string
+  sent_at?: string
+  voided_at?: string
+  void_reason?: string
+  original_invoice_id?: number  // 수정세금계산서의 당초 세금계산서
+  amendment_reason?: 'return' | 'price_change' | 'cancellation' | 'duplicate'
   created_at?: string
 }
 
+// 견적서
+export interface QuotationItem {
+  id?: number
+  quotation_id?: number
+  product_id: number
+  product?: Product
+  quantity: number
+  unit_price: number
+  tax_rate: number
+  tax_category: TaxCategory
+  subtotal: number
+  tax_amount: number
+  total_amount: number
+  notes?: string
+}
+
+export interface Quotation {
+  id?: number
+  quotation_number: string  // Q-20261017-0001
+  customer_id: number
+  customer?: Customer
+  quotation_date: string
+  valid_until: string  // YYYY-MM-DD, 이 날까지 유효
+  price_mode: PriceMode
+  subtotal: number
+  tax_amount: number
+  exempt_amount: number
+  total_amount: number
+  status: 'draft' | 'sent' | 'accepted' | 'expired' | 'rejected'
+  transaction_id?: number  // 전환된 매출 거래
+  notes?: string
+  items: QuotationItem[]
+  created_at?: string
+  updated_at?: string
+}
+
+// 수주(매출 주문) / 발주(매입 주문)
+export interface OrderItem {
+  id?: number
+  order_id?: number
+  product_id: number
+  product?: Product
+  quantity: number
+  delivered_quantity: number  // 취소되지 않은 거래로 납품된 수량
+  open_quantity?: number      // 미납 수량
+  unit_price: number
+  tax_rate: number
+  notes?: string
+}
+
+export interface Order {
+  id?: number
+  order_type: 'sale' | 'purchase'
+  order_number: string  // SO-20261017-0001 / PO-20261017-0001
+  customer_id: number
+  customer?: Customer
+  order_date: string
+  expected_date?: string
+  status: 'open' | 'partially_fulfilled' | 'fulfilled' | 'closed'
+  price_mode: PriceMode  // 납품 거래로 이어짐
+  notes?: string
+  items: OrderItem[]
+  created_at?: string
+  updated_at?: string
+}
+
+// 사용 중인 DB 파일과 연결 설정
+export interface DatabaseInfo {
+  path: string
+  source: 'cli' | 'env' | 'settings' | 'legacy' | 'default'  // 경로를 정한 곳: --db-path, SIMPLE_ERP_DB_PATH, settings.json, 이전 data/ 폴더, 앱 데이터 폴더
+  journal_mode: string  // 'wal'
+  foreign_keys: boolean
+  busy_timeout_ms: number
+  size_bytes: number
+}
+
 export interface Company {
   id?: number
   name: string
@@ -172,6 +269,7 @@ export interface Company {
   tax_invoice_api_key?: string
   tax_invoice_cert_file?: string
   default_invoice_memo?: string  // 🆕 기본 메모 추가
+  document_font_path?: string  // PDF 문서에 임베드할 TrueType 글꼴 경로 (비우면 시스템 한글 글꼴)
   created_at?: string
 }
 
@@ -252,6 +350,7 @@ export interface StockMovement {
   lot_number?: string             // 로트번호
   expiry_date?: string            // 유통기한
   traceability_number?: string   // 이력번호
+  traceability_warning?: string  // 이력번호 형식 경고 (warn 모드)
   origin?: string                 // 🆕 원산지 (선택사항)
   slaughterhouse?: string         // 🆕 도축장 (선택사항)
   transaction_id?: number         // 연결된 거래 ID
@@ -295,3 +394,28 @@ export interface DeleteAccountResult {
   success: boolean
   error?: string
 }
+
+// Tauri 명령 실패 시 전달되는 에러 (src-tauri/src/errors.rs ErrorResponse)
+export type ErrorCode =
+  | 'NOT_FOUND'
+  | 'VALIDATION_ERROR'
+  | 'BUSINESS_ERROR'
+  | 'EXTERNAL_ERROR'
+  | 'DATABASE_ERROR'
+  | 'UNIQUE_VIOLATION'
+  | 'CHECK_VIOLATION'
+  | 'FOREIGN_KEY_VIOLATION'
+  | 'NOT_NULL_VIOLATION'
+  | 'IO_ERROR'
+  | 'SERIALIZATION_ERROR'
+
+export interface ErrorResponse {
+  error: string                   // 표시용 메시지
+  code: ErrorCode
+  details?: {
+    field?: string                // 검증 실패 필드 (예: items[1].quantity)
+    columns?: string[]            // UNIQUE/NOT NULL 위반 컬럼 (예: products.code)
+    constraint?: string           // CHECK 위반 조건
+    message?: string
+  } | string | null
+}